@@ -0,0 +1,61 @@
+//! Overhead of the runner per iteration, measured on runs which do next to no work.
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+use trellis::bench::{runner, state};
+use trellis::State;
+
+const ITERATIONS: usize = 1000;
+
+fn state_update(c: &mut Criterion) {
+    c.bench_function("state update", |b| {
+        b.iter_batched(
+            state,
+            |mut state| {
+                state.increment_iteration();
+                state.update()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn observer_dispatch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("observer dispatch");
+    group.throughput(Throughput::Elements(ITERATIONS as u64));
+    for observers in [0, 1, 5] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(observers),
+            &observers,
+            |b, &observers| {
+                b.iter_batched(
+                    || runner(ITERATIONS, observers, false),
+                    |runner| runner.run(),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+fn killswitch_polling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("killswitch polling");
+    group.throughput(Throughput::Elements(ITERATIONS as u64));
+    for controller in [false, true] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(if controller { "controller" } else { "none" }),
+            &controller,
+            |b, &controller| {
+                b.iter_batched(
+                    || runner(ITERATIONS, 0, controller),
+                    |runner| runner.run(),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, state_update, observer_dispatch, killswitch_polling);
+criterion_main!(benches);
@@ -0,0 +1,399 @@
+//! Integrity records for the files a run produces.
+//!
+//! Writers record the size and SHA-256 hash of every artifact they finish, such as checkpoints,
+//! measure traces and reports, in an index file in the directory holding the artifact, along with
+//! the run which wrote it. [`verify`] later checks a directory against its index, detecting
+//! outputs which were truncated by a crash or modified after the run. [`gc`] uses the indexes to
+//! prune old runs, several of which may share a directory.
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use hifitime::Duration;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::writers::write_atomic;
+
+/// The name of the index file in a run directory
+pub const INDEX_FILE: &str = "trellis-index.json";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArtifactError {
+    #[error("error in IO operation {0}")]
+    Io(#[from] std::io::Error),
+    #[error("malformed index {0}")]
+    Index(#[from] serde_json::Error),
+}
+
+/// The recorded size and content hash of an artifact
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Artifact {
+    pub bytes: u64,
+    /// Hex encoded SHA-256 of the contents
+    pub sha256: String,
+    /// Identifier of the run which wrote the artifact, if it was known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+}
+
+/// The artifacts recorded in a directory, keyed by path relative to the directory
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunIndex {
+    pub artifacts: BTreeMap<String, Artifact>,
+    /// The final measure of each run which recorded one, keyed by run identifier
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub measures: BTreeMap<String, f64>,
+}
+
+impl RunIndex {
+    /// Read the index of `dir`, which is empty if nothing has been recorded there
+    pub fn read(dir: &Path) -> Result<Self, ArtifactError> {
+        match fs_err::read(dir.join(INDEX_FILE)) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// How an artifact differs from its record
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Damage {
+    Missing,
+    /// The artifact is shorter than recorded
+    Truncated {
+        recorded: u64,
+        found: u64,
+    },
+    /// The contents differ from those recorded
+    Modified,
+}
+
+/// An artifact which failed verification
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Damaged {
+    pub path: PathBuf,
+    pub damage: Damage,
+}
+
+fn hash(path: &Path) -> Result<Artifact, std::io::Error> {
+    let mut hasher = Sha256::new();
+    let bytes = std::io::copy(&mut fs_err::File::open(path)?, &mut hasher)?;
+    let sha256 = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    Ok(Artifact {
+        bytes,
+        sha256,
+        run_id: None,
+    })
+}
+
+// Serialises updates to index files, which are read, modified and replaced
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// Read, modify and replace the index of `dir`
+fn update_index(dir: &Path, update: impl FnOnce(&mut RunIndex)) -> Result<(), ArtifactError> {
+    let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut index = RunIndex::read(dir)?;
+    update(&mut index);
+    let json = serde_json::to_vec_pretty(&index)?;
+    write_atomic(&dir.join(INDEX_FILE), false, |f| f.write_all(&json))?;
+    Ok(())
+}
+
+/// Record the artifact at `path`, written by the run `run_id`, in the index of `dir`, replacing
+/// any earlier record
+pub(crate) fn record(dir: &Path, path: &Path, run_id: Option<&str>) -> Result<(), ArtifactError> {
+    let artifact = Artifact {
+        run_id: run_id.map(str::to_owned),
+        ..hash(path)?
+    };
+    let key = path
+        .strip_prefix(dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned();
+    update_index(dir, |index| {
+        index.artifacts.insert(key, artifact);
+    })
+}
+
+/// Record the final measure of the run `run_id` in the index of `dir`, for [`GcPolicy::keep_best`]
+pub(crate) fn record_measure(dir: &Path, run_id: &str, measure: f64) -> Result<(), ArtifactError> {
+    update_index(dir, |index| {
+        index.measures.insert(run_id.to_owned(), measure);
+    })
+}
+
+/// Check every artifact recorded in the index of `run_dir`, returning those which are damaged.
+///
+/// An empty result means every recorded artifact is intact. Files which were never recorded are
+/// not checked.
+pub fn verify(run_dir: impl AsRef<Path>) -> Result<Vec<Damaged>, ArtifactError> {
+    let run_dir = run_dir.as_ref();
+    let index = RunIndex::read(run_dir)?;
+    let mut damaged = vec![];
+    for (name, recorded) in index.artifacts {
+        let path = run_dir.join(name);
+        let damage = match hash(&path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Some(Damage::Missing),
+            Err(e) => return Err(e.into()),
+            Ok(found) if found.bytes < recorded.bytes => Some(Damage::Truncated {
+                recorded: recorded.bytes,
+                found: found.bytes,
+            }),
+            Ok(found) if found.sha256 != recorded.sha256 => Some(Damage::Modified),
+            Ok(_) => None,
+        };
+        if let Some(damage) = damage {
+            damaged.push(Damaged { path, damage });
+        }
+    }
+    Ok(damaged)
+}
+
+/// Which runs [`gc`] keeps.
+///
+/// A run is kept if any of the age and best measure rules keeps it, and pruned otherwise. With
+/// neither rule set every run is kept unless the size cap requires otherwise. The size cap then
+/// prunes the oldest remaining runs, other than those kept for their measure, until the total
+/// size of the runs fits.
+#[derive(Clone, Debug, Default)]
+pub struct GcPolicy {
+    max_age: Option<Duration>,
+    keep_best: Option<usize>,
+    max_total_bytes: Option<u64>,
+    dry_run: bool,
+}
+
+impl GcPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep runs whose artifacts were last modified within `age`
+    #[must_use]
+    pub fn max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// Keep the `count` runs with the lowest final measure, as recorded in the index by a
+    /// [`RunSummary`](crate::RunSummary)
+    #[must_use]
+    pub fn keep_best(mut self, count: usize) -> Self {
+        self.keep_best = Some(count);
+        self
+    }
+
+    /// Prune the oldest runs until the runs kept take up at most `bytes`
+    #[must_use]
+    pub fn max_total_bytes(mut self, bytes: u64) -> Self {
+        self.max_total_bytes = Some(bytes);
+        self
+    }
+
+    /// Report what would be pruned without deleting anything
+    #[must_use]
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+}
+
+/// A run found by [`gc`], identified by the directory holding its artifacts and its identifier
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexedRun {
+    pub dir: PathBuf,
+    /// The identifier of the run, which is unknown for artifacts recorded without one
+    pub run_id: Option<String>,
+}
+
+/// The outcome of [`gc`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GcReport {
+    /// Runs whose artifacts were deleted, or would be in a dry run
+    pub pruned: Vec<IndexedRun>,
+    /// Runs kept
+    pub kept: Vec<IndexedRun>,
+    /// The size of the artifacts of the pruned runs
+    pub freed_bytes: u64,
+}
+
+struct Run {
+    id: IndexedRun,
+    /// Paths of the artifacts of the run, relative to its directory
+    artifacts: Vec<String>,
+    updated: SystemTime,
+    measure: Option<f64>,
+    bytes: u64,
+}
+
+/// Split the index of `dir` into the runs which recorded artifacts there
+fn runs_in(dir: &Path) -> Result<Vec<Run>, ArtifactError> {
+    let indexed = fs_err::metadata(dir.join(INDEX_FILE))?.modified()?;
+    let index = RunIndex::read(dir)?;
+    let mut grouped: BTreeMap<Option<String>, Vec<String>> = BTreeMap::new();
+    for (name, artifact) in &index.artifacts {
+        grouped
+            .entry(artifact.run_id.clone())
+            .or_default()
+            .push(name.clone());
+    }
+
+    let mut runs = vec![];
+    for (run_id, artifacts) in grouped {
+        let mut updated = None;
+        let mut bytes = 0;
+        for name in &artifacts {
+            if let Ok(metadata) = fs_err::metadata(dir.join(name)) {
+                bytes += metadata.len();
+                updated = updated.max(metadata.modified().ok());
+            }
+        }
+        // Artifacts recorded before runs were identified can only have their measure read from
+        // the directory's `termination.json`
+        let measure = match run_id.as_ref() {
+            Some(run_id) => index.measures.get(run_id).copied(),
+            None => artifacts
+                .iter()
+                .any(|name| name == "termination.json")
+                .then(|| fs_err::read(dir.join("termination.json")).ok())
+                .flatten()
+                .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+                .and_then(|termination| termination.get("measure")?.as_f64()),
+        };
+        runs.push(Run {
+            id: IndexedRun {
+                dir: dir.to_owned(),
+                run_id,
+            },
+            artifacts,
+            updated: updated.unwrap_or(indexed),
+            measure,
+            bytes,
+        });
+    }
+    Ok(runs)
+}
+
+/// Delete the artifacts of `run`, and any directories below its directory this empties
+fn remove(run: &Run) -> Result<(), ArtifactError> {
+    for name in &run.artifacts {
+        let path = run.id.dir.join(name);
+        match fs_err::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        let mut parent = path.parent();
+        while let Some(dir) = parent.filter(|dir| *dir != run.id.dir) {
+            if fs_err::remove_dir(dir).is_err() {
+                break;
+            }
+            parent = dir.parent();
+        }
+    }
+    update_index(&run.id.dir, |index| {
+        for name in &run.artifacts {
+            index.artifacts.remove(name);
+        }
+        if let Some(run_id) = run.id.run_id.as_ref() {
+            index.measures.remove(run_id);
+        }
+    })
+}
+
+/// Prune the runs recorded below `root` according to `policy`.
+///
+/// Runs are found in the artifact indexes of the immediate subdirectories of `root`, and are told
+/// apart by the run identifier recorded with each artifact, so a directory shared by several
+/// runs, such as that of a [`FileWriter`](crate::FileWriter), is pruned run by run. Pruning a
+/// run deletes its recorded artifacts, and a directory all of whose runs are pruned is deleted
+/// along with any files in it which were never recorded. The age of a run is taken from when its
+/// artifacts were last modified. Runs which are still in progress should not be below `root`, as
+/// their artifacts may be deleted while they are written.
+pub fn gc(root: impl AsRef<Path>, policy: &GcPolicy) -> Result<GcReport, ArtifactError> {
+    let mut runs = vec![];
+    for entry in fs_err::read_dir(root.as_ref())? {
+        let dir = entry?.path();
+        if dir.join(INDEX_FILE).is_file() {
+            runs.extend(runs_in(&dir)?);
+        }
+    }
+
+    // Best measure first, with runs which recorded no measure last
+    runs.sort_by(|a, b| match (a.measure, b.measure) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+    let now = SystemTime::now();
+    let best = policy.keep_best.unwrap_or(0);
+    let mut keep: Vec<bool> = runs
+        .iter()
+        .enumerate()
+        .map(|(rank, run)| {
+            let recent = policy.max_age.map(|age| {
+                now.duration_since(run.updated)
+                    .is_ok_and(|since| Duration::from(since) <= age)
+            });
+            let among_best = policy
+                .keep_best
+                .map(|_| rank < best && run.measure.is_some());
+            match (recent, among_best) {
+                (None, None) => true,
+                (recent, among_best) => recent.unwrap_or(false) || among_best.unwrap_or(false),
+            }
+        })
+        .collect();
+
+    if let Some(cap) = policy.max_total_bytes {
+        let mut total: u64 = runs
+            .iter()
+            .zip(&keep)
+            .filter(|(_, kept)| **kept)
+            .map(|(run, _)| run.bytes)
+            .sum();
+        let mut oldest: Vec<usize> = (best.min(runs.len())..runs.len())
+            .filter(|index| keep[*index])
+            .collect();
+        oldest.sort_by_key(|index| runs[*index].updated);
+        for index in oldest {
+            if total <= cap {
+                break;
+            }
+            keep[index] = false;
+            total -= runs[index].bytes;
+        }
+    }
+
+    // Directories with no run left are deleted whole
+    let mut emptied: BTreeMap<PathBuf, bool> = BTreeMap::new();
+    for (run, kept) in runs.iter().zip(&keep) {
+        *emptied.entry(run.id.dir.clone()).or_insert(true) &= !kept;
+    }
+
+    let mut report = GcReport::default();
+    for (run, kept) in runs.into_iter().zip(keep) {
+        if kept {
+            report.kept.push(run.id);
+            continue;
+        }
+        if !policy.dry_run && !emptied[&run.id.dir] {
+            remove(&run)?;
+        }
+        report.freed_bytes += run.bytes;
+        report.pruned.push(run.id);
+    }
+    if !policy.dry_run {
+        for (dir, _) in emptied.into_iter().filter(|(_, emptied)| *emptied) {
+            fs_err::remove_dir_all(dir)?;
+        }
+    }
+    Ok(report)
+}
@@ -0,0 +1,53 @@
+//! Fixtures for measuring the overhead of the runner.
+//!
+//! The runs here iterate a map which does next to no work, so their time per iteration is
+//! spent almost entirely in the runner: updating the state, dispatching to observers and polling
+//! killswitches. The criterion benchmarks in `benches/runner.rs` are built on them, and run with
+//! `cargo bench --features bench`.
+use std::hint::black_box;
+
+use crate::solvers::{FixedPoint, FixedPointError, FixedPointState};
+use crate::{BoxedRunner, Cancellation, Frequency, GenerateBuilder, Observer, Stage, State};
+
+/// Halves its argument, converging on zero without ever reaching the tolerance of the runs here
+fn halve(x: &f64) -> f64 {
+    0.5 * x
+}
+
+/// An observer which reads the measure and nothing else
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Discard;
+
+impl<S: State> Observer<S> for Discard {
+    fn observe(&self, _ident: &'static str, subject: &S, _stage: Stage) {
+        black_box(subject.measure());
+    }
+}
+
+/// A fresh state for the benchmark runs, as the calculation would be handed it
+pub fn state() -> FixedPointState<f64> {
+    FixedPointState::new().with_param(1.0).tolerance(-1.0)
+}
+
+/// A run of exactly `iterations` iterations, dispatching every one to `observers` instances of
+/// [`Discard`], and polling the killswitch of a controller which never fires if `controller`
+pub fn runner(
+    iterations: usize,
+    observers: usize,
+    controller: bool,
+) -> BoxedRunner<FixedPointState<f64>, FixedPointError> {
+    let mut builder = FixedPoint::new(halve as fn(&f64) -> f64)
+        .build_with_state((), state().max_iterations(iterations));
+    for _ in 0..observers {
+        builder = builder.attach_observer(Discard, Frequency::Always);
+    }
+    if controller {
+        builder
+            .with_controller(Cancellation::new())
+            .finalise()
+            .unwrap()
+            .boxed()
+    } else {
+        builder.finalise().unwrap().boxed()
+    }
+}
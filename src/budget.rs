@@ -0,0 +1,82 @@
+//! Planning iteration budgets.
+//!
+//! Schedulers sizing jobs need to know how many iterations a calculation takes to reach a given
+//! tolerance. [`plan_from_history`] answers this from a recorded convergence curve, while
+//! [`plan_by_search`] binary searches over the iteration budget of a replayable calculation.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// One row of a budget-planning table
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BudgetRow {
+    /// The tolerance to be reached
+    pub tolerance: f64,
+    /// The minimum number of iterations needed to reach it, `None` if it was never reached
+    pub iterations: Option<usize>,
+}
+
+/// Find the budget needed to reach each tolerance from a recorded `(iteration, measure)` curve.
+///
+/// A tolerance is reached at the first iteration whose best measure so far is at or below it.
+pub fn plan_from_history(points: &[(usize, f64)], tolerances: &[f64]) -> Vec<BudgetRow> {
+    let mut best = f64::INFINITY;
+    let best_so_far = points
+        .iter()
+        .map(|&(iteration, measure)| {
+            best = best.min(measure);
+            (iteration, best)
+        })
+        .collect::<Vec<_>>();
+
+    tolerances
+        .iter()
+        .map(|&tolerance| {
+            // The best measure never increases, so the curve is partitioned by the tolerance
+            let index = best_so_far.partition_point(|&(_, best)| best > tolerance);
+            BudgetRow {
+                tolerance,
+                iterations: best_so_far.get(index).map(|&(iteration, _)| iteration),
+            }
+        })
+        .collect()
+}
+
+/// Find the budget needed to reach each tolerance by replaying a calculation.
+///
+/// `best_after` runs the calculation with the given maximum number of iterations and returns the
+/// best measure it reached. Budgets up to `max_budget` are binary searched, and each budget is
+/// replayed at most once however many tolerances are requested. The best measure is assumed not
+/// to get worse as the budget grows, which holds for any deterministic calculation.
+pub fn plan_by_search<F>(mut best_after: F, max_budget: usize, tolerances: &[f64]) -> Vec<BudgetRow>
+where
+    F: FnMut(usize) -> f64,
+{
+    let mut replayed = BTreeMap::new();
+    let mut best_with =
+        |budget: usize| *replayed.entry(budget).or_insert_with(|| best_after(budget));
+
+    tolerances
+        .iter()
+        .map(|&tolerance| {
+            let iterations = if best_with(max_budget) <= tolerance {
+                let (mut low, mut high) = (0, max_budget);
+                while low < high {
+                    let mid = low + (high - low) / 2;
+                    if best_with(mid) <= tolerance {
+                        high = mid;
+                    } else {
+                        low = mid + 1;
+                    }
+                }
+                Some(low)
+            } else {
+                None
+            };
+            BudgetRow {
+                tolerance,
+                iterations,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,140 @@
+//! A cache shared by related runs, so expensive setup is done once per sweep rather than once per
+//! run.
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex, OnceLock,
+};
+
+type Slot = Arc<OnceLock<Arc<dyn Any + Send + Sync>>>;
+
+// Entries are keyed by the types of key and value and the hash of the key, with the key kept
+// alongside to tell colliding keys apart
+type Entries = HashMap<(TypeId, TypeId, u64), Vec<(Box<dyn Any + Send + Sync>, Slot)>>;
+
+/// How often a [`WarmCache`] was able to return a value it already held
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// Lookups which found a value built earlier
+    pub hits: usize,
+    /// Lookups which had to build the value
+    pub misses: usize,
+    /// The number of values held
+    pub entries: usize,
+}
+
+impl CacheStats {
+    /// The fraction of lookups which found a value built earlier
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: Mutex<Entries>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+/// A thread-safe cache of values, such as factorisations or meshes, shared between runs.
+///
+/// Values are keyed by any hashable key and built on first use; every later lookup of the same
+/// key and value type returns the same value. When several runs look up a missing key at once
+/// only one builds it while the others wait. The cache is a cheap handle: clones share the same
+/// values, so a [`RunQueue`](crate::RunQueue) hands a clone to each job through
+/// [`RunQueue::cache`](crate::RunQueue::cache), and a job can pass it on to the calculation with
+/// [`Builder::with_cache`](crate::Builder::with_cache).
+#[derive(Clone, Default)]
+pub struct WarmCache(Arc<Inner>);
+
+impl WarmCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The value for `key`, building it with `build` if the cache does not hold it yet
+    pub fn get_or_insert_with<K, V>(&self, key: K, build: impl FnOnce() -> V) -> Arc<V>
+    where
+        K: Hash + Eq + Send + Sync + 'static,
+        V: Send + Sync + 'static,
+    {
+        let slot = self.slot::<K, V>(key);
+        let mut built = false;
+        let value = slot.get_or_init(|| {
+            built = true;
+            Arc::new(build())
+        });
+        let counter = if built { &self.0.misses } else { &self.0.hits };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+            .clone()
+            .downcast()
+            .expect("cache slots are keyed by the type of their value")
+    }
+
+    /// The value for `key`, if the cache holds one.
+    ///
+    /// Lookups with `get` are not counted in the statistics.
+    pub fn get<K, V>(&self, key: &K) -> Option<Arc<V>>
+    where
+        K: Hash + Eq + Send + Sync + 'static,
+        V: Send + Sync + 'static,
+    {
+        let entries = self.0.entries.lock().unwrap();
+        entries
+            .get(&Self::id::<K, V>(key))?
+            .iter()
+            .find(|(other, _)| other.downcast_ref::<K>() == Some(key))
+            .and_then(|(_, slot)| slot.get())
+            .and_then(|value| value.clone().downcast().ok())
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.0.hits.load(Ordering::Relaxed),
+            misses: self.0.misses.load(Ordering::Relaxed),
+            entries: self.0.entries.lock().unwrap().values().map(Vec::len).sum(),
+        }
+    }
+
+    fn id<K: Hash + 'static, V: 'static>(key: &K) -> (TypeId, TypeId, u64) {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (TypeId::of::<K>(), TypeId::of::<V>(), hasher.finish())
+    }
+
+    // The slot for `key`, inserting an empty one if there is none. The lock is released before
+    // the value is built, so building one value does not hold up lookups of others.
+    fn slot<K, V>(&self, key: K) -> Slot
+    where
+        K: Hash + Eq + Send + Sync + 'static,
+        V: 'static,
+    {
+        let mut entries = self.0.entries.lock().unwrap();
+        let bucket = entries.entry(Self::id::<K, V>(&key)).or_default();
+        if let Some((_, slot)) = bucket
+            .iter()
+            .find(|(other, _)| other.downcast_ref::<K>() == Some(&key))
+        {
+            return slot.clone();
+        }
+        let slot = Slot::default();
+        bucket.push((Box::new(key), slot.clone()));
+        slot
+    }
+}
+
+impl fmt::Debug for WarmCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WarmCache").field(&self.stats()).finish()
+    }
+}
@@ -1,4 +1,10 @@
-use crate::Problem;
+use crate::{Grade, Problem, State, KV};
+
+/// The floating point type of the state `S`.
+///
+/// Shorthand for `<S as State>::Float` in calculations generic over their state, for example
+/// `fn step_size(&self) -> FloatOf<S>`.
+pub type FloatOf<S> = <S as State>::Float;
 
 /// Trait implemented by all problems solved by `Trellis`
 pub trait Calculation<P, S> {
@@ -13,13 +19,44 @@ pub trait Calculation<P, S> {
     type Output;
 
     const NAME: &'static str;
+    /// Version of the algorithm.
+    ///
+    /// This is recorded in the run metadata, and should change whenever a change to the
+    /// calculation makes data recorded by earlier runs unusable.
+    const VERSION: &'static str = "0.0.0";
+    /// The name observers are notified under.
+    ///
+    /// Defaults to [`Calculation::NAME`]. Calculations made of several stages, such as a
+    /// [`Pipeline`](crate::Pipeline), give the name of the stage in progress.
+    fn ident(&self) -> &'static str {
+        Self::NAME
+    }
     /// Initialisation.
     ///
     /// This step prepares the state object for the main calculation loop.
     fn initialise(&mut self, problem: &mut Problem<P>, state: S) -> Result<S, Self::Error>;
     /// One iteration of the core algorithm
     fn next(&mut self, problem: &mut Problem<P>, state: S) -> Result<S, Self::Error>;
+    /// Values describing the calculation's internals at `state`, such as a step size or the
+    /// components of the residual.
+    ///
+    /// Called at initialisation and after every iteration. Observers see the values alongside
+    /// those from [`State::kv`], if the state holds [`Extensions`](crate::Extensions). The
+    /// default reports nothing.
+    fn report(&self, _state: &S) -> KV {
+        KV::new()
+    }
     /// Converts the internal state to the return datatype
     fn finalise(&mut self, problem: &mut Problem<P>, state: S)
         -> Result<Self::Output, Self::Error>;
+    /// Assess the quality of the final state.
+    ///
+    /// The default grades from the termination reason and the finiteness of the measure.
+    /// Calculations which know their requested tolerance can use [`Grade::from_tolerance`].
+    fn grade(&self, state: &S) -> Grade
+    where
+        S: State,
+    {
+        Grade::from_state(state)
+    }
 }
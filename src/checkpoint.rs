@@ -0,0 +1,330 @@
+//! A compact, versioned binary format for checkpointing the state of a run.
+//!
+//! A checkpoint is a header followed by the state encoded with `bincode`, optionally compressed.
+//! The header holds a magic string, the version of the format, a hash of the schema of the state
+//! type, the compression of the payload and the name and version of the calculation which wrote
+//! it. Resuming with a state which has different fields, or with a different calculation or
+//! version of it, fails with a [`CheckpointError`] naming the problem instead of decoding
+//! garbage. With the `encryption` feature the payload can also be encrypted, leaving only the
+//! header readable. Checkpoints are written during a run by the
+//! [`Checkpointer`](crate::Checkpointer) observer and loaded with
+//! [`Builder::resume_from_checkpoint`](crate::Builder::resume_from_checkpoint).
+use std::io::Write;
+use std::path::Path;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    metadata::{self, MetadataError},
+    writers::{write_atomic, Compression},
+    State,
+};
+
+const MAGIC: &[u8; 8] = b"TRLSCKPT";
+
+/// The version of the checkpoint format written by this build.
+///
+/// Version 2 added the compression of the payload to the header, and version 3 the calculation
+/// which wrote the checkpoint. Earlier versions can still be read, but the calculation they were
+/// written by cannot be checked.
+pub const FORMAT_VERSION: u16 = 3;
+
+const HEADER_LEN: usize = MAGIC.len() + 2 + 8;
+
+// Set in the compression byte when the payload is encrypted
+const ENCRYPTED: u8 = 0x80;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CheckpointError {
+    #[error("not a trellis checkpoint")]
+    NotACheckpoint,
+    #[error(
+        "checkpoint format version {found} is not supported, this build reads version {supported}"
+    )]
+    UnsupportedVersion { found: u16, supported: u16 },
+    #[error("checkpoint was written for a different definition of {state} (schema {recorded:016x}, this build {current:016x})")]
+    SchemaMismatch {
+        state: &'static str,
+        recorded: u64,
+        current: u64,
+    },
+    #[error("checkpoint is compressed with a method this build does not support ({0})")]
+    UnsupportedCompression(u8),
+    #[error("checkpoint is encrypted, load it with its key")]
+    Encrypted,
+    #[error("checkpoint cannot be resumed: {0}")]
+    Incompatible(#[from] MetadataError),
+    #[cfg(feature = "encryption")]
+    #[error("error decrypting checkpoint {0}")]
+    Encryption(#[from] crate::EncryptionError),
+    #[error("error in serde bincode {0}")]
+    Bincode(#[from] Box<bincode::ErrorKind>),
+    #[error("error describing the state schema {0}")]
+    Schema(#[from] serde_json::Error),
+    #[error("error in IO operation {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A hash of the schema of the state type `S`.
+///
+/// The schema is the serialised shape of a freshly constructed state: the names of its fields,
+/// recursively, and the kind of every value. Adding, removing, renaming or retyping a field
+/// changes the hash, but changes in parameters which are empty in a new state are not detected.
+/// Only states with a [`Default`] can be checkpointed, as the schema is read from it.
+/// As the hash depends only on what serde writes, it is the same for every build of the state.
+pub fn schema_hash<S: State + Serialize + Default>() -> Result<u64, CheckpointError> {
+    let mut schema = String::new();
+    describe(&serde_json::to_value(S::new())?, &mut schema);
+    Ok(fnv1a(schema.as_bytes()))
+}
+
+// Checkpoints before version 3 also hashed `std::any::type_name`, which is not guaranteed to be
+// the same between compiler releases
+fn legacy_schema_hash<S: State + Serialize + Default>() -> Result<u64, CheckpointError> {
+    let mut schema = std::any::type_name::<S>().to_owned();
+    describe(&serde_json::to_value(S::new())?, &mut schema);
+    Ok(fnv1a(schema.as_bytes()))
+}
+
+fn describe(value: &serde_json::Value, out: &mut String) {
+    use serde_json::Value;
+    match value {
+        Value::Null => out.push('n'),
+        Value::Bool(_) => out.push('b'),
+        Value::Number(_) => out.push('#'),
+        Value::String(_) => out.push('s'),
+        Value::Array(items) => {
+            out.push('[');
+            if let Some(first) = items.first() {
+                describe(first, out);
+            }
+            out.push(']');
+        }
+        Value::Object(fields) => {
+            out.push('{');
+            for (name, field) in fields {
+                out.push_str(name);
+                out.push(':');
+                describe(field, out);
+                out.push(',');
+            }
+            out.push('}');
+        }
+    }
+}
+
+// FNV-1a, which unlike the standard library hasher is fixed across releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn compress<S: State + Serialize>(
+    state: &S,
+    compression: Compression,
+) -> Result<Vec<u8>, CheckpointError> {
+    let run_id = state.run_id().map(ToString::to_string);
+    Ok(compression.compress_for(run_id.as_deref(), &bincode::serialize(state)?)?)
+}
+
+fn header<S: State + Serialize + Default>(
+    calculation: (&str, &str),
+    tag: u8,
+) -> Result<Vec<u8>, CheckpointError> {
+    let mut out = MAGIC.to_vec();
+    out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    out.extend_from_slice(&schema_hash::<S>()?.to_le_bytes());
+    out.push(tag);
+    let (calculation, version) = calculation;
+    for field in [calculation, version] {
+        let len = u16::try_from(field.len()).unwrap_or(u16::MAX);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&field.as_bytes()[..usize::from(len)]);
+    }
+    Ok(out)
+}
+
+/// Split a length prefixed string from the front of `bytes`
+fn take_str(bytes: &[u8]) -> Result<(&str, &[u8]), CheckpointError> {
+    if bytes.len() < 2 {
+        return Err(CheckpointError::NotACheckpoint);
+    }
+    let (len, rest) = bytes.split_at(2);
+    let len = usize::from(u16::from_le_bytes(len.try_into().unwrap()));
+    if rest.len() < len {
+        return Err(CheckpointError::NotACheckpoint);
+    }
+    let (field, rest) = rest.split_at(len);
+    let field = std::str::from_utf8(field).map_err(|_| CheckpointError::NotACheckpoint)?;
+    Ok((field, rest))
+}
+
+/// Encode a state as a checkpoint.
+///
+/// `calculation` is the name and version of the calculation which produced the state. An empty
+/// name records that it is unknown, and the checkpoint is then resumed without checking it.
+pub fn encode<S: State + Serialize + Default>(
+    state: &S,
+    calculation: (&str, &str),
+    compression: Compression,
+) -> Result<Vec<u8>, CheckpointError> {
+    let mut out = header::<S>(calculation, compression.tag())?;
+    out.extend_from_slice(&compress(state, compression)?);
+    Ok(out)
+}
+
+/// Encode a state as a checkpoint whose payload is encrypted with `key`
+#[cfg(feature = "encryption")]
+pub fn encode_encrypted<S: State + Serialize + Default>(
+    state: &S,
+    calculation: (&str, &str),
+    compression: Compression,
+    key: &crate::EncryptionKey,
+) -> Result<Vec<u8>, CheckpointError> {
+    let mut out = header::<S>(calculation, compression.tag() | ENCRYPTED)?;
+    let payload = compress(state, compression)?;
+    out.extend_from_slice(&crate::writers::encrypt(key, &payload));
+    Ok(out)
+}
+
+/// Decode a checkpoint, checking it was written by a compatible build.
+///
+/// `calculation` is the name and version of the calculation which is to continue from the
+/// checkpoint, which must match those recorded in it.
+pub fn decode<S: State + Serialize + Default + DeserializeOwned>(
+    bytes: &[u8],
+    calculation: (&str, &str),
+) -> Result<S, CheckpointError> {
+    open(bytes, calculation, |_| Err(CheckpointError::Encrypted))
+}
+
+/// Decode a checkpoint which may be encrypted with `key`
+#[cfg(feature = "encryption")]
+pub fn decode_encrypted<S: State + Serialize + Default + DeserializeOwned>(
+    bytes: &[u8],
+    calculation: (&str, &str),
+    key: &crate::EncryptionKey,
+) -> Result<S, CheckpointError> {
+    open(bytes, calculation, |payload| {
+        Ok(crate::decrypt(key, payload)?)
+    })
+}
+
+fn open<S: State + Serialize + Default + DeserializeOwned>(
+    bytes: &[u8],
+    calculation: (&str, &str),
+    decrypt: impl FnOnce(&[u8]) -> Result<Vec<u8>, CheckpointError>,
+) -> Result<S, CheckpointError> {
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(CheckpointError::NotACheckpoint);
+    }
+    let (version, rest) = bytes[MAGIC.len()..].split_at(2);
+    let version = u16::from_le_bytes(version.try_into().unwrap());
+    if version == 0 || version > FORMAT_VERSION {
+        return Err(CheckpointError::UnsupportedVersion {
+            found: version,
+            supported: FORMAT_VERSION,
+        });
+    }
+    let (recorded, payload) = rest.split_at(8);
+    let recorded = u64::from_le_bytes(recorded.try_into().unwrap());
+    let current = if version < 3 {
+        legacy_schema_hash::<S>()?
+    } else {
+        schema_hash::<S>()?
+    };
+    if recorded != current {
+        return Err(CheckpointError::SchemaMismatch {
+            state: std::any::type_name::<S>(),
+            recorded,
+            current,
+        });
+    }
+    if version == 1 {
+        return Ok(bincode::deserialize(payload)?);
+    }
+    let (&tag, payload) = payload
+        .split_first()
+        .ok_or(CheckpointError::NotACheckpoint)?;
+    let compression = Compression::from_tag(tag & !ENCRYPTED)
+        .ok_or(CheckpointError::UnsupportedCompression(tag))?;
+    let payload = if version < 3 {
+        tracing::warn!(
+            version,
+            "checkpoint does not record the calculation which wrote it, so it cannot be checked"
+        );
+        payload
+    } else {
+        let (name, rest) = take_str(payload)?;
+        let (recorded_version, rest) = take_str(rest)?;
+        if name.is_empty() {
+            tracing::warn!(
+                "checkpoint does not record the calculation which wrote it, so it cannot be checked"
+            );
+        } else {
+            metadata::ensure_compatible((name, recorded_version), calculation)?;
+        }
+        rest
+    };
+    if tag & ENCRYPTED != 0 {
+        let payload = decrypt(payload)?;
+        return Ok(bincode::deserialize(&compression.decompress(&payload)?)?);
+    }
+    Ok(bincode::deserialize(&compression.decompress(payload)?)?)
+}
+
+/// Write a state to a checkpoint file.
+///
+/// The file is replaced atomically, so an interrupted write leaves the previous checkpoint
+/// intact. If `sync` is set the checkpoint is flushed to disk before the call returns.
+pub fn save<S: State + Serialize + Default>(
+    state: &S,
+    path: &Path,
+    calculation: (&str, &str),
+    compression: Compression,
+    sync: bool,
+) -> Result<(), CheckpointError> {
+    write(&encode(state, calculation, compression)?, path, sync)
+}
+
+/// Write a state to a checkpoint file, encrypting the payload with `key`
+#[cfg(feature = "encryption")]
+pub fn save_encrypted<S: State + Serialize + Default>(
+    state: &S,
+    path: &Path,
+    calculation: (&str, &str),
+    compression: Compression,
+    key: &crate::EncryptionKey,
+    sync: bool,
+) -> Result<(), CheckpointError> {
+    write(
+        &encode_encrypted(state, calculation, compression, key)?,
+        path,
+        sync,
+    )
+}
+
+fn write(bytes: &[u8], path: &Path, sync: bool) -> Result<(), CheckpointError> {
+    write_atomic(path, sync, |f| {
+        f.write_all(bytes).map_err(CheckpointError::from)
+    })
+}
+
+/// Read a state from a checkpoint file, to be continued by `calculation`
+pub fn load<S: State + Serialize + Default + DeserializeOwned>(
+    path: &Path,
+    calculation: (&str, &str),
+) -> Result<S, CheckpointError> {
+    decode(&fs_err::read(path)?, calculation)
+}
+
+/// Read a state from a checkpoint file which may be encrypted with `key`
+#[cfg(feature = "encryption")]
+pub fn load_encrypted<S: State + Serialize + Default + DeserializeOwned>(
+    path: &Path,
+    calculation: (&str, &str),
+    key: &crate::EncryptionKey,
+) -> Result<S, CheckpointError> {
+    decode_encrypted(&fs_err::read(path)?, calculation, key)
+}
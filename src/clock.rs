@@ -0,0 +1,125 @@
+//! Sources of time for the runner.
+//!
+//! The runner reads the time through a [`Clock`], set with
+//! [`Builder::with_clock`](crate::Builder::with_clock), for timestamps, elapsed times and the
+//! resource ledger. Runs use the [`SystemClock`] unless told otherwise, while tests of timeouts
+//! and duration reporting, and deterministic replays, can drive a [`MockClock`] by hand.
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use hifitime::Epoch;
+
+/// A source of monotonic and wall-clock time
+pub trait Clock: Send + Sync {
+    /// Time since an arbitrary origin, which never goes backwards
+    fn monotonic(&self) -> Duration;
+
+    /// The current wall-clock time
+    fn wall(&self) -> SystemTime;
+
+    /// The current wall-clock time as a hifitime epoch
+    fn epoch(&self) -> Epoch {
+        let seconds = match self.wall().duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs_f64(),
+            Err(before) => -before.duration().as_secs_f64(),
+        };
+        Epoch::from_unix_seconds(seconds)
+    }
+}
+
+/// The real time, as read from the operating system
+#[derive(Copy, Clone, Debug)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn monotonic(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    fn wall(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+#[derive(Debug)]
+struct Readings {
+    elapsed: Duration,
+    start: SystemTime,
+    tick: Duration,
+}
+
+/// A clock which only moves when told to.
+///
+/// Clones share the same time, so a test can keep one clone and advance it while the runner
+/// reads another. With [`MockClock::ticking`] every reading also advances the clock, giving each
+/// step of a run a distinct, reproducible time.
+#[derive(Clone, Debug)]
+pub struct MockClock(Arc<Mutex<Readings>>);
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::starting_at(UNIX_EPOCH)
+    }
+}
+
+impl MockClock {
+    /// A stopped clock, whose wall-clock time starts at the Unix epoch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A stopped clock whose wall-clock time starts at `start`
+    pub fn starting_at(start: SystemTime) -> Self {
+        Self(Arc::new(Mutex::new(Readings {
+            elapsed: Duration::ZERO,
+            start,
+            tick: Duration::ZERO,
+        })))
+    }
+
+    /// Advance the clock by `tick` after every reading
+    #[must_use]
+    pub fn ticking(self, tick: Duration) -> Self {
+        self.0.lock().unwrap().tick = tick;
+        self
+    }
+
+    /// Move the clock forward by `by`
+    pub fn advance(&self, by: Duration) {
+        self.0.lock().unwrap().elapsed += by;
+    }
+
+    /// The time the clock has moved since it was created
+    pub fn elapsed(&self) -> Duration {
+        self.0.lock().unwrap().elapsed
+    }
+
+    fn read(&self) -> Duration {
+        let mut readings = self.0.lock().unwrap();
+        let elapsed = readings.elapsed;
+        let tick = readings.tick;
+        readings.elapsed += tick;
+        elapsed
+    }
+}
+
+impl Clock for MockClock {
+    fn monotonic(&self) -> Duration {
+        self.read()
+    }
+
+    fn wall(&self) -> SystemTime {
+        let readings = self.0.lock().unwrap();
+        readings.start + readings.elapsed
+    }
+}
@@ -1,5 +1,8 @@
 //! Controllers are external processes which can kill the main loop.
 
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
 
 /// A controller has to implement the `Control` trait
@@ -26,6 +29,110 @@ where
     Ok(())
 }
 
+type Spawn = Box<dyn FnOnce(Arc<AtomicBool>) -> Result<(), std::io::Error>>;
+
+/// A controller of erased type, so controllers of different types can be held together
+pub(crate) struct Spawner(Spawn);
+
+impl Spawner {
+    pub(crate) fn new<R: Control + 'static>(controller: R) -> Self {
+        Self(Box::new(move |killed: Arc<AtomicBool>| {
+            set_handler(controller, move || killed.store(true, Ordering::SeqCst))
+        }))
+    }
+
+    /// Start waiting on the controller, returning the flag set when it sends a kill signal
+    pub(crate) fn spawn(self) -> Result<Arc<AtomicBool>, std::io::Error> {
+        let killed = Arc::new(AtomicBool::new(false));
+        (self.0)(killed.clone())?;
+        Ok(killed)
+    }
+}
+
+/// A cheap handle for checking whether a run has been told to stop.
+///
+/// The runner only checks its kill signals between iterations, so a calculation whose iterations
+/// run for a long time can take one from [`Problem::cancel_checker`](crate::Problem::cancel_checker)
+/// and poll it inside its inner loops, returning early once the run is cancelled. Checking is a
+/// handful of atomic loads, and clones observe the same signals.
+///
+/// ```ignore
+/// fn next(&mut self, problem: &mut Problem<P>, mut state: S) -> Result<S, Self::Error> {
+///     let cancel = problem.cancel_checker().clone();
+///     for sample in 0..self.samples {
+///         if cancel.is_cancelled() {
+///             break;
+///         }
+///         ..
+///     }
+///     ..
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancelChecker(Arc<[Arc<AtomicBool>]>);
+
+impl CancelChecker {
+    pub(crate) fn new(signals: Vec<Arc<AtomicBool>>) -> Self {
+        Self(signals.into())
+    }
+
+    /// Whether a kill signal has been received by any controller of the run
+    pub fn is_cancelled(&self) -> bool {
+        self.0.iter().any(|signal| signal.load(Ordering::SeqCst))
+    }
+}
+
+#[derive(Default)]
+struct CancellationState {
+    cancelled: bool,
+    released: bool,
+}
+
+/// A cancellation signal which can be shared between many runs.
+///
+/// Every clone observes the same signal, so a single call to [`Cancellation::cancel`] stops all
+/// runs which were given a clone as their controller.
+#[derive(Clone, Default)]
+pub struct Cancellation(Arc<(Mutex<CancellationState>, Condvar)>);
+
+impl Cancellation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Signal every run sharing this cancellation to stop
+    pub fn cancel(&self) {
+        let (state, condvar) = &*self.0;
+        state.lock().unwrap().cancelled = true;
+        condvar.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0 .0.lock().unwrap().cancelled
+    }
+
+    // Wake the threads waiting on the signal once no run can observe it any more
+    pub(crate) fn release(&self) {
+        let (state, condvar) = &*self.0;
+        state.lock().unwrap().released = true;
+        condvar.notify_all();
+    }
+}
+
+impl Control for Cancellation {
+    type Value = ();
+    type Error = Infallible;
+    fn blocking_recv_kill_signal(self) -> Result<Self::Value, Self::Error> {
+        let (state, condvar) = &*self.0;
+        let _state = condvar
+            .wait_while(state.lock().unwrap(), |state| {
+                !(state.cancelled || state.released)
+            })
+            .unwrap();
+        Ok(())
+    }
+}
+
 #[cfg(feature = "tokio")]
 impl<M> Control for tokio::sync::oneshot::Receiver<M>
 where
@@ -0,0 +1,108 @@
+//! Estimating the rate of convergence of a run.
+//!
+//! For errors `e_k` converging with order `q` and contraction factor `C`, successive errors
+//! satisfy `e_{k+1} ≈ C e_k^q`. Both are estimated from the tail of the error history, which
+//! makes the report useful when developing algorithms and as a regression check that a method
+//! still converges as fast as it should.
+use serde::{Deserialize, Serialize};
+
+/// How many of the most recent estimates are combined into the report
+const WINDOW: usize = 5;
+
+/// The asymptotic behaviour of a converging sequence
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConvergenceOrder {
+    /// The error ratio tends to one, as in a stagnating run
+    Sublinear,
+    /// The error shrinks by a constant factor every iteration
+    Linear,
+    /// Faster than linear, but slower than quadratic
+    Superlinear,
+    /// The number of correct digits roughly doubles every iteration
+    Quadratic,
+}
+
+/// Estimated rate of convergence
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConvergenceReport {
+    /// The estimated order `q`
+    pub order: f64,
+    /// The estimated contraction factor `C`
+    pub contraction: f64,
+    /// Classification of the estimated order
+    pub classification: ConvergenceOrder,
+    /// The number of estimates the report was formed from
+    pub samples: usize,
+}
+
+impl ConvergenceReport {
+    /// Estimate the rate of convergence from a history of errors.
+    ///
+    /// Only the final run of strictly decreasing, positive and finite errors is used, and the
+    /// median is taken over the last few estimates to damp noise. Returns `None` when fewer than
+    /// three such errors are available.
+    pub fn estimate(errors: &[f64]) -> Option<Self> {
+        let start = errors
+            .windows(2)
+            .rposition(|pair| !(pair[1] < pair[0] && is_usable(pair[0]) && is_usable(pair[1])))
+            .map_or(0, |position| position + 1);
+        let tail = &errors[start..];
+        if tail.len() < 3 {
+            return None;
+        }
+
+        let (orders, ratios): (Vec<f64>, Vec<f64>) = tail
+            .windows(3)
+            .rev()
+            .take(WINDOW)
+            .map(|e| {
+                let order = (e[2] / e[1]).ln() / (e[1] / e[0]).ln();
+                (order, e[2] / e[1])
+            })
+            .filter(|(order, _)| order.is_finite())
+            .unzip();
+        if orders.is_empty() {
+            return None;
+        }
+
+        let order = median(orders.clone());
+        let contraction = median(
+            tail.windows(2)
+                .rev()
+                .take(WINDOW)
+                .map(|e| e[1] / e[0].powf(order))
+                .collect(),
+        );
+        let classification = if order >= 1.8 {
+            ConvergenceOrder::Quadratic
+        } else if order > 1.2 {
+            ConvergenceOrder::Superlinear
+        } else if median(ratios) < 0.99 {
+            ConvergenceOrder::Linear
+        } else {
+            ConvergenceOrder::Sublinear
+        };
+
+        Some(Self {
+            order,
+            contraction,
+            classification,
+            samples: orders.len(),
+        })
+    }
+}
+
+fn is_usable(error: f64) -> bool {
+    error.is_finite() && error > 0.0
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    }
+}
@@ -0,0 +1,149 @@
+//! Distributing sweeps over a Redis queue.
+//!
+//! A [`Coordinator`] publishes run configurations to a Redis list, and any number of [`Worker`]
+//! processes, on this or other machines, pop configurations, execute them and push the results
+//! back. Workers are thin wrappers around a closure which builds and runs a runner, so a sweep
+//! can scale beyond a single node without a separate orchestration system.
+use std::collections::BTreeMap;
+
+use redis::Commands;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+#[derive(Debug, thiserror::Error)]
+pub enum DistributedError {
+    #[error("error communicating with redis: {0}")]
+    Redis(#[from] redis::RedisError),
+    #[error("malformed message on the queue: {0}")]
+    Malformed(#[from] serde_json::Error),
+    #[error("timed out waiting for results, {0} runs outstanding")]
+    TimedOut(usize),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Task<T> {
+    index: usize,
+    config: T,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TaskResult<O> {
+    index: usize,
+    outcome: Result<O, String>,
+}
+
+/// The names of the lists a sweep communicates through
+#[derive(Clone, Debug)]
+struct Queues {
+    tasks: String,
+    results: String,
+}
+
+impl Queues {
+    fn new(sweep: &str) -> Self {
+        Self {
+            tasks: format!("trellis:{sweep}:tasks"),
+            results: format!("trellis:{sweep}:results"),
+        }
+    }
+}
+
+/// Publishes the configurations of a sweep and collects the results
+pub struct Coordinator {
+    connection: redis::Connection,
+    queues: Queues,
+}
+
+impl Coordinator {
+    /// Connect to the redis server at `url`, coordinating the sweep named `sweep`.
+    ///
+    /// Workers must be started with the same sweep name.
+    pub fn connect(url: &str, sweep: &str) -> Result<Self, DistributedError> {
+        Ok(Self {
+            connection: redis::Client::open(url)?.get_connection()?,
+            queues: Queues::new(sweep),
+        })
+    }
+
+    /// Publish every configuration, returning the number published
+    pub fn publish<T: Serialize>(&mut self, configs: &[T]) -> Result<usize, DistributedError> {
+        for (index, config) in configs.iter().enumerate() {
+            let task = serde_json::to_string(&Task { index, config })?;
+            let _: () = self.connection.lpush(&self.queues.tasks, task)?;
+        }
+        Ok(configs.len())
+    }
+
+    /// Collect the results of `count` runs, in the order their configurations were published.
+    ///
+    /// Runs which failed on the worker are returned as the error message. Waits at most
+    /// `timeout_seconds` for each result.
+    pub fn collect<O: DeserializeOwned>(
+        &mut self,
+        count: usize,
+        timeout_seconds: f64,
+    ) -> Result<Vec<Result<O, String>>, DistributedError> {
+        let mut results = BTreeMap::new();
+        while results.len() < count {
+            let popped: Option<(String, String)> = self
+                .connection
+                .brpop(&self.queues.results, timeout_seconds)?;
+            let Some((_, message)) = popped else {
+                return Err(DistributedError::TimedOut(count - results.len()));
+            };
+            let result: TaskResult<O> = serde_json::from_str(&message)?;
+            results.insert(result.index, result.outcome);
+        }
+        Ok(results.into_values().collect())
+    }
+}
+
+/// Executes configurations popped from a sweep's queue
+pub struct Worker {
+    connection: redis::Connection,
+    queues: Queues,
+}
+
+impl Worker {
+    /// Connect to the redis server at `url`, serving the sweep named `sweep`
+    pub fn connect(url: &str, sweep: &str) -> Result<Self, DistributedError> {
+        Ok(Self {
+            connection: redis::Client::open(url)?.get_connection()?,
+            queues: Queues::new(sweep),
+        })
+    }
+
+    /// Execute configurations until the queue has been empty for `idle_seconds`.
+    ///
+    /// `run` builds and executes the runner for a configuration. Returns the number of runs
+    /// executed.
+    pub fn serve<T, O, E, F>(
+        &mut self,
+        idle_seconds: f64,
+        mut run: F,
+    ) -> Result<usize, DistributedError>
+    where
+        T: DeserializeOwned,
+        O: Serialize,
+        E: std::fmt::Display,
+        F: FnMut(T) -> Result<O, E>,
+    {
+        let mut executed = 0;
+        loop {
+            let popped: Option<(String, String)> =
+                self.connection.brpop(&self.queues.tasks, idle_seconds)?;
+            let Some((_, message)) = popped else {
+                return Ok(executed);
+            };
+            let task: Task<T> = serde_json::from_str(&message)?;
+
+            let outcome = run(task.config).map_err(|e| e.to_string());
+            executed += 1;
+
+            let result = serde_json::to_string(&TaskResult {
+                index: task.index,
+                outcome,
+            })?;
+            let _: () = self.connection.lpush(&self.queues.results, result)?;
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Errors returned from a run.
+use hifitime::Duration;
+use serde::{Deserialize, Serialize};
+
+/// What caused a run to fail
+#[derive(Debug, thiserror::Error)]
+pub enum ErrorKind<E: std::error::Error + 'static> {
+    #[error("error in calculation: {0}")]
+    Calculation(#[source] E),
+    #[error("invalid measure {measure} at iteration {iteration}")]
+    InvalidMeasure { iteration: usize, measure: f64 },
+    /// The calculation panicked, with the message carried by the panic
+    #[error("calculation panicked: {0}")]
+    Panicked(String),
+    /// The state was lost when an earlier call on the runner failed, so the run cannot continue
+    #[error("the state was lost to an earlier failure, so the run cannot continue")]
+    StateUnavailable,
+}
+
+/// Error raised while setting up a runner
+#[derive(Debug, thiserror::Error)]
+pub enum RunnerError {
+    /// The control-c handler could not be installed
+    #[error("failed to install the control-c handler: {0}")]
+    HandlerInstallFailed(#[source] std::io::Error),
+    /// The thread listening for the controller's kill signal could not be spawned
+    #[error("failed to spawn the controller thread: {0}")]
+    ControllerSpawnFailed(#[source] std::io::Error),
+    /// The runner's controllers have already been initialised
+    #[error("the runner has already been finalised")]
+    AlreadyFinalised,
+    /// A required observer could not start
+    #[error("observer {index} could not start: {reason}")]
+    ObserverUnavailable { index: usize, reason: String },
+}
+
+/// How far a run got before it failed
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunProgress {
+    /// The last iteration completed
+    pub iteration: usize,
+    /// The measure at the last completed iteration
+    pub measure: f64,
+    /// The best measure found
+    pub best_measure: f64,
+    /// Time elapsed since the run started, if the run was timed
+    pub elapsed: Option<Duration>,
+}
+
+/// Error returned by the runner.
+///
+/// Wraps the error of the calculation alongside failures detected by the runner itself. Where
+/// the run completed at least one iteration before failing, the error records how far it got.
+#[derive(Debug, thiserror::Error)]
+#[error("{kind}")]
+pub struct TrellisError<E: std::error::Error + 'static> {
+    #[source]
+    kind: ErrorKind<E>,
+    progress: Option<RunProgress>,
+}
+
+impl<E: std::error::Error + 'static> From<E> for TrellisError<E> {
+    fn from(error: E) -> Self {
+        ErrorKind::Calculation(error).into()
+    }
+}
+
+impl<E: std::error::Error + 'static> From<ErrorKind<E>> for TrellisError<E> {
+    fn from(kind: ErrorKind<E>) -> Self {
+        Self {
+            kind,
+            progress: None,
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> TrellisError<E> {
+    pub(crate) fn with_progress(mut self, progress: Option<RunProgress>) -> Self {
+        if self.progress.is_none() {
+            self.progress = progress;
+        }
+        self
+    }
+
+    /// What caused the run to fail
+    pub fn kind(&self) -> &ErrorKind<E> {
+        &self.kind
+    }
+
+    pub fn into_kind(self) -> ErrorKind<E> {
+        self.kind
+    }
+
+    /// How far the run got before failing, `None` if it failed before completing an iteration
+    pub fn progress(&self) -> Option<&RunProgress> {
+        self.progress.as_ref()
+    }
+
+    /// The calculation error, if that is what caused the failure
+    pub fn calculation_error(&self) -> Option<&E> {
+        match &self.kind {
+            ErrorKind::Calculation(error) => Some(error),
+            _ => None,
+        }
+    }
+}
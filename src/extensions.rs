@@ -0,0 +1,120 @@
+//! A typemap in which subsystems and plugins attach their own data to a state.
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A value stored in [`Extensions`]
+trait Extension: Any + Send + Sync {
+    fn clone_box(&self) -> Box<dyn Extension>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+    fn type_name(&self) -> &'static str;
+}
+
+impl<T: Any + Clone + Send + Sync> Extension for T {
+    fn clone_box(&self) -> Box<dyn Extension> {
+        Box::new(self.clone())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+}
+
+/// Data attached to a state by subsystems and plugins, holding at most one value of each type.
+///
+/// Cross-cutting features such as history capture, random number generators or scratch
+/// directories stash what they need here rather than each requiring a field on every state.
+/// States expose their extensions through [`State::extensions`](crate::State::extensions) and
+/// [`State::extensions_mut`](crate::State::extensions_mut); a state deriving `Clone` clones its
+/// extensions with it. Extensions are not serialised, so a state holding them should mark the
+/// field `#[serde(skip)]`.
+#[derive(Default)]
+pub struct Extensions(HashMap<TypeId, Box<dyn Extension>>);
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a value, returning the previous value of the same type
+    pub fn insert<T: Any + Clone + Send + Sync>(&mut self, value: T) -> Option<T> {
+        self.0
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|previous| previous.into_any().downcast().ok())
+            .map(|previous| *previous)
+    }
+
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.0
+            .get(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any().downcast_ref())
+    }
+
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.0
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any_mut().downcast_mut())
+    }
+
+    /// The value of type `T`, inserting one made by `default` if there is none
+    pub fn get_or_insert_with<T: Any + Clone + Send + Sync>(
+        &mut self,
+        default: impl FnOnce() -> T,
+    ) -> &mut T {
+        self.0
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(default()))
+            .as_mut()
+            .as_any_mut()
+            .downcast_mut()
+            .unwrap()
+    }
+
+    pub fn remove<T: Any>(&mut self) -> Option<T> {
+        self.0
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.into_any().downcast().ok())
+            .map(|value| *value)
+    }
+
+    pub fn contains<T: Any>(&self) -> bool {
+        self.0.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl Clone for Extensions {
+    fn clone(&self) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|(id, value)| (*id, (**value).clone_box()))
+                .collect(),
+        )
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.0.values().map(|value| (**value).type_name()))
+            .finish()
+    }
+}
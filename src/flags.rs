@@ -0,0 +1,69 @@
+//! Typed feature flags set on the builder and read by calculations.
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use crate::KV;
+
+/// A small typemap of feature flags, holding at most one value of each type.
+///
+/// Flags let experimental code paths inside a calculation be toggled per run without changing
+/// its signature. They are set with [`Builder::with_flag`](crate::Builder::with_flag), read from
+/// the [`Problem`](crate::Problem) passed to every step of the calculation, and recorded in the
+/// [`RunMetadata`](crate::RunMetadata) of the run.
+#[derive(Default)]
+pub struct Flags {
+    values: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    described: Vec<(TypeId, &'static str, String)>,
+}
+
+impl Flags {
+    /// Set a flag, replacing any earlier flag of the same type
+    pub fn insert<T: Any + Debug + Send + Sync>(&mut self, value: T) {
+        let id = TypeId::of::<T>();
+        let description = format!("{value:?}");
+        match self.described.iter_mut().find(|(other, ..)| *other == id) {
+            Some(entry) => entry.2 = description,
+            None => self
+                .described
+                .push((id, std::any::type_name::<T>(), description)),
+        }
+        self.values.insert(id, Box::new(value));
+    }
+
+    /// The flag of type `T`, if one was set
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    /// Whether a flag of type `T` was set
+    pub fn contains<T: Any>(&self) -> bool {
+        self.values.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// The flags in the order they were first set, keyed by type name with `Debug` formatted
+    /// values
+    pub fn describe(&self) -> KV {
+        self.described
+            .iter()
+            .fold(KV::new(), |kv, (_, name, value)| kv.with(*name, value))
+    }
+}
+
+impl Debug for Flags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.described.iter().map(|(_, name, value)| (name, value)))
+            .finish()
+    }
+}
@@ -0,0 +1,77 @@
+//! Coarse assessment of the quality of a result.
+//!
+//! Downstream pipelines rarely care about the detail of a run, only whether the result can be
+//! trusted. A [`Grade`] condenses the outcome into one of four labels, each with an exit code.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{Measure, Reason, State};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Grade {
+    /// Converged to the requested tolerance
+    ConvergedTight,
+    /// Converged, but only to a looser tolerance than requested
+    ConvergedLoose,
+    /// Did not converge, but the result is finite and may be usable
+    UnconvergedUsable,
+    /// The result should not be used
+    Failed,
+}
+
+impl Grade {
+    /// Grade a result from the tolerance achieved against that requested.
+    ///
+    /// Results within `loose_factor` times the requested tolerance are graded as loosely
+    /// converged. Non-finite tolerances are failures.
+    pub fn from_tolerance(achieved: f64, requested: f64, loose_factor: f64) -> Self {
+        if !achieved.is_finite() {
+            Self::Failed
+        } else if achieved <= requested {
+            Self::ConvergedTight
+        } else if achieved <= requested * loose_factor {
+            Self::ConvergedLoose
+        } else {
+            Self::UnconvergedUsable
+        }
+    }
+
+    /// Grade a result from the reason the run terminated and its final measure
+    pub fn from_state<S: State>(state: &S) -> Self {
+        if !state.measure().real().is_finite() {
+            return Self::Failed;
+        }
+        match state.termination_reason() {
+            Some(Reason::Converged) => Self::ConvergedTight,
+            _ => Self::UnconvergedUsable,
+        }
+    }
+
+    /// Process exit code for the grade, zero only for converged results
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::ConvergedTight => 0,
+            Self::ConvergedLoose => 1,
+            Self::UnconvergedUsable => 2,
+            Self::Failed => 3,
+        }
+    }
+
+    /// Whether the result converged, tightly or loosely
+    pub fn is_converged(&self) -> bool {
+        matches!(self, Self::ConvergedTight | Self::ConvergedLoose)
+    }
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::ConvergedTight => "converged-tight",
+            Self::ConvergedLoose => "converged-loose",
+            Self::UnconvergedUsable => "unconverged-usable",
+            Self::Failed => "failed",
+        };
+        f.write_str(label)
+    }
+}
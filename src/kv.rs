@@ -0,0 +1,68 @@
+//! Key-value pairs reported alongside the core measures of a run.
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+use crate::State;
+
+/// An ordered collection of key-value pairs attached to an observation
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KV(Vec<(String, String)>);
+
+impl KV {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The values reported by `state`, followed by any its calculation reported for it.
+    ///
+    /// Observers should read values through this rather than [`State::kv`], so they include
+    /// those from [`Calculation::report`](crate::Calculation::report).
+    pub fn of<S: State>(state: &S) -> Self {
+        let mut kv = state.kv();
+        if let Some(ReportedKV(reported)) = state
+            .extensions()
+            .and_then(|extensions| extensions.get::<ReportedKV>())
+        {
+            kv.0.extend(reported.0.iter().cloned());
+        }
+        kv
+    }
+
+    /// Append a pair, formatting the value with its `Display` implementation
+    #[must_use]
+    pub fn with(mut self, key: impl Into<String>, value: impl Display) -> Self {
+        self.push(key, value);
+        self
+    }
+
+    /// Append a pair, formatting the value with its `Display` implementation
+    pub fn push(&mut self, key: impl Into<String>, value: impl Display) {
+        self.0.push((key.into(), value.to_string()));
+    }
+
+    /// Append every pair from `other`
+    pub fn merge(&mut self, other: KV) {
+        self.0.extend(other.0);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+/// Values reported by the calculation with [`Calculation::report`](crate::Calculation::report).
+///
+/// The runner inserts them into the [`Extensions`](crate::Extensions) of states which hold them
+/// at initialisation and after every iteration, where [`KV::of`] finds them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReportedKV(pub KV);
@@ -0,0 +1,124 @@
+//! Accounting for the resources a run consumes.
+//!
+//! The runner times the calculation and the observers as the run proceeds, and collects the
+//! remaining figures when the run is finalised into a [`ResourceLedger`]. The ledger is part of
+//! the run's [`Output`](crate::Output) and of its [`Report`](crate::Report), including the one
+//! written by the [`RunSummary`](crate::RunSummary) observer, which reads it from the extensions
+//! of the final state.
+use std::cell::Cell;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::Clock;
+
+/// Everything measured about what a run cost
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ResourceLedger {
+    /// Wall time of the run in seconds, if the run was timed
+    pub wall_time: Option<f64>,
+    /// Seconds spent in the calculation, initialising and iterating
+    pub compute_time: f64,
+    /// Seconds spent notifying observers
+    pub observer_time: f64,
+    /// Iterations completed
+    pub iterations: usize,
+    /// Evaluations of the problem, if the calculation counted them with
+    /// [`Problem::record_evaluations`](crate::Problem::record_evaluations)
+    pub evaluations: Option<u64>,
+    /// Bytes in the files written by writers, observers and checkpoints during the run
+    pub bytes_written: u64,
+    /// The peak resident memory of the process, which includes any other runs in it
+    pub peak_rss_bytes: Option<u64>,
+    /// Energy consumed, if an [`EnergyMeter`](crate::EnergyMeter) was attached
+    pub energy_joules: Option<f64>,
+}
+
+// Running totals of the resources used on this thread. Observers run on the thread of the run
+// they observe, so the difference between two readings is what the run used in between, unless
+// other runs are interleaved on the same thread.
+thread_local! {
+    static BYTES_WRITTEN: Cell<u64> = const { Cell::new(0) };
+    static ENERGY: Cell<(f64, usize)> = const { Cell::new((0.0, 0)) };
+}
+
+/// Count `bytes` written to a file against the run on this thread
+#[cfg(feature = "writing")]
+pub(crate) fn record_bytes_written(bytes: u64) {
+    BYTES_WRITTEN.with(|total| total.set(total.get() + bytes));
+}
+
+/// Count `joules` consumed against the run on this thread
+#[cfg(feature = "energy")]
+pub(crate) fn record_energy(joules: f64) {
+    ENERGY.with(|total| {
+        let (consumed, samples) = total.get();
+        total.set((consumed + joules, samples + 1));
+    });
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+struct Totals {
+    bytes_written: u64,
+    energy: (f64, usize),
+}
+
+impl Totals {
+    fn read() -> Self {
+        Self {
+            bytes_written: BYTES_WRITTEN.with(Cell::get),
+            energy: ENERGY.with(Cell::get),
+        }
+    }
+}
+
+/// Times a run's calculation and observers, and reads the other totals when the run finishes
+#[derive(Debug, Default)]
+pub(crate) struct Meter {
+    compute: Duration,
+    observers: Duration,
+    start: Totals,
+}
+
+impl Meter {
+    /// Take the starting totals, when the run starts
+    pub(crate) fn start(&mut self) {
+        self.start = Totals::read();
+    }
+
+    /// Run part of the calculation, adding its duration on `clock` to the compute time
+    pub(crate) fn compute<T>(&mut self, clock: &dyn Clock, f: impl FnOnce() -> T) -> T {
+        let started = clock.monotonic();
+        let result = f();
+        self.compute += clock.monotonic().saturating_sub(started);
+        result
+    }
+
+    /// Notify observers, adding the duration on `clock` to the observer time
+    pub(crate) fn observe<T>(&mut self, clock: &dyn Clock, f: impl FnOnce() -> T) -> T {
+        let started = clock.monotonic();
+        let result = f();
+        self.observers += clock.monotonic().saturating_sub(started);
+        result
+    }
+
+    pub(crate) fn ledger(
+        &self,
+        wall_time: Option<f64>,
+        iterations: usize,
+        evaluations: Option<u64>,
+    ) -> ResourceLedger {
+        let now = Totals::read();
+        let (joules, samples) = now.energy;
+        ResourceLedger {
+            wall_time,
+            compute_time: self.compute.as_secs_f64(),
+            observer_time: self.observers.as_secs_f64(),
+            iterations,
+            evaluations,
+            bytes_written: now.bytes_written - self.start.bytes_written,
+            peak_rss_bytes: crate::resources::peak_rss_bytes(),
+            energy_joules: (samples > self.start.energy.1).then_some(joules - self.start.energy.0),
+        }
+    }
+}
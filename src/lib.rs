@@ -1,42 +1,154 @@
 #![allow(dead_code)]
 
+#[cfg(feature = "writing")]
+pub mod artifacts;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod budget;
+mod cache;
 mod calculation;
+#[cfg(feature = "writing")]
+pub mod checkpoint;
+mod clock;
 mod controller;
+mod convergence;
+#[cfg(feature = "redis")]
+pub mod distributed;
+mod error;
+mod extensions;
+mod flags;
+mod grade;
+mod kv;
+mod ledger;
+pub mod lineage;
+mod metadata;
+pub mod mixins;
+mod norm;
 
 #[cfg(feature = "plotting")]
 mod plotters;
 
+mod pipeline;
 pub mod prelude;
 mod problem;
+#[cfg(feature = "writing")]
+mod replay;
+mod report;
+mod resources;
 mod result;
 mod runner;
+pub mod solvers;
 mod state;
+#[cfg(feature = "writing")]
+mod telemetry;
+mod timestamp;
+#[cfg(feature = "uom")]
+mod units;
 mod watchers;
 
 #[cfg(feature = "writing")]
 mod writers;
 
-pub use calculation::Calculation;
+pub use cache::{CacheStats, WarmCache};
+pub use calculation::{Calculation, FloatOf};
+#[cfg(feature = "writing")]
+pub use checkpoint::CheckpointError;
+pub use clock::{Clock, MockClock, SystemClock};
 pub(crate) use controller::Control;
+pub use controller::{CancelChecker, Cancellation};
+pub use convergence::{ConvergenceOrder, ConvergenceReport};
+pub use error::{ErrorKind, RunProgress, RunnerError, TrellisError};
+pub use extensions::Extensions;
+pub use flags::Flags;
+pub use grade::Grade;
+pub use kv::{ReportedKV, KV};
+pub use ledger::ResourceLedger;
+pub use metadata::{MetadataError, RunId, RunMetadata};
+pub use norm::Norm;
 
 #[cfg(feature = "plotting")]
-pub use plotters::PlotConfig;
+pub use plotters::{ComparisonPlotter, MeasureScale, PlotConfig, PlotterError, RenderMode};
 #[cfg(feature = "plotting")]
 pub use watchers::PlotGenerator;
 
+pub use pipeline::{Pipeline, PipelineError, StageSummary};
 pub use problem::Problem;
+#[cfg(feature = "writing")]
+pub use replay::{Journal, JournalEntry, ReplayCalculation, ReplayError, ReplayState};
+pub use report::{Report, ReportFormat};
+pub use resources::ContainerLimits;
 pub use result::Output;
-pub use runner::GenerateBuilder;
+pub use runner::{BoxedRunner, Run, Solver};
+pub use runner::{Builder, GenerateBuilder, InvalidMeasurePolicy, Plan, ProbeEstimate, Runner};
+pub use runner::{Divergence, DivergenceKind, Lockstep, LockstepOutcome};
+pub use runner::{Installer, Plugin};
+pub use runner::{Interleave, InterleaveError, InterleaveOutcome, PairedComparison, RunTrace};
+pub use runner::{IterationAttempts, IterationErrorPolicy};
+pub use runner::{
+    Preemption, QueueError, QueueProgress, QueueSummary, RunQueue, Submitter, WorkerUtilisation,
+};
+pub use runner::{RestartAttempt, RestartPolicy};
 pub use state::{Reason, State, Status};
-pub use watchers::Tracer;
-pub use watchers::{Frequency, Target};
+#[cfg(feature = "writing")]
+pub use telemetry::Telemetry;
+pub use timestamp::Timestamp;
+#[cfg(feature = "uom")]
+pub use units::SiMeasure;
+#[cfg(feature = "dashboard")]
+pub use watchers::Dashboard;
+#[cfg(feature = "log")]
+pub use watchers::LogLogger;
+#[cfg(feature = "metrics")]
+pub use watchers::MetricsPublisher;
+#[cfg(feature = "notify")]
+pub use watchers::NotifyOnExit;
+#[cfg(feature = "otel")]
+pub use watchers::OtelMetrics;
+#[cfg(feature = "parquet")]
+pub use watchers::ParquetWriter;
+#[cfg(feature = "tokio")]
+pub use watchers::ProgressSnapshot;
+pub use watchers::RingFile;
+#[cfg(feature = "slog")]
+pub use watchers::SlogLogger;
+#[cfg(feature = "webhook")]
+pub use watchers::WebhookObserver;
+pub use watchers::{clear_default_observers, register_default_observer, QUIET_ENV_VAR};
+pub use watchers::{ChannelObserver, EventSink, ObservationEvent};
+#[cfg(feature = "energy")]
+pub use watchers::{EnergyMeter, EnergyReport, EnergySource};
+pub use watchers::{
+    Frequency, FrequencySet, Mutable, Observation, ObservationError, Observer, ObserverHandle,
+    ObserverId, ObserverMut, ObserverPlan, Snapshot, SnapshotAdapter, SnapshotObserver, Stage,
+    Target,
+};
+#[cfg(feature = "static-plots")]
+pub use watchers::{StaticPlotFormat, StaticPlotGenerator};
+pub use watchers::{Tracer, TracerConfig};
 
 #[cfg(feature = "writing")]
-pub use watchers::FileWriter;
+pub use watchers::{
+    Autosuspend, DispatchError, Outage, RemoteDispatcher, RemoteForwarder, RemoteState,
+    SuspendPolicy,
+};
+#[cfg(feature = "writing")]
+pub use watchers::{FileWriter, JsonLinesLogger};
 
+#[cfg(feature = "writing")]
+pub use watchers::ArrayWriter;
+#[cfg(feature = "writing")]
+pub use watchers::Checkpointer;
+#[cfg(feature = "writing")]
+pub use watchers::{Heartbeat, RunSummary};
 #[cfg(feature = "writing")]
 pub use writers::WriteToFileSerializer;
+#[cfg(feature = "encryption")]
+pub use writers::{decrypt, EncryptionError, EncryptionKey};
+#[cfg(feature = "writing")]
+pub use writers::{ArrayElement, ArrayFormat, ArrayParam, Compression, FsyncPolicy, Rotation};
 
 pub use hifitime::Duration;
 
-pub use state::TrellisFloat;
+#[cfg(feature = "num-traits")]
+pub use state::FloatMeasure;
+pub use state::{Measure, TrellisFloat};
@@ -0,0 +1,83 @@
+//! Stitching the convergence curves of related runs.
+//!
+//! A run continued from an earlier one records the earlier run as its parent. Given the
+//! convergence curves of every run in a lineage, [`stitch`] orders them from the root and joins
+//! them into one continuous curve.
+use std::collections::HashMap;
+
+use crate::{RunId, RunMetadata};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LineageError {
+    #[error("no curves were provided")]
+    Empty,
+    #[error("the curves do not form a single lineage, found {0} roots")]
+    MultipleRoots(usize),
+    #[error("run {0} has more than one child")]
+    Branched(RunId),
+    #[error("{0} curves are not reachable from the root")]
+    Disconnected(usize),
+}
+
+/// The convergence curve of a single run, as `(iteration, measure)` pairs
+#[derive(Clone, Debug)]
+pub struct Curve {
+    pub metadata: RunMetadata,
+    pub points: Vec<(usize, f64)>,
+}
+
+/// Join the curves of a lineage into one continuous curve.
+///
+/// Curves are ordered by following parent links from the single root. A child whose iterations
+/// restart from zero is offset to follow on from the last iteration of its parent, while a child
+/// which carried the iteration count over is left unchanged.
+pub fn stitch(curves: Vec<Curve>) -> Result<Vec<(usize, f64)>, LineageError> {
+    if curves.is_empty() {
+        return Err(LineageError::Empty);
+    }
+    let total = curves.len();
+
+    let ids = curves
+        .iter()
+        .map(|curve| curve.metadata.run_id.clone())
+        .collect::<Vec<_>>();
+    let (mut roots, mut children): (Vec<_>, HashMap<RunId, Curve>) = (vec![], HashMap::new());
+    for curve in curves {
+        match curve.metadata.parent.clone() {
+            Some(parent) if ids.contains(&parent) => {
+                if children.insert(parent.clone(), curve).is_some() {
+                    return Err(LineageError::Branched(parent));
+                }
+            }
+            _ => roots.push(curve),
+        }
+    }
+    if roots.len() != 1 {
+        return Err(LineageError::MultipleRoots(roots.len()));
+    }
+
+    let mut stitched: Vec<(usize, f64)> = vec![];
+    let mut next = roots.pop();
+    let mut visited = 0;
+    while let Some(curve) = next {
+        let last = stitched.last().map(|(iteration, _)| *iteration);
+        let first = curve.points.first().map(|(iteration, _)| *iteration);
+        let offset = match (last, first) {
+            (Some(last), Some(first)) if first <= last => last,
+            _ => 0,
+        };
+        stitched.extend(
+            curve
+                .points
+                .iter()
+                .map(|(iteration, measure)| (iteration + offset, *measure)),
+        );
+        visited += 1;
+        next = children.remove(&curve.metadata.run_id);
+    }
+
+    if visited != total {
+        return Err(LineageError::Disconnected(total - visited));
+    }
+    Ok(stitched)
+}
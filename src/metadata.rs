@@ -0,0 +1,137 @@
+//! Descriptive information recorded about a run.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use hifitime::Epoch;
+
+use crate::{ContainerLimits, KV};
+
+/// Identifier unique to a single run.
+///
+/// Identifiers combine the start time, the process id and a per-process counter, so runs started
+/// concurrently, in this or any other process, never share an identifier.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct RunId(String);
+
+impl RunId {
+    pub(crate) fn generate() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let millis = Epoch::now()
+            .map(|now| now.to_unix_milliseconds() as u64)
+            .unwrap_or_default();
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        Self(format!("{millis:x}-{:x}-{count}", std::process::id()))
+    }
+
+    // Stands in for the identifier of a run whose state does not record it
+    pub(crate) fn unrecorded() -> Self {
+        Self("unrecorded".to_owned())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RunId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MetadataError {
+    #[error("recorded by calculation {recorded}, but resuming with {current}")]
+    CalculationMismatch { recorded: String, current: String },
+    #[error("recorded by {calculation} version {recorded}, but resuming with version {current}")]
+    VersionMismatch {
+        calculation: String,
+        recorded: String,
+        current: String,
+    },
+}
+
+/// Metadata describing a run.
+///
+/// The runner also attaches the metadata, as it stood when the run started, to the extensions of
+/// its state. This is how a state carried into another run, directly or through a checkpoint,
+/// records the calculation which produced it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunMetadata {
+    /// Identifier of the run
+    pub run_id: RunId,
+    /// Identifier of the run this one continues, if any
+    pub parent: Option<RunId>,
+    /// The last iteration completed, recorded when the run is finalised
+    pub final_iteration: Option<usize>,
+    /// Name of the calculation
+    pub calculation: String,
+    /// Version of the calculation
+    pub calculation_version: String,
+    /// Resource limits of the container the run executed in
+    pub container_limits: Option<ContainerLimits>,
+    /// Feature flags set on the builder, keyed by type name
+    #[serde(default)]
+    pub flags: KV,
+    /// Names of the plugins installed on the builder
+    #[serde(default)]
+    pub plugins: Vec<String>,
+    /// Values contributed by plugins, keyed by plugin name and key
+    #[serde(default)]
+    pub annotations: KV,
+}
+
+impl RunMetadata {
+    pub(crate) fn new(
+        run_id: RunId,
+        calculation: &str,
+        calculation_version: &str,
+        parent: Option<RunId>,
+    ) -> Self {
+        Self {
+            run_id,
+            parent,
+            final_iteration: None,
+            calculation: calculation.to_owned(),
+            calculation_version: calculation_version.to_owned(),
+            container_limits: None,
+            flags: KV::new(),
+            plugins: vec![],
+            annotations: KV::new(),
+        }
+    }
+
+    /// Check that data recorded by a previous run can be used to continue this one.
+    ///
+    /// Continuing from the output of a different calculation, or a different version of the same
+    /// calculation, would silently produce garbage if the algorithm changed between runs.
+    pub fn ensure_compatible(&self, recorded: &RunMetadata) -> Result<(), MetadataError> {
+        ensure_compatible(
+            (&recorded.calculation, &recorded.calculation_version),
+            (&self.calculation, &self.calculation_version),
+        )
+    }
+}
+
+/// Check that data recorded by the calculation and version `recorded` can be used to continue a
+/// run of `current`
+pub(crate) fn ensure_compatible(
+    recorded: (&str, &str),
+    current: (&str, &str),
+) -> Result<(), MetadataError> {
+    if recorded.0 != current.0 {
+        return Err(MetadataError::CalculationMismatch {
+            recorded: recorded.0.to_owned(),
+            current: current.0.to_owned(),
+        });
+    }
+    if recorded.1 != current.1 {
+        return Err(MetadataError::VersionMismatch {
+            calculation: current.0.to_owned(),
+            recorded: recorded.1.to_owned(),
+            current: current.1.to_owned(),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,245 @@
+//! Reusable bookkeeping for common solver states.
+//!
+//! Line searches, trust regions and gradient methods each track a quantity of their own beside
+//! the measure, which is worth reporting to observers. Rather than adding it to every state, a
+//! state can be wrapped in [`With`], which carries the quantity, reports it through
+//! [`State::kv`] and otherwise behaves as the wrapped state:
+//!
+//! ```ignore
+//! type MyState = WithStepSize<WithGradientNorm<NewtonState>>;
+//!
+//! fn next(&mut self, problem: &mut Problem<P>, mut state: MyState) -> Result<MyState, E> {
+//!     state.set_step_size(alpha);
+//!     // The wrapper dereferences to the wrapped state, so its methods stay available
+//!     state.set_gradient_norm(gradient.norm());
+//!     ..
+//! }
+//! ```
+use std::ops::{Deref, DerefMut};
+
+use hifitime::Duration;
+
+use crate::{Extensions, Reason, RunId, State, KV};
+
+/// A quantity tracked alongside a state by [`With`]
+pub trait Auxiliary: Default {
+    /// Report the quantity to observers
+    fn report(&self, kv: &mut KV);
+}
+
+/// The step taken by a line search in the last iteration
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct StepSize(pub Option<f64>);
+
+impl Auxiliary for StepSize {
+    fn report(&self, kv: &mut KV) {
+        if let Some(step_size) = self.0 {
+            kv.push("step_size", step_size);
+        }
+    }
+}
+
+/// The norm of the gradient at the current parameter
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct GradientNorm(pub Option<f64>);
+
+impl Auxiliary for GradientNorm {
+    fn report(&self, kv: &mut KV) {
+        if let Some(norm) = self.0 {
+            kv.push("gradient_norm", norm);
+        }
+    }
+}
+
+/// The radius of a trust region, and how many steps it has rejected
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct TrustRadius {
+    pub radius: Option<f64>,
+    pub rejected_steps: usize,
+}
+
+impl Auxiliary for TrustRadius {
+    fn report(&self, kv: &mut KV) {
+        if let Some(radius) = self.radius {
+            kv.push("trust_radius", radius);
+        }
+        kv.push("rejected_steps", self.rejected_steps);
+    }
+}
+
+/// A state extended with the quantity `A`.
+///
+/// Every [`State`] method is forwarded to the wrapped state, except that [`State::kv`] also
+/// reports the quantity.
+#[derive(Clone, Debug, Default)]
+pub struct With<S, A> {
+    inner: S,
+    auxiliary: A,
+}
+
+pub type WithStepSize<S> = With<S, StepSize>;
+pub type WithGradientNorm<S> = With<S, GradientNorm>;
+pub type WithTrustRadius<S> = With<S, TrustRadius>;
+
+impl<S, A: Auxiliary> With<S, A> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            auxiliary: A::default(),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    pub fn auxiliary(&self) -> &A {
+        &self.auxiliary
+    }
+
+    pub fn auxiliary_mut(&mut self) -> &mut A {
+        &mut self.auxiliary
+    }
+}
+
+impl<S> WithStepSize<S> {
+    pub fn step_size(&self) -> Option<f64> {
+        self.auxiliary.0
+    }
+
+    pub fn set_step_size(&mut self, step_size: f64) {
+        self.auxiliary.0 = Some(step_size);
+    }
+}
+
+impl<S> WithGradientNorm<S> {
+    pub fn gradient_norm(&self) -> Option<f64> {
+        self.auxiliary.0
+    }
+
+    pub fn set_gradient_norm(&mut self, norm: f64) {
+        self.auxiliary.0 = Some(norm);
+    }
+}
+
+impl<S> WithTrustRadius<S> {
+    pub fn trust_radius(&self) -> Option<f64> {
+        self.auxiliary.radius
+    }
+
+    pub fn set_trust_radius(&mut self, radius: f64) {
+        self.auxiliary.radius = Some(radius);
+    }
+
+    /// Count a step rejected by the trust region
+    pub fn reject_step(&mut self) {
+        self.auxiliary.rejected_steps += 1;
+    }
+
+    pub fn rejected_steps(&self) -> usize {
+        self.auxiliary.rejected_steps
+    }
+}
+
+impl<S, A> Deref for With<S, A> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S, A> DerefMut for With<S, A> {
+    fn deref_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+}
+
+impl<S: State, A: Auxiliary> State for With<S, A> {
+    type Float = S::Float;
+    type Param = S::Param;
+
+    fn record_time(&mut self, duration: Duration) {
+        self.inner.record_time(duration);
+    }
+    fn duration(&self) -> Option<Duration> {
+        self.inner.duration()
+    }
+    fn increment_iteration(&mut self) {
+        self.inner.increment_iteration();
+    }
+    fn current_iteration(&self) -> usize {
+        self.inner.current_iteration()
+    }
+    fn update(mut self) -> Self {
+        self.inner = self.inner.update();
+        self
+    }
+    fn is_initialised(&self) -> bool {
+        self.inner.is_initialised()
+    }
+    fn is_terminated(&self) -> bool {
+        self.inner.is_terminated()
+    }
+    fn terminate_due_to(mut self, reason: Reason) -> Self {
+        self.inner = self.inner.terminate_due_to(reason);
+        self
+    }
+    fn termination_reason(&self) -> Option<Reason> {
+        self.inner.termination_reason()
+    }
+    fn get_param(&self) -> Option<&Self::Param> {
+        self.inner.get_param()
+    }
+    fn get_param_mut(&mut self) -> Option<&mut Self::Param> {
+        self.inner.get_param_mut()
+    }
+    fn measure(&self) -> Self::Float {
+        self.inner.measure()
+    }
+    fn best_measure(&self) -> Self::Float {
+        self.inner.best_measure()
+    }
+    fn iterations_since_best(&self) -> usize {
+        self.inner.iterations_since_best()
+    }
+    fn kv(&self) -> KV {
+        let mut kv = self.inner.kv();
+        self.auxiliary.report(&mut kv);
+        kv
+    }
+    fn for_warm_start(mut self) -> Self {
+        self.inner = self.inner.for_warm_start();
+        self
+    }
+    fn set_relative_tolerance(&mut self, tolerance: f64) {
+        self.inner.set_relative_tolerance(tolerance);
+    }
+    fn max_evaluations(&self) -> Option<u64> {
+        self.inner.max_evaluations()
+    }
+    fn set_run_id(&mut self, run_id: RunId) {
+        self.inner.set_run_id(run_id);
+    }
+    fn run_id(&self) -> Option<&RunId> {
+        self.inner.run_id()
+    }
+    fn extensions(&self) -> Option<&Extensions> {
+        self.inner.extensions()
+    }
+    fn extensions_mut(&mut self) -> Option<&mut Extensions> {
+        self.inner.extensions_mut()
+    }
+    #[cfg(feature = "plotting")]
+    fn field(&self) -> Option<ndarray::ArrayView2<'_, Self::Float>> {
+        self.inner.field()
+    }
+}
@@ -0,0 +1,77 @@
+//! Norms of parameters, for convergence criteria on the parameter rather than the measure.
+
+/// A parameter with a norm.
+///
+/// Implemented for scalars and `Vec<f64>` with the absolute value and Euclidean norm, and for
+/// the vector types of `ndarray` and `nalgebra` when their features are enabled.
+pub trait Norm {
+    fn norm(&self) -> f64;
+    /// The norm of `self - other`
+    fn distance(&self, other: &Self) -> f64;
+
+    /// The change from `previous` to `self`, relative to the norm of `self`.
+    ///
+    /// Infinite when `self` is zero but `previous` is not.
+    fn relative_change(&self, previous: &Self) -> f64 {
+        let change = self.distance(previous);
+        let norm = self.norm();
+        if change == 0.0 {
+            0.0
+        } else if norm == 0.0 {
+            f64::INFINITY
+        } else {
+            change / norm
+        }
+    }
+}
+
+impl Norm for f64 {
+    fn norm(&self) -> f64 {
+        self.abs()
+    }
+    fn distance(&self, other: &Self) -> f64 {
+        (self - other).abs()
+    }
+}
+
+impl Norm for f32 {
+    fn norm(&self) -> f64 {
+        f64::from(self.abs())
+    }
+    fn distance(&self, other: &Self) -> f64 {
+        f64::from((self - other).abs())
+    }
+}
+
+impl Norm for Vec<f64> {
+    fn norm(&self) -> f64 {
+        self.iter().map(|x| x * x).sum::<f64>().sqrt()
+    }
+    fn distance(&self, other: &Self) -> f64 {
+        self.iter()
+            .zip(other)
+            .map(|(a, b)| (a - b).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl Norm for ndarray::Array1<f64> {
+    fn norm(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+    fn distance(&self, other: &Self) -> f64 {
+        (self - other).mapv(|x| x * x).sum().sqrt()
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl Norm for nalgebra::DVector<f64> {
+    fn norm(&self) -> f64 {
+        nalgebra::DVector::norm(self)
+    }
+    fn distance(&self, other: &Self) -> f64 {
+        (self - other).norm()
+    }
+}
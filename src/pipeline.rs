@@ -0,0 +1,226 @@
+//! Chaining calculations into staged solves.
+//!
+//! A [`Pipeline`] runs several calculations over the same problem and state one after another,
+//! such as a cheap coarse solve followed by an expensive refinement. Each stage starts from the
+//! state the previous stage terminated with, so the whole pipeline is a single run: one set of
+//! observers sees every iteration, notified under the name of the stage in progress, and
+//! `run_to_output` returns one [`Output`](crate::Output) whose history spans all the stages.
+use serde::Serialize;
+use std::error::Error;
+
+use tracing::info;
+
+use crate::{Calculation, Grade, Measure, Problem, Reason, State, KV};
+
+/// Error raised by a stage of a pipeline
+#[derive(Debug, thiserror::Error)]
+#[error("pipeline stage {stage} failed: {source}")]
+pub struct PipelineError {
+    /// The name of the stage which failed
+    pub stage: &'static str,
+    #[source]
+    pub source: Box<dyn Error>,
+}
+
+/// How a completed stage of a pipeline went
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StageSummary {
+    pub name: &'static str,
+    /// The iteration of the run at which the stage started
+    pub first_iteration: usize,
+    pub iterations: usize,
+    /// Why the stage terminated, if the state records it
+    pub reason: Option<Reason>,
+    pub best_measure: f64,
+}
+
+/// A calculation as seen by the pipeline, with its error erased
+trait Step<P, S> {
+    fn initialise(&mut self, problem: &mut Problem<P>, state: S) -> Result<S, Box<dyn Error>>;
+    fn next(&mut self, problem: &mut Problem<P>, state: S) -> Result<S, Box<dyn Error>>;
+    fn grade(&self, state: &S) -> Grade;
+    fn report(&self, state: &S) -> KV;
+}
+
+impl<C, P, S> Step<P, S> for C
+where
+    C: Calculation<P, S>,
+    S: State,
+{
+    fn initialise(&mut self, problem: &mut Problem<P>, state: S) -> Result<S, Box<dyn Error>> {
+        Calculation::initialise(self, problem, state).map_err(|e| Box::new(e) as Box<dyn Error>)
+    }
+
+    fn next(&mut self, problem: &mut Problem<P>, state: S) -> Result<S, Box<dyn Error>> {
+        Calculation::next(self, problem, state).map_err(|e| Box::new(e) as Box<dyn Error>)
+    }
+
+    fn grade(&self, state: &S) -> Grade {
+        Calculation::grade(self, state)
+    }
+
+    fn report(&self, state: &S) -> KV {
+        Calculation::report(self, state)
+    }
+}
+
+/// The stages of a pipeline, with their names
+type Steps<P, S> = Vec<(&'static str, Box<dyn Step<P, S>>)>;
+
+/// Calculations run in sequence, each continuing from the state the last terminated with.
+///
+/// When a stage terminates the state is prepared with [`State::for_warm_start`], and the next
+/// stage initialises it at the start of the following iteration. States must clear their termination reason there, or the pipeline ends with
+/// the first stage. A run terminated by the runner, for example by a controller or a predicate,
+/// ends the whole pipeline.
+///
+/// The pipeline finalises to the state of the last stage. The stages completed are described by
+/// [`Pipeline::stages`], available from the [`Output`](crate::Output) of the run.
+pub struct Pipeline<P, S> {
+    steps: Steps<P, S>,
+    current: usize,
+    first_iteration: usize,
+    /// Why the current stage terminated, once it has, until the next stage takes over
+    handover: Option<Option<Reason>>,
+    stages: Vec<StageSummary>,
+}
+
+impl<P, S> Pipeline<P, S>
+where
+    S: State,
+{
+    /// A pipeline whose first stage, called `name`, runs `calculation`
+    pub fn starting_with<C>(name: &'static str, calculation: C) -> Self
+    where
+        C: Calculation<P, S> + 'static,
+    {
+        Self {
+            steps: vec![(name, Box::new(calculation))],
+            current: 0,
+            first_iteration: 0,
+            handover: None,
+            stages: vec![],
+        }
+    }
+
+    /// Run `calculation` as the stage called `name` once the stages before it terminate
+    #[must_use]
+    pub fn then<C>(mut self, name: &'static str, calculation: C) -> Self
+    where
+        C: Calculation<P, S> + 'static,
+    {
+        self.steps.push((name, Box::new(calculation)));
+        self
+    }
+
+    /// The stages completed so far, in order.
+    ///
+    /// A stage is complete once it terminates. The stage in progress when a run is stopped by
+    /// the runner, rather than by a stage terminating, is also recorded when the run is finalised
+    /// with [`Runner::run`](crate::Runner::run).
+    pub fn stages(&self) -> &[StageSummary] {
+        &self.stages
+    }
+
+    /// Record the current stage as complete, its last iteration being `last_iteration`
+    fn complete_stage(&mut self, reason: Option<Reason>, last_iteration: usize, best_measure: f64) {
+        let name = self.steps[self.current].0;
+        let iterations = last_iteration - self.first_iteration;
+        info!(stage = name, iterations, "pipeline stage complete");
+        self.stages.push(StageSummary {
+            name,
+            first_iteration: self.first_iteration,
+            iterations,
+            reason,
+            best_measure,
+        });
+        self.first_iteration = last_iteration;
+    }
+
+    fn failed(&self, source: Box<dyn Error>) -> PipelineError {
+        PipelineError {
+            stage: self.steps[self.current].0,
+            source,
+        }
+    }
+}
+
+impl<P, S> Calculation<P, S> for Pipeline<P, S>
+where
+    S: State,
+{
+    type Error = PipelineError;
+    type Output = S;
+    const NAME: &'static str = "pipeline";
+
+    fn ident(&self) -> &'static str {
+        self.steps[self.current].0
+    }
+
+    fn initialise(&mut self, problem: &mut Problem<P>, state: S) -> Result<S, Self::Error> {
+        self.current = 0;
+        self.first_iteration = state.current_iteration();
+        self.handover = None;
+        self.stages.clear();
+        self.steps[0]
+            .1
+            .initialise(problem, state)
+            .map_err(|e| self.failed(e))
+    }
+
+    fn next(&mut self, problem: &mut Problem<P>, mut state: S) -> Result<S, Self::Error> {
+        // The next stage takes over at the start of the iteration after the last one terminated,
+        // so that iteration is observed and counted as part of the stage which made it
+        if let Some(reason) = self.handover.take() {
+            let best_measure = state.best_measure().real();
+            self.complete_stage(reason, state.current_iteration(), best_measure);
+            self.current += 1;
+            state = self.steps[self.current]
+                .1
+                .initialise(problem, state)
+                .map_err(|e| self.failed(e))?;
+        }
+
+        let state = self.steps[self.current]
+            .1
+            .next(problem, state)
+            .map_err(|e| self.failed(e))?;
+        if !state.is_terminated() {
+            return Ok(state);
+        }
+        if self.current + 1 < self.steps.len() {
+            self.handover = Some(state.termination_reason());
+            return Ok(state.for_warm_start());
+        }
+
+        // The runner counts and records this iteration once it returns
+        let best_measure = state.best_measure().real().min(state.measure().real());
+        self.complete_stage(
+            state.termination_reason(),
+            state.current_iteration() + 1,
+            best_measure,
+        );
+        Ok(state)
+    }
+
+    fn finalise(&mut self, _problem: &mut Problem<P>, state: S) -> Result<S, Self::Error> {
+        // A stage stopped by the runner rather than by terminating is recorded here
+        if self.stages.len() == self.current {
+            let reason = self
+                .handover
+                .take()
+                .unwrap_or_else(|| state.termination_reason());
+            let best_measure = state.best_measure().real();
+            self.complete_stage(reason, state.current_iteration(), best_measure);
+        }
+        Ok(state)
+    }
+
+    fn grade(&self, state: &S) -> Grade {
+        self.steps[self.current].1.grade(state)
+    }
+
+    fn report(&self, state: &S) -> KV {
+        self.steps[self.current].1.report(state)
+    }
+}
@@ -0,0 +1,178 @@
+use plotly::{
+    common::{Mode, Title},
+    layout::{themes::PLOTLY_DARK, Axis, AxisType},
+    Layout, Plot, Scatter,
+};
+use std::path::Path;
+
+use crate::{Measure, Output, State};
+
+#[cfg(feature = "writing")]
+use super::PlotterError;
+
+/// The scale of the measure axis of a [`ComparisonPlotter`]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum MeasureScale {
+    #[default]
+    Log,
+    Linear,
+}
+
+/// The convergence curve of a single run
+#[derive(Clone, Debug)]
+struct Curve {
+    label: String,
+    iterations: Vec<usize>,
+    measures: Vec<f64>,
+}
+
+/// Overlays the convergence curves of several runs on one figure.
+///
+/// Each run is drawn as a separate trace labelled with its run identifier, which makes it easy to
+/// compare solver variants on the same problem.
+#[derive(Clone, Debug)]
+pub struct ComparisonPlotter {
+    title: String,
+    x_label: String,
+    y_label: String,
+    scale: MeasureScale,
+    curves: Vec<Curve>,
+}
+
+impl ComparisonPlotter {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            x_label: "Iteration".to_owned(),
+            y_label: "Measure".to_owned(),
+            scale: MeasureScale::default(),
+            curves: vec![],
+        }
+    }
+
+    #[must_use]
+    pub fn with_scale(mut self, scale: MeasureScale) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    #[must_use]
+    pub fn with_axis_labels(
+        mut self,
+        x_label: impl Into<String>,
+        y_label: impl Into<String>,
+    ) -> Self {
+        self.x_label = x_label.into();
+        self.y_label = y_label.into();
+        self
+    }
+
+    /// Add the measure history of a completed run, labelled with its run identifier.
+    ///
+    /// The run must have recorded its history with
+    /// [`Builder::record_history`](crate::Builder::record_history).
+    #[must_use]
+    pub fn with_output<C, P, S: State>(self, output: &Output<C, P, S>) -> Self {
+        // The history ends at the final iteration, which need not be its length if the run was
+        // warm started
+        let history = output.history();
+        let first = (output.state.current_iteration() + 1).saturating_sub(history.len());
+        let label = output.metadata().run_id.to_string();
+        self.with_curve(label, (first..).zip(history.iter().copied()))
+    }
+
+    /// Add a curve from iteration and measure pairs
+    #[must_use]
+    pub fn with_curve<F: Measure>(
+        mut self,
+        label: impl Into<String>,
+        points: impl IntoIterator<Item = (usize, F)>,
+    ) -> Self {
+        let (iterations, measures) = points
+            .into_iter()
+            .map(|(iteration, measure)| (iteration, measure.real()))
+            .unzip();
+        self.curves.push(Curve {
+            label: label.into(),
+            iterations,
+            measures,
+        });
+        self
+    }
+
+    /// Add every run recorded in a log written by [`JsonLinesLogger`](crate::JsonLinesLogger).
+    ///
+    /// Runs are told apart by their run identifier. Events without one are labelled with the
+    /// name of the calculation.
+    #[cfg(feature = "writing")]
+    pub fn with_journal(mut self, path: impl AsRef<Path>) -> Result<Self, PlotterError> {
+        use std::io::BufRead;
+
+        let file = std::io::BufReader::new(fs_err::File::open(path.as_ref())?);
+        for line in file.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: serde_json::Value = serde_json::from_str(&line)?;
+            if event["stage"] != "Iteration" {
+                continue;
+            }
+            let (Some(iteration), Some(measure)) =
+                (event["iteration"].as_u64(), event["measure"].as_f64())
+            else {
+                continue;
+            };
+            let label = event["run_id"]
+                .as_str()
+                .or_else(|| event["calculation"].as_str())
+                .unwrap_or("unknown");
+            let curve = match self.curves.iter_mut().position(|c| c.label == label) {
+                Some(index) => &mut self.curves[index],
+                None => {
+                    self.curves.push(Curve {
+                        label: label.to_owned(),
+                        iterations: vec![],
+                        measures: vec![],
+                    });
+                    self.curves.last_mut().unwrap()
+                }
+            };
+            curve.iterations.push(iteration as usize);
+            curve.measures.push(measure);
+        }
+        Ok(self)
+    }
+
+    fn to_layout(&self) -> Layout {
+        let x_axis = Axis::new().title(Title::new(&format!("<b>{}</b>", self.x_label)));
+        let y_axis = Axis::new()
+            .type_(match self.scale {
+                MeasureScale::Log => AxisType::Log,
+                MeasureScale::Linear => AxisType::Linear,
+            })
+            .title(Title::new(&format!("<b>{}</b>", self.y_label)));
+
+        Layout::new()
+            .template(&*PLOTLY_DARK)
+            .x_axis(x_axis)
+            .y_axis(y_axis)
+            .show_legend(true)
+            .title(Title::new(&format!("<b>{}</b>", self.title)))
+            .width(1000)
+            .height(1000)
+    }
+
+    /// Write the figure as an HTML file
+    pub fn write_html(&self, path: impl AsRef<Path>) {
+        let mut plot = Plot::new();
+        for curve in &self.curves {
+            let trace = Scatter::new(curve.iterations.clone(), curve.measures.clone())
+                .mode(Mode::Lines)
+                .name(&curve.label);
+            plot.add_trace(trace);
+        }
+        plot.set_layout(self.to_layout());
+        plot.write_html(path.as_ref());
+    }
+}
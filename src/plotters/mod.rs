@@ -9,12 +9,20 @@ use serde::Serialize;
 use std::ops::Range;
 use std::path::PathBuf;
 
-use crate::state::TrellisFloat;
+use crate::state::Measure;
+
+mod comparison;
+pub use comparison::{ComparisonPlotter, MeasureScale};
 
 #[derive(Debug, thiserror::Error)]
 pub enum PlotterError {
     #[error("dimensional mismatch in plot variables")]
     DimensionMismatch,
+    #[error("error reading journal: {0}")]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "writing")]
+    #[error("malformed journal entry: {0}")]
+    Malformed(#[from] serde_json::Error),
 }
 
 pub trait PlottableLine<'a, R> {
@@ -34,6 +42,20 @@ pub trait PlottableHeatmap<'a, R> {
     fn identifier(&'a self) -> &'a str;
 }
 
+/// When a plot is written to disk
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum RenderMode {
+    /// Rewrite the plot whenever data is added.
+    ///
+    /// Each write renders every point so far, so the total output grows quadratically with the
+    /// length of the run.
+    #[default]
+    Immediate,
+    /// Accumulate data in memory, rendering every `every` additions if set and when the run is
+    /// finalised
+    Buffered { every: Option<usize> },
+}
+
 #[derive(Clone, Debug)]
 pub struct PlotConfig<R> {
     pub x_limits: Range<R>,
@@ -41,9 +63,10 @@ pub struct PlotConfig<R> {
     pub x_label: String,
     pub y_label: String,
     pub title: String,
+    pub render: RenderMode,
 }
 
-impl<F: TrellisFloat> PlotConfig<F> {
+impl<F: Measure> PlotConfig<F> {
     fn to_layout_scatter(&self) -> Layout {
         let x_axis = Axis::new()
             .range(vec![
@@ -91,6 +114,8 @@ pub struct Plotter<R> {
     config: PlotConfig<R>,
     grid_points: Array1<R>,
     data: Option<MeasureData<R>>,
+    /// Additions since the plot was last written
+    unwritten: usize,
 }
 
 #[derive(Clone)]
@@ -108,7 +133,7 @@ impl<R> MeasureData<R> {
 
 impl<R> Plotter<R>
 where
-    R: Clone + Default + PartialOrd + Serialize + TrellisFloat + 'static,
+    R: Clone + Default + PartialOrd + Serialize + Measure + 'static,
 {
     pub(crate) fn new(
         mut output_directory: PathBuf,
@@ -125,9 +150,44 @@ where
                 .map(|nodes| nodes.to_owned())
                 .unwrap_or(Array1::default(0)),
             data: None,
+            unwritten: 0,
+        }
+    }
+
+    pub(crate) fn output_path(&self) -> &PathBuf {
+        &self.output_path
+    }
+
+    // Write the plot if the render mode calls for it after an addition
+    fn added(&mut self) {
+        self.unwritten += 1;
+        let due = match self.config.render {
+            RenderMode::Immediate => true,
+            RenderMode::Buffered { every: Some(every) } => self.unwritten >= every,
+            RenderMode::Buffered { every: None } => false,
+        };
+        if due {
+            self.flush();
         }
     }
 
+    /// Write any data not yet rendered to disk
+    pub(crate) fn flush(&mut self) {
+        if self.unwritten == 0 {
+            return;
+        }
+        if let Some(data) = self.data.as_ref() {
+            let trace = Scatter::new(data.x.clone(), data.y.clone())
+                .mode(plotly::common::Mode::Markers) // Set the marker mode
+                .marker(Marker::new().size(10).color(NamedColor::ForestGreen)); // Set the marker size
+            self.plot = Plot::new();
+            self.plot.add_trace(trace);
+            self.plot.set_layout(self.config.to_layout_scatter());
+        }
+        self.plot.write_html(&self.output_path);
+        self.unwritten = 0;
+    }
+
     pub(crate) fn plot_point(&mut self, iteration: usize, point: R) -> Result<(), PlotterError> {
         if let Some(data) = self.data.as_mut() {
             data.extend(iteration, point);
@@ -137,13 +197,7 @@ where
                 y: vec![point],
             });
         }
-        let trace = Scatter::new(self.data.clone().unwrap().x, self.data.clone().unwrap().y)
-            .mode(plotly::common::Mode::Markers) // Set the marker mode
-            .marker(Marker::new().size(10).color(NamedColor::ForestGreen)); // Set the marker size
-        self.plot = Plot::new();
-        self.plot.add_trace(trace);
-        self.plot.set_layout(self.config.to_layout_scatter());
-        self.plot.write_html(&self.output_path);
+        self.added();
         Ok(())
     }
 
@@ -158,7 +212,7 @@ where
                     .name(item.identifier());
             self.plot.add_trace(trace);
             self.plot.set_layout(self.config.to_layout());
-            self.plot.write_html(&self.output_path);
+            self.added();
             return Ok(());
         }
 
@@ -180,13 +234,34 @@ where
             .name(item.identifier());
             self.plot.add_trace(trace);
             self.plot.set_layout(self.config.to_layout());
-            self.plot.write_html(&self.output_path);
+            self.added();
             return Ok(());
         }
 
         Err(PlotterError::DimensionMismatch)
     }
 
+    /// Replace the plot with a heatmap of a field over the full grid
+    pub(crate) fn plot_heatmap<'a, P: PlottableHeatmap<'a, R>>(
+        &mut self,
+        item: &'a P,
+    ) -> Result<(), PlotterError> {
+        let independent_variable: ArrayView1<'a, R> = item.independent_variable();
+        let heatmap: ArrayView2<'a, R> = item.heatmap();
+        if heatmap.shape() != [self.grid_points.len(), independent_variable.len()] {
+            return Err(PlotterError::DimensionMismatch);
+        }
+        let z: Vec<Vec<R>> = heatmap.columns().into_iter().map(|c| c.to_vec()).collect();
+        let trace = Contour::new(self.grid_points.to_vec(), independent_variable.to_vec(), z)
+            .name(item.identifier());
+        // Only the latest field is of interest, so it replaces any earlier one
+        self.plot = Plot::new();
+        self.plot.add_trace(trace);
+        self.plot.set_layout(self.config.to_layout());
+        self.added();
+        Ok(())
+    }
+
     pub(crate) fn plot_heatmap_internal<'a, P: PlottableHeatmap<'a, R>>(
         &mut self,
         item: &'a P,
@@ -207,7 +282,7 @@ where
             let trace = Contour::new(x, y, z).name(item.identifier());
             self.plot.add_trace(trace);
             self.plot.set_layout(self.config.to_layout());
-            self.plot.write_html(&self.output_path);
+            self.added();
             return Ok(());
         }
 
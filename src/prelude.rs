@@ -1,23 +1,80 @@
 pub use crate::Calculation;
+pub use crate::Cancellation;
+pub use crate::ChannelObserver;
+pub use crate::WarmCache;
+
+#[cfg(feature = "dashboard")]
+pub use crate::Dashboard;
+
+#[cfg(feature = "energy")]
+pub use crate::{EnergyMeter, EnergySource};
 
 #[cfg(feature = "writing")]
-pub use crate::FileWriter;
+pub use crate::{
+    ArrayFormat, ArrayWriter, Checkpointer, Compression, FileWriter, FsyncPolicy, Rotation,
+};
 
-pub use crate::Frequency;
+#[cfg(feature = "encryption")]
+pub use crate::EncryptionKey;
+
+pub use crate::Extensions;
+pub use crate::Flags;
 pub use crate::GenerateBuilder;
+pub use crate::Grade;
+pub use crate::{Frequency, FrequencySet};
+pub use crate::{Observer, ObserverHandle, ObserverId};
+
+#[cfg(feature = "writing")]
+pub use crate::JsonLinesLogger;
+
+pub use crate::Interleave;
+pub use crate::Lockstep;
+pub use crate::KV;
+pub use crate::{clear_default_observers, register_default_observer};
+
+#[cfg(feature = "metrics")]
+pub use crate::MetricsPublisher;
+
+#[cfg(feature = "otel")]
+pub use crate::OtelMetrics;
+
+#[cfg(feature = "parquet")]
+pub use crate::ParquetWriter;
 
 #[cfg(feature = "plotting")]
-pub use crate::PlotConfig;
+pub use crate::{ComparisonPlotter, MeasureScale, PlotConfig, RenderMode};
 
 #[cfg(feature = "plotting")]
 pub use crate::PlotGenerator;
 
+pub use crate::Plugin;
 pub use crate::Problem;
+
+#[cfg(feature = "tokio")]
+pub use crate::ProgressSnapshot;
+
 pub use crate::Reason;
+pub use crate::RingFile;
+pub use crate::{Report, ReportFormat};
+
+#[cfg(feature = "slog")]
+pub use crate::SlogLogger;
+
+#[cfg(feature = "log")]
+pub use crate::LogLogger;
+
+#[cfg(feature = "uom")]
+pub use crate::SiMeasure;
+
+pub use crate::RunnerError;
+pub use crate::Stage;
 pub use crate::State;
 pub use crate::Status;
 pub use crate::Target;
-pub use crate::Tracer;
+#[cfg(feature = "writing")]
+pub use crate::Telemetry;
+pub use crate::TrellisError;
+pub use crate::{Tracer, TracerConfig};
 
 #[cfg(feature = "writing")]
 pub use crate::WriteToFileSerializer;
@@ -1,11 +1,145 @@
-pub struct Problem<P>(P);
+use std::any::Any;
+
+use crate::{CancelChecker, Flags, WarmCache};
+
+pub struct Problem<P> {
+    inner: P,
+    flags: Flags,
+    cache: Option<WarmCache>,
+    evaluations: Option<u64>,
+    cancel: CancelChecker,
+    parallel: bool,
+}
 
 impl<P> Problem<P> {
     pub(crate) fn new(inner: P) -> Self {
-        Self(inner)
+        Self::with_flags(inner, Flags::default())
+    }
+
+    pub(crate) fn with_flags(inner: P, flags: Flags) -> Self {
+        Self {
+            inner,
+            flags,
+            cache: None,
+            evaluations: None,
+            cancel: CancelChecker::default(),
+            parallel: false,
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn with_cache(mut self, cache: Option<WarmCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    #[must_use]
+    pub(crate) fn with_parallel_evaluations(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    pub(crate) fn set_cancel_checker(&mut self, cancel: CancelChecker) {
+        self.cancel = cancel;
     }
 
     pub fn as_ref(&self) -> &P {
-        &self.0
+        &self.inner
+    }
+
+    /// The feature flag of type `T` set on the builder, if any
+    pub fn flag<T: Any>(&self) -> Option<&T> {
+        self.flags.get()
+    }
+
+    /// All feature flags set on the builder
+    pub fn flags(&self) -> &Flags {
+        &self.flags
+    }
+
+    /// The cache shared with related runs, if one was set on the builder
+    pub fn cache(&self) -> Option<&WarmCache> {
+        self.cache.as_ref()
+    }
+
+    /// A handle reporting whether the run has been cancelled, for polling within an iteration.
+    ///
+    /// It observes the run's controllers and Ctrl-C handler once the runner is finalised, and
+    /// is never cancelled before.
+    pub fn cancel_checker(&self) -> &CancelChecker {
+        &self.cancel
+    }
+
+    /// Count `count` evaluations of the problem, for the run's
+    /// [resource ledger](crate::ResourceLedger) and any
+    /// [evaluation budget](crate::State::max_evaluations)
+    pub fn record_evaluations(&mut self, count: u64) {
+        *self.evaluations.get_or_insert(0) += count;
+    }
+
+    /// The evaluations counted with [`Problem::record_evaluations`], if any were
+    pub fn evaluations(&self) -> Option<u64> {
+        self.evaluations
+    }
+
+    /// Evaluate every member of a population, in parallel if enabled with
+    /// [`Builder::parallel_evaluations`](crate::Builder::parallel_evaluations).
+    ///
+    /// Members are evaluated in chunks, checking between chunks whether the run has been
+    /// cancelled. If it has, `None` is returned once the chunk in progress finishes, and the
+    /// calculation should return without updating its state so the runner can stop. Every
+    /// member evaluated is counted as with [`Problem::record_evaluations`].
+    ///
+    /// ```ignore
+    /// let Some(fitness) = problem.evaluate_population(&state.members, |problem, member| {
+    ///     problem.objective(member)
+    /// }) else {
+    ///     return Ok(state);
+    /// };
+    /// ```
+    pub fn evaluate_population<X, Y, F>(&mut self, members: &[X], evaluate: F) -> Option<Vec<Y>>
+    where
+        P: Sync,
+        X: Sync,
+        Y: Send,
+        F: Fn(&P, &X) -> Y + Sync,
+    {
+        let mut values = Vec::with_capacity(members.len());
+        for chunk in members.chunks(self.population_chunk()) {
+            if self.cancel.is_cancelled() {
+                return None;
+            }
+            self.evaluate_chunk(chunk, &evaluate, &mut values);
+            self.record_evaluations(chunk.len() as u64);
+        }
+        Some(values)
+    }
+
+    /// The number of members evaluated between checks for cancellation.
+    ///
+    /// Parallel chunks hold a few members for every thread, so threads finishing cheap members
+    /// early can take more rather than wait on the most expensive.
+    fn population_chunk(&self) -> usize {
+        #[cfg(feature = "rayon")]
+        if self.parallel {
+            return 4 * rayon::current_num_threads().max(1);
+        }
+        1
+    }
+
+    fn evaluate_chunk<X, Y, F>(&self, chunk: &[X], evaluate: &F, values: &mut Vec<Y>)
+    where
+        P: Sync,
+        X: Sync,
+        Y: Send,
+        F: Fn(&P, &X) -> Y + Sync,
+    {
+        #[cfg(feature = "rayon")]
+        if self.parallel {
+            use rayon::prelude::*;
+            values.par_extend(chunk.par_iter().map(|member| evaluate(&self.inner, member)));
+            return;
+        }
+        values.extend(chunk.iter().map(|member| evaluate(&self.inner, member)));
     }
 }
@@ -0,0 +1,350 @@
+//! Replaying recorded runs.
+//!
+//! A [`JsonLinesLogger`](crate::JsonLinesLogger) records the measures of every iteration it
+//! observes. A [`ReplayCalculation`] built from that record re-drives a runner through the same
+//! iterations without repeating the computation, so observers added after the fact, such as a new
+//! plot or report, can be run against an expensive calculation which has already finished.
+//!
+//! Replays are deterministic. The runner reads the time from a [`MockClock`] which the replay
+//! moves to the time recorded for each iteration, so timestamps and durations seen by observers
+//! match the original run.
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::convert::Infallible;
+use std::io::BufRead;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{watchers::Stage, Calculation, MockClock, Problem, Reason, RunId, State, KV};
+
+/// Error raised while reading a journal
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("failed to read the journal: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("malformed journal entry on line {line}: {source}")]
+    Malformed {
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("the journal records no iterations")]
+    Empty,
+    #[error("the journal records {0} runs, read them with `Journal::read_runs`")]
+    MultipleRuns(usize),
+}
+
+/// A line of the journal, as written by the `JsonLinesLogger`
+#[derive(Deserialize)]
+struct Line {
+    calculation: String,
+    run_id: Option<RunId>,
+    stage: Stage,
+    iteration: usize,
+    measure: f64,
+    best_measure: f64,
+    elapsed: f64,
+    timestamp: f64,
+    reason: Option<Reason>,
+    #[serde(flatten)]
+    fields: Map<String, Value>,
+}
+
+/// One recorded iteration
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalEntry {
+    pub iteration: usize,
+    pub measure: f64,
+    pub best_measure: f64,
+    /// Time since the run started
+    pub elapsed: Duration,
+    /// Fields added to the journal with `JsonLinesLogger::with_field`
+    pub kv: KV,
+}
+
+/// The recorded iterations of a single run
+#[derive(Clone, Debug)]
+pub struct Journal {
+    calculation: String,
+    run_id: Option<RunId>,
+    started: Option<SystemTime>,
+    /// The measure and best measure at initialisation
+    initial: Option<(f64, f64)>,
+    entries: Vec<JournalEntry>,
+    reason: Option<Reason>,
+}
+
+impl Journal {
+    fn begin(line: &Line) -> Self {
+        let started = line.timestamp - line.elapsed;
+        Self {
+            calculation: line.calculation.clone(),
+            run_id: line.run_id.clone(),
+            started: (started >= 0.0).then(|| UNIX_EPOCH + Duration::from_secs_f64(started)),
+            initial: None,
+            entries: vec![],
+            reason: None,
+        }
+    }
+
+    /// Read the journal of a single run from the file at `path`
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let file = fs_err::File::open(path.as_ref())?;
+        Self::from_reader(std::io::BufReader::new(file))
+    }
+
+    /// Read the journal of a single run
+    pub fn from_reader(reader: impl BufRead) -> Result<Self, ReplayError> {
+        let mut runs = Self::runs_from_reader(reader)?;
+        match runs.len() {
+            0 => Err(ReplayError::Empty),
+            1 => Ok(runs.remove(0)),
+            n => Err(ReplayError::MultipleRuns(n)),
+        }
+    }
+
+    /// Read every run recorded in the file at `path`
+    pub fn read_runs(path: impl AsRef<Path>) -> Result<Vec<Self>, ReplayError> {
+        let file = fs_err::File::open(path.as_ref())?;
+        Self::runs_from_reader(std::io::BufReader::new(file))
+    }
+
+    /// Read every run in a journal.
+    ///
+    /// A new run starts at each initialisation, or wherever the recorded run identifier changes.
+    /// Runs without recorded iterations are skipped.
+    pub fn runs_from_reader(reader: impl BufRead) -> Result<Vec<Self>, ReplayError> {
+        let mut runs: Vec<Self> = vec![];
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line: Line =
+                serde_json::from_str(&line).map_err(|source| ReplayError::Malformed {
+                    line: index + 1,
+                    source,
+                })?;
+            let starts_run = match runs.last() {
+                None => true,
+                Some(run) => line.stage == Stage::Initialisation || run.run_id != line.run_id,
+            };
+            if starts_run {
+                runs.push(Self::begin(&line));
+            }
+            let run = runs.last_mut().unwrap();
+            match line.stage {
+                Stage::Initialisation => run.initial = Some((line.measure, line.best_measure)),
+                Stage::Iteration => run.entries.push(JournalEntry {
+                    iteration: line.iteration,
+                    measure: line.measure,
+                    best_measure: line.best_measure,
+                    elapsed: Duration::from_secs_f64(line.elapsed.max(0.0)),
+                    kv: line
+                        .fields
+                        .iter()
+                        .fold(KV::new(), |kv, (key, value)| match value {
+                            Value::String(value) => kv.with(key, value),
+                            value => kv.with(key, value),
+                        }),
+                }),
+                Stage::Restart => {}
+                Stage::Termination | Stage::Finalisation | Stage::Aborted => {
+                    if let Some(reason) = line.reason {
+                        run.reason = Some(reason);
+                    }
+                }
+            }
+        }
+        runs.retain(|run| !run.entries.is_empty());
+        Ok(runs)
+    }
+
+    /// The name of the calculation which was recorded
+    pub fn calculation(&self) -> &str {
+        &self.calculation
+    }
+
+    pub fn run_id(&self) -> Option<&RunId> {
+        self.run_id.as_ref()
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Why the recorded run terminated, if the journal includes its finalisation
+    pub fn reason(&self) -> Option<&Reason> {
+        self.reason.as_ref()
+    }
+}
+
+/// A [`Calculation`] which replays the iterations recorded in a [`Journal`].
+///
+/// Each iteration sets the state to the next recorded entry. After the last entry the run
+/// terminates for the recorded reason, or with [`Reason::ExceededMaxIterations`] if the journal
+/// ends before the run finished. For recorded times to be reproduced the runner must read the
+/// time from the replay's clock:
+///
+/// ```ignore
+/// let replay = ReplayCalculation::new(Journal::read("run.jsonl")?);
+/// let clock = replay.clock();
+/// let runner = replay.build_for(()).with_clock(clock).attach_observer(..).finalise()?;
+/// ```
+///
+/// Observers are notified under the name `"replay"`. The original name is available from
+/// [`Journal::calculation`].
+pub struct ReplayCalculation {
+    journal: Journal,
+    cursor: usize,
+    clock: MockClock,
+}
+
+impl ReplayCalculation {
+    pub fn new(journal: Journal) -> Self {
+        let clock = MockClock::starting_at(journal.started.unwrap_or(UNIX_EPOCH));
+        Self {
+            journal,
+            cursor: 0,
+            clock,
+        }
+    }
+
+    /// The clock the replay moves through the recorded times
+    pub fn clock(&self) -> MockClock {
+        self.clock.clone()
+    }
+
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+}
+
+impl Calculation<(), ReplayState> for ReplayCalculation {
+    type Error = Infallible;
+    type Output = ReplayState;
+    const NAME: &'static str = "replay";
+
+    fn initialise(
+        &mut self,
+        _problem: &mut Problem<()>,
+        mut state: ReplayState,
+    ) -> Result<ReplayState, Self::Error> {
+        self.cursor = 0;
+        if let Some((measure, best_measure)) = self.journal.initial {
+            state.measure = measure;
+            state.best_measure = best_measure;
+        }
+        state.initialised = true;
+        Ok(state)
+    }
+
+    fn next(
+        &mut self,
+        _problem: &mut Problem<()>,
+        mut state: ReplayState,
+    ) -> Result<ReplayState, Self::Error> {
+        let Some(entry) = self.journal.entries.get(self.cursor) else {
+            let reason = self
+                .journal
+                .reason
+                .clone()
+                .unwrap_or(Reason::ExceededMaxIterations);
+            return Ok(state.terminate_due_to(reason));
+        };
+        self.cursor += 1;
+        self.clock
+            .advance(entry.elapsed.saturating_sub(self.clock.elapsed()));
+
+        // The runner increments the iteration after each step, and a journal written by a
+        // sampling observer need not record every iteration
+        state.iteration = entry.iteration.saturating_sub(1);
+        state.measure = entry.measure;
+        state.best_measure = entry.best_measure;
+        state.kv = entry.kv.clone();
+        if self.cursor == self.journal.entries.len() {
+            if let Some(reason) = self.journal.reason.clone() {
+                state = state.terminate_due_to(reason);
+            }
+        }
+        Ok(state)
+    }
+
+    fn finalise(
+        &mut self,
+        _problem: &mut Problem<()>,
+        state: ReplayState,
+    ) -> Result<Self::Output, Self::Error> {
+        Ok(state)
+    }
+}
+
+/// The state of a replayed run.
+///
+/// The run identifier is that of the replay. The recorded one is available from
+/// [`Journal::run_id`].
+#[derive(Clone, Debug, Default)]
+pub struct ReplayState {
+    iteration: usize,
+    measure: f64,
+    best_measure: f64,
+    /// The best measure, and the iteration at which it was recorded
+    last_best: Option<(f64, usize)>,
+    termination_reason: Option<Reason>,
+    run_id: Option<RunId>,
+    kv: KV,
+    initialised: bool,
+}
+
+impl State for ReplayState {
+    type Float = f64;
+    type Param = ();
+    fn record_time(&mut self, _duration: hifitime::Duration) {}
+    fn increment_iteration(&mut self) {
+        self.iteration += 1;
+    }
+    fn current_iteration(&self) -> usize {
+        self.iteration
+    }
+    fn update(mut self) -> Self {
+        if self.last_best.map(|(best, _)| best) != Some(self.best_measure) {
+            self.last_best = Some((self.best_measure, self.iteration));
+        }
+        self
+    }
+    fn is_initialised(&self) -> bool {
+        self.initialised
+    }
+    fn is_terminated(&self) -> bool {
+        self.termination_reason.is_some()
+    }
+    fn terminate_due_to(mut self, reason: Reason) -> Self {
+        self.termination_reason = Some(reason);
+        self
+    }
+    fn termination_reason(&self) -> Option<Reason> {
+        self.termination_reason.clone()
+    }
+    fn get_param(&self) -> Option<&Self::Param> {
+        None
+    }
+    fn measure(&self) -> Self::Float {
+        self.measure
+    }
+    fn best_measure(&self) -> Self::Float {
+        self.best_measure
+    }
+    fn iterations_since_best(&self) -> usize {
+        self.last_best
+            .map_or(0, |(_, iteration)| self.iteration - iteration)
+    }
+    fn kv(&self) -> KV {
+        self.kv.clone()
+    }
+    fn set_run_id(&mut self, run_id: RunId) {
+        self.run_id = Some(run_id);
+    }
+    fn run_id(&self) -> Option<&RunId> {
+        self.run_id.as_ref()
+    }
+}
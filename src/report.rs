@@ -0,0 +1,220 @@
+//! Summary reports of completed runs.
+//!
+//! A [`Report`] collects the facts a command line application usually prints when a run exits,
+//! and renders them as Markdown, JSON or plain text.
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::{ConvergenceReport, Grade, Measure, Output, Reason, ResourceLedger, RunId, State, KV};
+
+/// The format a [`Report`] is rendered in
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ReportFormat {
+    Markdown,
+    #[cfg(feature = "writing")]
+    Json,
+    Text,
+}
+
+/// A structured summary of a run
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    /// Name of the calculation
+    pub calculation: String,
+    /// Identifier of the run
+    pub run_id: RunId,
+    /// Quality of the final state
+    pub grade: Grade,
+    /// Why the run terminated, if the state records it
+    pub termination: Option<Reason>,
+    /// The last iteration completed
+    pub iterations: usize,
+    /// Wall time of the run in seconds, if the run was timed
+    pub wall_time: Option<f64>,
+    /// The measure of the final state
+    pub final_measure: f64,
+    /// The best measure found
+    pub best_measure: f64,
+    /// The estimated rate of convergence
+    pub convergence: Option<ConvergenceReport>,
+    /// Additional values reported by the state, such as evaluation counts
+    pub kv: KV,
+    /// The resources the run consumed, if they were accounted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourceLedger>,
+    /// The measure at every iteration, if included
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history: Option<Vec<f64>>,
+}
+
+impl Report {
+    /// Summarise the output of a run
+    pub fn new<C, P, S: State>(output: &Output<C, P, S>) -> Self {
+        let metadata = output.metadata();
+        Self {
+            calculation: metadata.calculation.clone(),
+            run_id: metadata.run_id.clone(),
+            grade: output.grade(),
+            termination: output.state.termination_reason(),
+            iterations: output.state.current_iteration(),
+            wall_time: output.wall_time().map(|duration| duration.to_seconds()),
+            final_measure: output.state.measure().real(),
+            best_measure: output.state.best_measure().real(),
+            convergence: output.convergence_report(),
+            kv: KV::of(&output.state),
+            resources: Some(output.resources().clone()),
+            history: None,
+        }
+    }
+
+    /// Summarise a run from its final state and measure history, for observers which see the
+    /// state but not the output.
+    ///
+    /// The grade is assessed from the state with [`Grade::from_state`], and the resources are
+    /// those the runner accounted when it finalised the run.
+    pub(crate) fn from_state<S: State>(
+        calculation: &str,
+        state: &S,
+        wall_time: Option<f64>,
+        history: &[f64],
+    ) -> Self {
+        Self {
+            calculation: calculation.to_owned(),
+            run_id: state.run_id().cloned().unwrap_or_else(RunId::unrecorded),
+            grade: Grade::from_state(state),
+            termination: state.termination_reason(),
+            iterations: state.current_iteration(),
+            wall_time,
+            final_measure: state.measure().real(),
+            best_measure: state.best_measure().real(),
+            convergence: ConvergenceReport::estimate(history),
+            kv: KV::of(state),
+            resources: state
+                .extensions()
+                .and_then(|extensions| extensions.get::<ResourceLedger>())
+                .cloned(),
+            history: Some(history.to_vec()),
+        }
+    }
+
+    /// Include the measure at every iteration in the report
+    #[must_use]
+    pub fn with_history<C, P, S>(mut self, output: &Output<C, P, S>) -> Self {
+        self.history = Some(output.history().to_vec());
+        self
+    }
+
+    /// Render the report in the given format
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            #[cfg(feature = "writing")]
+            ReportFormat::Json => {
+                serde_json::to_string_pretty(self).expect("report must serialize to JSON")
+            }
+            ReportFormat::Markdown => self.render_markdown(),
+            ReportFormat::Text => self.render_text(),
+        }
+    }
+
+    fn rows(&self) -> Vec<(String, String)> {
+        let mut rows = vec![
+            ("run".to_owned(), self.run_id.to_string()),
+            ("grade".to_owned(), self.grade.to_string()),
+            (
+                "termination".to_owned(),
+                self.termination
+                    .as_ref()
+                    .map_or_else(|| "-".to_owned(), ToString::to_string),
+            ),
+            ("iterations".to_owned(), self.iterations.to_string()),
+            (
+                "wall time".to_owned(),
+                self.wall_time
+                    .map_or_else(|| "-".to_owned(), |seconds| format!("{seconds:.3} s")),
+            ),
+            (
+                "final measure".to_owned(),
+                format!("{:e}", self.final_measure),
+            ),
+            (
+                "best measure".to_owned(),
+                format!("{:e}", self.best_measure),
+            ),
+        ];
+        if let Some(convergence) = self.convergence {
+            rows.push((
+                "convergence".to_owned(),
+                format!(
+                    "{:?}, order {:.2}, contraction {:.3e}",
+                    convergence.classification, convergence.order, convergence.contraction
+                ),
+            ));
+        }
+        if let Some(resources) = self.resources.as_ref() {
+            rows.push((
+                "compute time".to_owned(),
+                format!(
+                    "{:.3} s, observers {:.3} s",
+                    resources.compute_time, resources.observer_time
+                ),
+            ));
+            if let Some(evaluations) = resources.evaluations {
+                rows.push(("evaluations".to_owned(), evaluations.to_string()));
+            }
+            rows.push((
+                "bytes written".to_owned(),
+                resources.bytes_written.to_string(),
+            ));
+            if let Some(bytes) = resources.peak_rss_bytes {
+                rows.push(("peak memory".to_owned(), format!("{bytes} bytes")));
+            }
+            if let Some(joules) = resources.energy_joules {
+                rows.push(("energy".to_owned(), format!("{joules:.3} J")));
+            }
+        }
+        rows.extend(
+            self.kv
+                .iter()
+                .map(|(key, value)| (key.to_owned(), value.to_owned())),
+        );
+        rows
+    }
+
+    // The history ends at the final iteration, which need not be the history's length if the
+    // run was warm started
+    fn numbered<'a>(&self, history: &'a [f64]) -> impl Iterator<Item = (usize, &'a f64)> {
+        let first = (self.iterations + 1).saturating_sub(history.len());
+        (first..).zip(history)
+    }
+
+    fn render_markdown(&self) -> String {
+        let mut out = format!("## {}\n\n| | |\n|---|---|\n", self.calculation);
+        for (key, value) in self.rows() {
+            let _ = writeln!(out, "| {key} | {value} |");
+        }
+        if let Some(history) = self.history.as_ref() {
+            out.push_str("\n| iteration | measure |\n|---|---|\n");
+            for (iteration, measure) in self.numbered(history) {
+                let _ = writeln!(out, "| {iteration} | {measure:e} |");
+            }
+        }
+        out
+    }
+
+    fn render_text(&self) -> String {
+        let rows = self.rows();
+        let width = rows.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+        let mut out = format!("{}\n", self.calculation);
+        for (key, value) in rows {
+            let _ = writeln!(out, "  {key:<width$}  {value}");
+        }
+        if let Some(history) = self.history.as_ref() {
+            out.push_str("  history\n");
+            for (iteration, measure) in self.numbered(history) {
+                let _ = writeln!(out, "    {iteration:>6}  {measure:e}");
+            }
+        }
+        out
+    }
+}
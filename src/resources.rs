@@ -0,0 +1,124 @@
+//! Container resource limits.
+//!
+//! When running inside a container the process is usually constrained by a cgroup. Exceeding the
+//! memory limit gets the process killed by the kernel with no chance to record anything, so the
+//! runner reads the limits at initialisation and warns when memory usage approaches them.
+use serde::{Deserialize, Serialize};
+
+/// Resource limits imposed on the current process by its cgroup
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContainerLimits {
+    /// The number of CPUs the process may use, `None` if unlimited
+    pub cpus: Option<f64>,
+    /// The memory limit in bytes, `None` if unlimited
+    pub memory_bytes: Option<u64>,
+}
+
+impl ContainerLimits {
+    /// Read the limits for the current process.
+    ///
+    /// Both cgroup v2 and v1 hierarchies are supported. On other platforms, or when no cgroup is
+    /// mounted, every limit is `None`.
+    pub fn detect() -> Self {
+        Self {
+            cpus: imp::cpus(),
+            memory_bytes: imp::memory_limit(),
+        }
+    }
+
+    /// Whether any limit applies to the process
+    pub fn is_limited(&self) -> bool {
+        self.cpus.is_some() || self.memory_bytes.is_some()
+    }
+
+    /// The fraction of the memory limit currently in use
+    pub fn memory_usage_fraction(&self) -> Option<f64> {
+        let limit = self.memory_bytes?;
+        let current = imp::memory_current()?;
+        Some(current as f64 / limit as f64)
+    }
+}
+
+/// The peak resident memory of the process in bytes, if the platform reports it
+pub(crate) fn peak_rss_bytes() -> Option<u64> {
+    imp::peak_rss()
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::fs;
+
+    const ROOT: &str = "/sys/fs/cgroup";
+
+    fn read(path: &str) -> Option<String> {
+        fs::read_to_string(format!("{ROOT}/{path}"))
+            .ok()
+            .map(|contents| contents.trim().to_owned())
+    }
+
+    // Limits which are not set are reported as `max` in v2, and as a very large number in v1
+    fn parse_limit(value: &str) -> Option<u64> {
+        match value.parse::<u64>() {
+            Ok(limit) if limit < u64::MAX / 2 => Some(limit),
+            _ => None,
+        }
+    }
+
+    pub(super) fn cpus() -> Option<f64> {
+        if let Some(max) = read("cpu.max") {
+            let mut parts = max.split_whitespace();
+            let quota = parts.next()?.parse::<f64>().ok()?;
+            let period = parts.next()?.parse::<f64>().ok()?;
+            return Some(quota / period);
+        }
+        let quota = read("cpu/cpu.cfs_quota_us")?.parse::<f64>().ok()?;
+        let period = read("cpu/cpu.cfs_period_us")?.parse::<f64>().ok()?;
+        (quota > 0.0).then_some(quota / period)
+    }
+
+    pub(super) fn memory_limit() -> Option<u64> {
+        read("memory.max")
+            .or_else(|| read("memory/memory.limit_in_bytes"))
+            .as_deref()
+            .and_then(parse_limit)
+    }
+
+    pub(super) fn memory_current() -> Option<u64> {
+        read("memory.current")
+            .or_else(|| read("memory/memory.usage_in_bytes"))?
+            .parse()
+            .ok()
+    }
+
+    pub(super) fn peak_rss() -> Option<u64> {
+        let status = fs::read_to_string("/proc/self/status").ok()?;
+        let kilobytes: u64 = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmHWM:"))?
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse()
+            .ok()?;
+        Some(kilobytes * 1024)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    pub(super) fn cpus() -> Option<f64> {
+        None
+    }
+
+    pub(super) fn memory_limit() -> Option<u64> {
+        None
+    }
+
+    pub(super) fn memory_current() -> Option<u64> {
+        None
+    }
+
+    pub(super) fn peak_rss() -> Option<u64> {
+        None
+    }
+}
@@ -1,4 +1,9 @@
-use crate::Problem;
+use hifitime::Duration;
+
+use crate::{
+    ConvergenceReport, Grade, Problem, Reason, Report, ResourceLedger, RunMetadata, State,
+    Timestamp,
+};
 
 pub struct Output<C, P, S> {
     /// calculation
@@ -7,14 +12,126 @@ pub struct Output<C, P, S> {
     pub problem: Problem<P>,
     /// Iteration state
     pub state: S,
+    /// State at the iteration with the best measure, if it was captured
+    best_state: Option<S>,
+    /// Quality of the final state, as assessed by the calculation
+    grade: Grade,
+    /// Metadata describing the run
+    metadata: RunMetadata,
+    /// The measures of the most recent iterations, if they were recorded
+    history: Vec<f64>,
+    /// When each measure in the history was recorded
+    timestamps: Vec<Timestamp>,
+    /// Time taken by the run, if it was timed
+    wall_time: Option<Duration>,
+    /// What the run cost
+    resources: ResourceLedger,
 }
 
 impl<C, P, S> Output<C, P, S> {
-    pub(crate) fn new(problem: Problem<P>, calculation: C, state: S) -> Self {
+    pub(crate) fn new(
+        problem: Problem<P>,
+        calculation: C,
+        state: S,
+        grade: Grade,
+        metadata: RunMetadata,
+    ) -> Self {
         Self {
             problem,
             calculation,
             state,
+            best_state: None,
+            grade,
+            metadata,
+            history: vec![],
+            timestamps: vec![],
+            wall_time: None,
+            resources: ResourceLedger::default(),
         }
     }
+
+    pub(crate) fn with_best_state(mut self, best_state: Option<S>) -> Self {
+        self.best_state = best_state;
+        self
+    }
+
+    pub(crate) fn with_history(mut self, history: Vec<f64>, timestamps: Vec<Timestamp>) -> Self {
+        self.history = history;
+        self.timestamps = timestamps;
+        self
+    }
+
+    pub(crate) fn with_wall_time(mut self, wall_time: Option<Duration>) -> Self {
+        self.wall_time = wall_time;
+        self
+    }
+
+    pub(crate) fn with_resources(mut self, resources: ResourceLedger) -> Self {
+        self.resources = resources;
+        self
+    }
+
+    /// The state from the iteration with the best measure.
+    ///
+    /// This is only available when the runner was built with `keep_best`.
+    pub fn best_state(&self) -> Option<&S> {
+        self.best_state.as_ref()
+    }
+
+    /// The quality of the final state
+    pub fn grade(&self) -> Grade {
+        self.grade
+    }
+
+    /// Why the run terminated
+    pub fn termination_reason(&self) -> Option<Reason>
+    where
+        S: State,
+    {
+        self.state.termination_reason()
+    }
+
+    /// Metadata describing the run
+    pub fn metadata(&self) -> &RunMetadata {
+        &self.metadata
+    }
+
+    /// Time taken by the run, if it was timed
+    pub fn wall_time(&self) -> Option<Duration> {
+        self.wall_time
+    }
+
+    /// The resources the run consumed
+    pub fn resources(&self) -> &ResourceLedger {
+        &self.resources
+    }
+
+    /// The measures of the last iterations of the run, oldest first.
+    ///
+    /// Empty unless the runner was built with
+    /// [`Builder::record_history`](crate::Builder::record_history), which sets how many are kept.
+    pub fn history(&self) -> &[f64] {
+        &self.history
+    }
+
+    /// When each measure in the [`history`](Output::history) was recorded, empty if none was
+    pub fn timestamps(&self) -> &[Timestamp] {
+        &self.timestamps
+    }
+
+    /// Estimate the order and contraction factor of convergence from the measure history.
+    ///
+    /// The measure is taken to be an error, converging to zero. Returns `None` if the recorded
+    /// [`history`](Output::history) does not end with at least three decreasing measures.
+    pub fn convergence_report(&self) -> Option<ConvergenceReport> {
+        ConvergenceReport::estimate(&self.history)
+    }
+
+    /// Summarise the run, including its [resource ledger](Output::resources)
+    pub fn summary(&self) -> Report
+    where
+        S: State,
+    {
+        Report::new(self)
+    }
 }
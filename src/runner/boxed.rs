@@ -0,0 +1,65 @@
+use super::Runner;
+use crate::{Calculation, RunId, State, TrellisError};
+
+/// The runner returned by [`Builder::finalise`](crate::Builder::finalise) when no controller is
+/// attached
+pub type Solver<C, P, S> = Runner<C, P, S, ()>;
+
+/// A configured runner of any calculation returning `O` or failing with `E`.
+///
+/// Runners of different calculations, problems and states can be stored side by side, in a
+/// collection or the field of a struct, when only their output matters:
+///
+/// ```ignore
+/// let runners: Vec<BoxedRunner<f64, MyError>> = vec![
+///     Newton.build_for(problem.clone()).finalise()?.boxed(),
+///     Bisection::new(f).build_for(problem).finalise()?.boxed(),
+/// ];
+/// for runner in runners {
+///     println!("{}", runner.run()?);
+/// }
+/// ```
+pub type BoxedRunner<O, E> = Box<dyn Run<Output = O, Error = E>>;
+
+/// A runner which can be executed without knowing the types it was built from
+pub trait Run {
+    type Output;
+    type Error: std::error::Error + 'static;
+
+    /// Execute the runner, as [`Runner::run`]
+    fn run(self: Box<Self>) -> Result<Self::Output, TrellisError<Self::Error>>;
+
+    /// The identifier of the run, as [`Runner::run_id`]
+    fn run_id(&self) -> &RunId;
+}
+
+impl<C, P, S, R> Run for Runner<C, P, S, R>
+where
+    C: Calculation<P, S>,
+    S: State,
+{
+    type Output = C::Output;
+    type Error = C::Error;
+
+    fn run(self: Box<Self>) -> Result<C::Output, TrellisError<C::Error>> {
+        Runner::run(*self)
+    }
+
+    fn run_id(&self) -> &RunId {
+        Runner::run_id(self)
+    }
+}
+
+impl<C, P, S, R> Runner<C, P, S, R>
+where
+    C: Calculation<P, S>,
+    S: State,
+{
+    /// Erase the types of the runner, leaving only those of its output and error
+    pub fn boxed(self) -> BoxedRunner<C::Output, C::Error>
+    where
+        Self: 'static,
+    {
+        Box::new(self)
+    }
+}
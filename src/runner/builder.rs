@@ -1,11 +1,38 @@
-use super::{Error, InitialiseRunner, Runner};
+use super::{
+    Batch, History, InitialiseRunner, Installer, InvalidMeasurePolicy, IterationErrorPolicy,
+    ParamChange, Plan, Plugin, Predicate, Recovery, RestartPolicy, Runner, Schedule, ThreadOptions,
+};
+use crate::{
+    controller::Spawner,
+    ledger::Meter,
+    watchers::{
+        default_observer_count, default_observers, Attachment, FrequencySet, Naming, Observer,
+        ObserverHandle, ObserverVec,
+    },
+    Calculation, Clock, Control, Flags, MetadataError, Norm, Problem, RunId, RunMetadata,
+    RunnerError, State, SystemClock, WarmCache, KV,
+};
+#[cfg(feature = "tokio")]
 use crate::{
-    watchers::{Frequency, Observable, Observer, ObserverVec},
-    Calculation, Control, Problem, State,
+    watchers::{Frequency, ProgressPublisher},
+    ProgressSnapshot,
 };
+use std::sync::Arc;
 
 pub trait GenerateBuilder<P, S>: Sized {
-    fn build_for(self, problem: P) -> Builder<Self, P, S, ()>;
+    /// Build a runner for `problem`, starting from the state given by [`State::new`].
+    ///
+    /// The state must implement [`Default`]. States without one are built with
+    /// [`build_with_state`](Self::build_with_state) instead.
+    fn build_for(self, problem: P) -> Builder<Self, P, S, ()>
+    where
+        S: Default;
+    /// Build a runner for `problem`, starting from `state`.
+    ///
+    /// Suits states with mandatory data, such as a mesh or an initial guess, which have no
+    /// meaningful default. As the runner cannot make a fresh state of its own, observers are not
+    /// notified when such a run is aborted by a panic.
+    fn build_with_state(self, problem: P, state: S) -> Builder<Self, P, S, ()>;
 }
 
 impl<C, P, S> GenerateBuilder<P, S> for C
@@ -13,13 +40,47 @@ where
     C: Calculation<P, S>,
     S: State,
 {
-    fn build_for(self, problem: P) -> Builder<Self, P, S, ()> {
+    fn build_for(self, problem: P) -> Builder<Self, P, S, ()>
+    where
+        S: Default,
+    {
+        Builder {
+            fresh: Some(S::new),
+            ..self.build_with_state(problem, S::new())
+        }
+    }
+
+    fn build_with_state(self, problem: P, state: S) -> Builder<Self, P, S, ()> {
         Builder {
             problem,
             calculation: self,
-            state: S::new(),
+            state,
             time: true,
             control_c: false,
+            quiet: false,
+            memory_warning_threshold: Some(0.9),
+            keep_best: None,
+            fresh: None,
+            history: None,
+            batch: None,
+            predicates: vec![],
+            tolerance_schedule: None,
+            param_change: None,
+            invalid_measure_policy: InvalidMeasurePolicy::default(),
+            recovery: None,
+            restart: None,
+            non_negative_measure: false,
+            parent: None,
+            soft_cancel: None,
+            iteration_timeout: None,
+            thread: ThreadOptions::default(),
+            parallel_evaluations: false,
+            flags: Flags::default(),
+            cache: None,
+            clock: Arc::new(SystemClock::default()),
+            plugins: vec![],
+            plugin_controllers: vec![],
+            annotations: KV::new(),
             controller: (),
             observers: ObserverVec::default(),
         }
@@ -32,6 +93,30 @@ pub struct Builder<C, P, S, R> {
     state: S,
     time: bool,
     control_c: bool,
+    quiet: bool,
+    memory_warning_threshold: Option<f64>,
+    keep_best: Option<fn(&S) -> S>,
+    fresh: Option<fn() -> S>,
+    history: Option<usize>,
+    batch: Option<Batch<S>>,
+    predicates: Vec<Predicate<S>>,
+    tolerance_schedule: Option<Schedule>,
+    param_change: Option<ParamChange<S>>,
+    invalid_measure_policy: InvalidMeasurePolicy,
+    recovery: Option<Recovery<S>>,
+    restart: Option<RestartPolicy<P, S>>,
+    non_negative_measure: bool,
+    parent: Option<RunId>,
+    soft_cancel: Option<usize>,
+    iteration_timeout: Option<std::time::Duration>,
+    thread: ThreadOptions,
+    parallel_evaluations: bool,
+    flags: Flags,
+    cache: Option<WarmCache>,
+    clock: Arc<dyn Clock>,
+    plugins: Vec<&'static str>,
+    plugin_controllers: Vec<Spawner>,
+    annotations: KV,
     controller: R,
     observers: ObserverVec<S>,
 }
@@ -42,12 +127,354 @@ impl<C, P, S, R> Builder<C, P, S, R> {
         self
     }
 
+    /// Do not attach the process-wide default observers.
+    ///
+    /// Only observers attached directly to this builder will be notified during the run.
+    #[must_use]
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    /// Warn when memory usage exceeds this fraction of the container memory limit.
+    ///
+    /// Defaults to `Some(0.9)`. Passing `None` disables the check, which is only ever performed
+    /// when the process runs under a cgroup memory limit.
+    #[must_use]
+    pub fn memory_warning_threshold(mut self, threshold: Option<f64>) -> Self {
+        self.memory_warning_threshold = threshold;
+        self
+    }
+
+    /// Keep a copy of the state from the iteration with the best measure.
+    ///
+    /// The copy is available from [`Output::best_state`](crate::Output::best_state) when the
+    /// runner is executed with `run_to_output`.
+    #[must_use]
+    pub fn keep_best(mut self) -> Self
+    where
+        S: Clone,
+    {
+        self.keep_best = Some(S::clone);
+        self
+    }
+
+    /// Record the measure of the last `capacity` iterations, and when each was recorded.
+    ///
+    /// The history is available from [`Output::history`](crate::Output::history) and
+    /// [`Output::timestamps`](crate::Output::timestamps) when the runner is executed with
+    /// `run_to_output`, and is what
+    /// [`Output::convergence_report`](crate::Output::convergence_report) estimates from. Older
+    /// iterations are dropped once `capacity` is reached. Without this no history is kept, so long
+    /// runs use no memory for it.
+    #[must_use]
+    pub fn record_history(mut self, capacity: usize) -> Self {
+        self.history = Some(capacity);
+        self
+    }
+
+    /// Notify observers of iterations in batches of `iterations`, rather than after every one.
+    ///
+    /// A copy of the state is kept for each iteration until the batch is flushed, when each
+    /// observer is handed the iterations its frequency selects in a single call to
+    /// [`Observer::observe_batch`]. Observers which override it, for example to write a batch in
+    /// one go, then do their per-call work once per batch rather than once per iteration, at the
+    /// cost of observations arriving late. Initialisation and finalisation are notified as usual,
+    /// the pending batch being flushed first. Iterations pending when a run fails are not
+    /// observed.
+    #[must_use]
+    pub fn batch_notifications(mut self, iterations: usize) -> Self
+    where
+        S: Clone,
+    {
+        self.batch = (iterations > 1).then_some(Batch {
+            size: iterations,
+            snapshot: S::clone,
+            pending: vec![],
+        });
+        self
+    }
+
+    /// Terminate the run when `predicate` returns `true`.
+    ///
+    /// Predicates are evaluated on the state before every iteration, and the run terminates with
+    /// [`Reason::UserPredicate`](crate::Reason::UserPredicate) if any of them holds. This covers
+    /// ad-hoc stopping rules, such as detecting a NaN or polling an external flag.
+    #[must_use]
+    pub fn terminate_if<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&S) -> bool + 'static,
+    {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    /// Continue from the final state of an earlier run.
+    ///
+    /// The state replaces the one the builder was created with, after passing through
+    /// [`State::for_warm_start`]. An initialised state is not initialised again, so the new run
+    /// picks up from the iteration and best measure the earlier run reached, with whatever
+    /// tolerances and stopping rules this builder is configured with.
+    ///
+    /// Fails if the state was produced by a different calculation, or a different version of this
+    /// one, as recorded in the [`RunMetadata`] in its extensions. A state without extensions
+    /// cannot be checked, and a warning is logged.
+    pub fn warm_start(mut self, state: S) -> Result<Self, MetadataError>
+    where
+        C: Calculation<P, S>,
+        S: State,
+    {
+        match state
+            .extensions()
+            .and_then(|extensions| extensions.get::<RunMetadata>())
+        {
+            Some(recorded) => crate::metadata::ensure_compatible(
+                (&recorded.calculation, &recorded.calculation_version),
+                (C::NAME, C::VERSION),
+            )?,
+            None => tracing::warn!(
+                calculation = C::NAME,
+                "state does not record the calculation which produced it, so it cannot be checked"
+            ),
+        }
+        self.state = state.for_warm_start();
+        Ok(self)
+    }
+
+    /// Vary the relative tolerance used for convergence as the run progresses.
+    ///
+    /// Before every iteration the schedule is called with the number of the coming iteration, and
+    /// the result passed to [`State::set_relative_tolerance`]. This allows the tolerance to be
+    /// tightened as the run progresses, as in inexact Newton and homotopy methods.
+    #[must_use]
+    pub fn tolerance_schedule<F>(mut self, schedule: F) -> Self
+    where
+        F: Fn(usize) -> f64 + 'static,
+    {
+        self.tolerance_schedule = Some(Box::new(schedule));
+        self
+    }
+
+    /// Converge once the parameter stops changing.
+    ///
+    /// After every iteration the change in the parameter since the last one is measured relative
+    /// to its size, `|x_k - x_{k-1}| / |x_k|`, and the run terminates with
+    /// [`Reason::Converged`](crate::Reason::Converged) once it falls below `tolerance`. This is
+    /// checked alongside whatever convergence test the calculation makes on the measure, and
+    /// suits calculations whose error estimate is unreliable or expensive. Iterations where the
+    /// state reports no parameter are not checked.
+    #[must_use]
+    pub fn param_tolerance(mut self, tolerance: f64) -> Self
+    where
+        S: State,
+        S::Param: Norm + Clone + 'static,
+    {
+        self.param_change = Some(ParamChange::new(tolerance));
+        self
+    }
+
+    /// Configure how a NaN or infinite measure is handled.
+    ///
+    /// By default the run terminates with [`Reason::InvalidMeasure`](crate::Reason::InvalidMeasure).
+    #[must_use]
+    pub fn invalid_measure_policy(mut self, policy: InvalidMeasurePolicy) -> Self {
+        self.invalid_measure_policy = policy;
+        self
+    }
+
+    /// Configure how an error returned from an iteration is handled.
+    ///
+    /// By default the run is aborted. Retrying or skipping an iteration restarts from a copy of
+    /// the state taken before it, which suits calculations whose iterations fail transiently, such
+    /// as those evaluating a problem over the network. Observers can read how the last iteration
+    /// was completed from the [`IterationAttempts`](crate::IterationAttempts) in the state's
+    /// extensions.
+    #[must_use]
+    pub fn on_iteration_error(mut self, policy: IterationErrorPolicy) -> Self
+    where
+        S: Clone,
+    {
+        self.recovery = Some(Recovery {
+            policy,
+            snapshot: S::clone,
+            failures: 0,
+        });
+        self
+    }
+
+    /// Restart a run which terminates without success, as described by the policy.
+    ///
+    /// Observers are notified of each restart at [`Stage::Restart`](crate::Stage::Restart), with
+    /// the state the failed attempt terminated with, and the restarted state carries a
+    /// [`RestartAttempt`](crate::RestartAttempt) in its extensions.
+    #[must_use]
+    pub fn restart_policy(mut self, policy: RestartPolicy<P, S>) -> Self {
+        self.restart = Some(policy);
+        self
+    }
+
+    /// Treat negative measures as invalid.
+    ///
+    /// Appropriate when the measure is an error estimate, which can never legitimately be
+    /// negative.
+    #[must_use]
+    pub fn non_negative_measure(mut self, non_negative: bool) -> Self {
+        self.non_negative_measure = non_negative;
+        self
+    }
+
+    /// Record that this run continues the run described by `parent`.
+    ///
+    /// The parent's identifier is stored in the metadata of this run, so the convergence curves
+    /// of the whole lineage can later be joined with [`lineage::stitch`](crate::lineage::stitch).
+    #[must_use]
+    pub fn descends_from(mut self, parent: &RunMetadata) -> Self {
+        self.parent = Some(parent.run_id.clone());
+        self
+    }
+
+    /// Continue a run from the state stored in a checkpoint written by a
+    /// [`Checkpointer`](crate::Checkpointer).
+    ///
+    /// The checkpointed state replaces the attached state, so configuration applied before this
+    /// call is lost. It passes through [`State::for_warm_start`], so a checkpoint taken as a run
+    /// was stopped, for example by a controller, continues past the point it stopped. Fails if
+    /// the checkpoint was written by a build whose state type differs, or by a different
+    /// calculation or version of this one.
+    #[cfg(feature = "writing")]
+    pub fn resume_from_checkpoint(
+        mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<Self, crate::CheckpointError>
+    where
+        C: Calculation<P, S>,
+        S: State + Default + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let state: S = crate::checkpoint::load(path.as_ref(), (C::NAME, C::VERSION))?;
+        self.state = state.for_warm_start();
+        Ok(self)
+    }
+
+    /// Continue a run from a checkpoint written by a [`Checkpointer`](crate::Checkpointer)
+    /// encrypting with `key`.
+    ///
+    /// Unencrypted checkpoints are also accepted. Fails as
+    /// [`resume_from_checkpoint`](Self::resume_from_checkpoint) does, or if the key is wrong.
+    #[cfg(feature = "encryption")]
+    pub fn resume_from_encrypted_checkpoint(
+        mut self,
+        path: impl AsRef<std::path::Path>,
+        key: &crate::EncryptionKey,
+    ) -> Result<Self, crate::CheckpointError>
+    where
+        C: Calculation<P, S>,
+        S: State + Default + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let state: S =
+            crate::checkpoint::load_encrypted(path.as_ref(), (C::NAME, C::VERSION), key)?;
+        self.state = state.for_warm_start();
+        Ok(self)
+    }
+
+    /// Let the run finish its current plateau when a kill signal is received.
+    ///
+    /// Rather than stopping at the next iteration boundary, the runner keeps iterating until the
+    /// best measure improves or `max_iterations` further iterations have been performed. A run
+    /// which is close to an improvement then returns a noticeably better partial result.
+    #[must_use]
+    pub fn soft_cancel(mut self, max_iterations: usize) -> Self {
+        self.soft_cancel = Some(max_iterations);
+        self
+    }
+
+    /// Stop the run if a single iteration takes longer than `timeout`.
+    ///
+    /// A watchdog thread times each call to [`Calculation::next`]. When one overruns it flips a
+    /// killswitch, and the run terminates with
+    /// [`Reason::IterationTimedOut`](crate::Reason::IterationTimedOut) once the iteration returns.
+    /// The iteration itself cannot be interrupted, so calculations whose evaluations can
+    /// hang should poll [`Problem::cancel_checker`](crate::Problem::cancel_checker), which the
+    /// watchdog also cancels.
+    #[must_use]
+    pub fn iteration_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.iteration_timeout = Some(timeout);
+        self
+    }
+
+    /// Run at the given niceness, from -20 for the highest priority to 19 for the lowest.
+    ///
+    /// Applied to the thread calling [`Runner::run`] when the run starts. Raising the priority
+    /// usually needs elevated privileges, and a niceness which cannot be set is logged and
+    /// ignored, as it is on platforms other than unix.
+    #[must_use]
+    pub fn thread_niceness(mut self, niceness: i32) -> Self {
+        self.thread.niceness = Some(niceness);
+        self
+    }
+
+    /// Pin the thread calling [`Runner::run`] to the core with index `core` when the run starts.
+    ///
+    /// Needs the `core_affinity` feature, without which the thread is left unpinned. The thread
+    /// stays pinned after the run returns.
+    #[must_use]
+    pub fn pin_to_core(mut self, core: usize) -> Self {
+        self.thread.core = Some(core);
+        self
+    }
+
+    /// Evaluate the members of populations in parallel, with rayon.
+    ///
+    /// Affects calculations which evaluate their population through
+    /// [`Problem::evaluate_population`], and needs the `rayon` feature, without which members are
+    /// evaluated in turn. The problem is shared between the threads of rayon's global pool.
+    #[must_use]
+    pub fn parallel_evaluations(mut self, parallel: bool) -> Self {
+        self.parallel_evaluations = parallel;
+        self
+    }
+
+    /// Set a feature flag, readable by the calculation through [`Problem::flag`].
+    ///
+    /// Flags are distinguished by type, so a flag is best defined as a dedicated type such as
+    /// `struct DampedUpdate(bool)`. Setting a second flag of the same type replaces the first.
+    /// Every flag is recorded in the metadata of the run with its `Debug` representation.
+    #[must_use]
+    pub fn with_flag<T: std::any::Any + std::fmt::Debug + Send + Sync>(mut self, value: T) -> Self {
+        self.flags.insert(value);
+        self
+    }
+
+    /// Share a cache with related runs, readable by the calculation through [`Problem::cache`]
+    #[must_use]
+    pub fn with_cache(mut self, cache: WarmCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Read the time from `clock` rather than the system clock, for example a
+    /// [`MockClock`](crate::MockClock) in tests of timing
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
     #[must_use]
     pub fn time(mut self, time: bool) -> Self {
         self.time = time;
         self
     }
 
+    /// Start the run from `state`, replacing the one the builder was created with.
+    ///
+    /// Unlike [`Builder::warm_start`] the state is taken as it is, and initialised by the
+    /// calculation unless it reports that it already has been.
+    #[must_use]
+    pub fn with_initial_state(mut self, state: S) -> Self {
+        self.state = state;
+        self
+    }
+
     /// Configure the attached state.
     ///
     /// Apply any runtime configuration option to the attached state.
@@ -62,14 +489,220 @@ impl<C, P, S, R> Builder<C, P, S, R> {
     pub fn attach_observer<OBS: Observer<S> + 'static>(
         mut self,
         observer: OBS,
-        frequency: Frequency,
+        frequency: impl Into<FrequencySet>,
+    ) -> Self {
+        self.observers.attach_with(
+            std::sync::Arc::new(observer),
+            Naming::of::<OBS>(),
+            frequency.into(),
+            Attachment::Required,
+        );
+        self
+    }
+
+    /// Attach a [`Checkpointer`](crate::Checkpointer), recording this calculation as the one
+    /// which wrote its checkpoints.
+    #[cfg(feature = "writing")]
+    #[must_use]
+    pub fn attach_checkpointer(
+        self,
+        checkpointer: crate::Checkpointer,
+        frequency: impl Into<FrequencySet>,
+    ) -> Self
+    where
+        C: Calculation<P, S>,
+        S: State + Default + serde::Serialize + 'static,
+    {
+        self.attach_observer(checkpointer.for_calculation(C::NAME, C::VERSION), frequency)
+    }
+
+    /// Attach an observer under `name`, by which it is listed in the plan and can be detached
+    /// from the runner.
+    ///
+    /// Observers attached without a name are named after their type.
+    #[must_use]
+    pub fn attach_named_observer<OBS: Observer<S> + 'static>(
+        mut self,
+        name: impl Into<String>,
+        observer: OBS,
+        frequency: impl Into<FrequencySet>,
+    ) -> Self {
+        self.observers.attach_with(
+            std::sync::Arc::new(observer),
+            Naming::Given(name.into()),
+            frequency.into(),
+            Attachment::Required,
+        );
+        self
+    }
+
+    /// Install a plugin, attaching its observers, stopping rules and controllers.
+    ///
+    /// The plugin's name and any values it records are stored in the metadata of the run.
+    #[must_use]
+    pub fn with_plugin<PL: Plugin<S>>(mut self, plugin: PL) -> Self {
+        let name = plugin.name();
+        self.plugins.push(name);
+        plugin.install(&mut Installer {
+            name,
+            observers: &mut self.observers,
+            predicates: &mut self.predicates,
+            controllers: &mut self.plugin_controllers,
+            annotations: &mut self.annotations,
+        });
+        self
+    }
+
+    /// Attach an observer the run can do without.
+    ///
+    /// If the observer's backend cannot start, for example because its output directory is
+    /// read-only, [`finalise`](Builder::finalise) disables it and records a warning, available
+    /// from [`Runner::observer_warnings`], instead of failing.
+    #[must_use]
+    pub fn attach_best_effort_observer<OBS: Observer<S> + 'static>(
+        mut self,
+        observer: OBS,
+        frequency: impl Into<FrequencySet>,
     ) -> Self {
-        self.observers.attach(
-            std::sync::Arc::new(std::sync::Mutex::new(observer)),
-            frequency,
+        self.observers.attach_with(
+            std::sync::Arc::new(observer),
+            Naming::of::<OBS>(),
+            frequency.into(),
+            Attachment::BestEffort,
         );
         self
     }
+
+    /// Publish a [`ProgressSnapshot`] after every iteration.
+    ///
+    /// Returns the builder alongside a watch receiver which always holds the latest snapshot, so
+    /// GUIs and servers can poll the progress of the run without writing a custom observer.
+    #[cfg(feature = "tokio")]
+    #[must_use]
+    pub fn with_progress_channel(
+        self,
+    ) -> (
+        Self,
+        tokio::sync::watch::Receiver<ProgressSnapshot<S::Float>>,
+    )
+    where
+        S: State + 'static,
+        S::Float: 'static,
+    {
+        let (publisher, receiver) = ProgressPublisher::new();
+        (self.attach_observer(publisher, Frequency::Always), receiver)
+    }
+
+    /// Describe what the runner would do, without running it.
+    ///
+    /// Nothing is started: default observers are counted rather than created, and no controller
+    /// threads or signal handlers are installed.
+    pub fn plan(&self) -> Plan
+    where
+        C: Calculation<P, S>,
+        S: State + 'static,
+    {
+        let controller = std::any::type_name::<R>();
+        Plan {
+            calculation: C::NAME,
+            state: self.state.kv(),
+            warm_start: self.state.is_initialised(),
+            predicates: self.predicates.len(),
+            tolerance_schedule: self.tolerance_schedule.is_some(),
+            param_tolerance: self.param_change.as_ref().map(|change| change.tolerance),
+            invalid_measure_policy: self.invalid_measure_policy,
+            iteration_error_policy: self
+                .recovery
+                .as_ref()
+                .map_or_else(IterationErrorPolicy::default, |recovery| recovery.policy),
+            max_restarts: self.restart.as_ref().map(RestartPolicy::max_restarts),
+            non_negative_measure: self.non_negative_measure,
+            soft_cancel: self.soft_cancel,
+            iteration_timeout: self.iteration_timeout,
+            niceness: self.thread.niceness,
+            pinned_core: self.thread.core,
+            parallel_evaluations: self.parallel_evaluations,
+            control_c: self.control_c,
+            controller: (controller != "()").then_some(controller),
+            timed: self.time,
+            keep_best: self.keep_best.is_some(),
+            batch_notifications: self.batch.as_ref().map(|batch| batch.size),
+            parent: self.parent.clone(),
+            flags: self.flags.describe(),
+            plugins: self.plugins.clone(),
+            observers: self.observers.describe(),
+            default_observers: if self.quiet {
+                0
+            } else {
+                default_observer_count::<S>()
+            },
+        }
+    }
+
+    fn attach_default_observers(&mut self)
+    where
+        S: 'static,
+    {
+        if !self.quiet {
+            for (observer, naming, frequency) in default_observers() {
+                self.observers
+                    .attach_with(observer, naming, frequency, Attachment::Required);
+            }
+        }
+    }
+
+    fn into_runner(mut self) -> Runner<C, P, S, R>
+    where
+        S: 'static,
+    {
+        self.attach_default_observers();
+        Runner {
+            problem: Problem::with_flags(self.problem, self.flags)
+                .with_cache(self.cache)
+                .with_parallel_evaluations(self.parallel_evaluations),
+            calculation: self.calculation,
+            state: Some(self.state),
+            time: self.time,
+            control_c: self.control_c,
+            controller: Some(self.controller),
+            signals: vec![],
+            plugin_controllers: self.plugin_controllers,
+            plugins: self.plugins,
+            annotations: self.annotations,
+            observers: self.observers,
+            memory_warning_threshold: self.memory_warning_threshold,
+            metadata: None,
+            memory_warning_issued: false,
+            memory_checked: None,
+            keep_best: self.keep_best,
+            fresh: self.fresh,
+            batch: self.batch,
+            best_state: None,
+            history: self.history.map(History::new),
+            clock: self.clock,
+            started: None,
+            predicates: self.predicates,
+            tolerance_schedule: self.tolerance_schedule,
+            param_change: self.param_change,
+            invalid_measure_policy: self.invalid_measure_policy,
+            recovery: self.recovery,
+            restart: self.restart,
+            restarts: 0,
+            termination: None,
+            non_negative_measure: self.non_negative_measure,
+            parent: self.parent,
+            progress: None,
+            run_id: RunId::generate(),
+            soft_cancel: self.soft_cancel,
+            grace_remaining: None,
+            iteration_timeout: self.iteration_timeout,
+            watchdog: None,
+            thread: self.thread,
+            observer_warnings: vec![],
+            observer_handle: ObserverHandle::new(),
+            meter: Meter::default(),
+        }
+    }
 }
 
 impl<C, P, S> Builder<C, P, S, ()> {
@@ -81,22 +714,41 @@ impl<C, P, S> Builder<C, P, S, ()> {
             state: self.state,
             time: self.time,
             control_c: self.control_c,
+            quiet: self.quiet,
+            memory_warning_threshold: self.memory_warning_threshold,
+            keep_best: self.keep_best,
+            history: self.history,
+            fresh: self.fresh,
+            batch: self.batch,
+            predicates: self.predicates,
+            tolerance_schedule: self.tolerance_schedule,
+            param_change: self.param_change,
+            invalid_measure_policy: self.invalid_measure_policy,
+            recovery: self.recovery,
+            restart: self.restart,
+            non_negative_measure: self.non_negative_measure,
+            parent: self.parent,
+            soft_cancel: self.soft_cancel,
+            iteration_timeout: self.iteration_timeout,
+            thread: self.thread,
+            parallel_evaluations: self.parallel_evaluations,
+            flags: self.flags,
+            cache: self.cache,
+            clock: self.clock,
+            plugins: self.plugins,
+            plugin_controllers: self.plugin_controllers,
+            annotations: self.annotations,
             controller,
             observers: self.observers,
         }
     }
 
-    pub fn finalise(self) -> Result<Runner<C, P, S, ()>, Error> {
-        let mut runner = Runner {
-            problem: Problem::new(self.problem),
-            calculation: self.calculation,
-            state: Some(self.state),
-            time: self.time,
-            control_c: self.control_c,
-            controller: None,
-            signals: vec![],
-            observers: self.observers,
-        };
+    pub fn finalise(self) -> Result<Runner<C, P, S, ()>, RunnerError>
+    where
+        S: 'static,
+    {
+        let mut runner = self.into_runner();
+        runner.start_observers()?;
         runner.initialise_controllers()?;
         Ok(runner)
     }
@@ -106,17 +758,12 @@ impl<C, P, S, R> Builder<C, P, S, R>
 where
     R: Control + 'static,
 {
-    pub fn finalise(self) -> Result<Runner<C, P, S, R>, Error> {
-        let mut runner = Runner {
-            problem: Problem::new(self.problem),
-            calculation: self.calculation,
-            state: Some(self.state),
-            time: self.time,
-            control_c: self.control_c,
-            controller: Some(self.controller),
-            signals: vec![],
-            observers: self.observers,
-        };
+    pub fn finalise(self) -> Result<Runner<C, P, S, R>, RunnerError>
+    where
+        S: 'static,
+    {
+        let mut runner = self.into_runner();
+        runner.start_observers()?;
         runner.initialise_controllers()?;
         Ok(runner)
     }
@@ -0,0 +1,211 @@
+//! Interleaved execution of two runners, for comparing calculations fairly.
+//!
+//! Benchmarking two calculations one after the other confounds the comparison with whatever
+//! changed on the machine in between, such as thermal throttling or competing load. The
+//! [`Interleave`] harness instead alternates single iterations of the two runners, so both see
+//! the same conditions, and records the measure and duration of every iteration of each.
+use std::ops::ControlFlow;
+
+use hifitime::{Duration, Epoch};
+
+use super::Runner;
+use crate::{Calculation, Measure, State, TrellisError};
+
+/// The iterations of one side of an interleaved comparison
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RunTrace {
+    /// Name of the calculation
+    pub calculation: &'static str,
+    /// The measure after every iteration
+    pub measures: Vec<f64>,
+    /// The wall time taken by every iteration
+    pub iteration_times: Vec<Duration>,
+}
+
+impl RunTrace {
+    fn new(calculation: &'static str) -> Self {
+        Self {
+            calculation,
+            ..Self::default()
+        }
+    }
+
+    /// Total wall time spent iterating
+    pub fn total_time(&self) -> Duration {
+        self.iteration_times
+            .iter()
+            .fold(Duration::ZERO, |total, time| total + *time)
+    }
+
+    /// The number of iterations until the measure first reached `tolerance`
+    pub fn iterations_to(&self, tolerance: f64) -> Option<usize> {
+        self.measures
+            .iter()
+            .position(|measure| *measure <= tolerance)
+            .map(|index| index + 1)
+    }
+
+    /// The wall time spent iterating until the measure first reached `tolerance`
+    pub fn time_to(&self, tolerance: f64) -> Option<Duration> {
+        self.iterations_to(tolerance).map(|iterations| {
+            self.iteration_times[..iterations]
+                .iter()
+                .fold(Duration::ZERO, |total, time| total + *time)
+        })
+    }
+}
+
+/// A paired comparison of two interleaved runs
+#[derive(Clone, Debug, PartialEq)]
+pub struct PairedComparison {
+    pub left: RunTrace,
+    pub right: RunTrace,
+}
+
+impl PairedComparison {
+    /// The median ratio of left to right iteration times, over iterations both runs performed.
+    ///
+    /// Pairing each iteration with its neighbour from the other run cancels out drift in the
+    /// speed of the machine. Returns `None` if either run performed no iterations.
+    pub fn median_time_ratio(&self) -> Option<f64> {
+        let mut ratios = self
+            .left
+            .iteration_times
+            .iter()
+            .zip(&self.right.iteration_times)
+            .map(|(left, right)| left.to_seconds() / right.to_seconds())
+            .filter(|ratio| ratio.is_finite())
+            .collect::<Vec<_>>();
+        if ratios.is_empty() {
+            return None;
+        }
+        ratios.sort_by(f64::total_cmp);
+        let middle = ratios.len() / 2;
+        Some(if ratios.len().is_multiple_of(2) {
+            (ratios[middle - 1] + ratios[middle]) / 2.0
+        } else {
+            ratios[middle]
+        })
+    }
+}
+
+/// The result of an interleaved execution
+pub struct InterleaveOutcome<L, R> {
+    pub comparison: PairedComparison,
+    /// The output of the first runner
+    pub left: L,
+    /// The output of the second runner
+    pub right: R,
+}
+
+/// Error from either side of an interleaved execution
+#[derive(Debug, thiserror::Error)]
+pub enum InterleaveError<L: std::error::Error + 'static, R: std::error::Error + 'static> {
+    #[error("left run failed: {0}")]
+    Left(#[source] TrellisError<L>),
+    #[error("right run failed: {0}")]
+    Right(#[source] TrellisError<R>),
+}
+
+/// Steps two runners alternately until both terminate.
+///
+/// The runners may run different calculations on different problems. When one terminates the
+/// other continues alone, and its remaining iterations are not paired.
+pub struct Interleave<LC, LP, LS, LR, RC, RP, RS, RR> {
+    left: Runner<LC, LP, LS, LR>,
+    right: Runner<RC, RP, RS, RR>,
+}
+
+impl<LC, LP, LS, LR, RC, RP, RS, RR> Interleave<LC, LP, LS, LR, RC, RP, RS, RR>
+where
+    LC: Calculation<LP, LS>,
+    LS: State,
+    RC: Calculation<RP, RS>,
+    RS: State,
+{
+    pub fn new(left: Runner<LC, LP, LS, LR>, right: Runner<RC, RP, RS, RR>) -> Self {
+        Self { left, right }
+    }
+
+    /// Run both calculations to termination, alternating iterations
+    #[allow(clippy::type_complexity)]
+    pub fn run(
+        mut self,
+    ) -> Result<InterleaveOutcome<LC::Output, RC::Output>, InterleaveError<LC::Error, RC::Error>>
+    {
+        let left_start_time = self.left.now().unwrap();
+        let right_start_time = self.right.now().unwrap();
+
+        let mut left = ControlFlow::Continue(self.left.prepare().map_err(InterleaveError::Left)?);
+        let mut right =
+            ControlFlow::Continue(self.right.prepare().map_err(InterleaveError::Right)?);
+
+        let mut comparison = PairedComparison {
+            left: RunTrace::new(LC::NAME),
+            right: RunTrace::new(RC::NAME),
+        };
+
+        while left.is_continue() || right.is_continue() {
+            if let ControlFlow::Continue(state) = left {
+                left = step(
+                    &mut self.left,
+                    state,
+                    left_start_time.as_ref(),
+                    &mut comparison.left,
+                )
+                .map_err(InterleaveError::Left)?;
+            }
+            if let ControlFlow::Continue(state) = right {
+                right = step(
+                    &mut self.right,
+                    state,
+                    right_start_time.as_ref(),
+                    &mut comparison.right,
+                )
+                .map_err(InterleaveError::Right)?;
+            }
+        }
+
+        Ok(InterleaveOutcome {
+            comparison,
+            left: self
+                .left
+                .finalise(into_state(left))
+                .map_err(InterleaveError::Left)?,
+            right: self
+                .right
+                .finalise(into_state(right))
+                .map_err(InterleaveError::Right)?,
+        })
+    }
+}
+
+/// Advance a runner by one iteration, recording it in `trace`
+fn step<C, P, S, R>(
+    runner: &mut Runner<C, P, S, R>,
+    state: S,
+    start_time: Option<&Epoch>,
+    trace: &mut RunTrace,
+) -> Result<ControlFlow<S, S>, TrellisError<C::Error>>
+where
+    C: Calculation<P, S>,
+    S: State,
+{
+    let iteration_start = runner.clock.epoch();
+    let step = runner
+        .advance(state, start_time)
+        .map_err(|e| e.with_progress(runner.progress))?;
+    if let ControlFlow::Continue(state) = &step {
+        trace
+            .iteration_times
+            .push(runner.clock.epoch() - iteration_start);
+        trace.measures.push(state.measure().real());
+    }
+    Ok(step)
+}
+
+fn into_state<S>(step: ControlFlow<S, S>) -> S {
+    match step {
+        ControlFlow::Continue(state) | ControlFlow::Break(state) => state,
+    }
+}
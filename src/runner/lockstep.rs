@@ -0,0 +1,161 @@
+//! Lockstep execution of two runners, for hunting non-determinism in calculations.
+//!
+//! Two runners configured identically should produce identical iterates. The [`Lockstep`]
+//! harness steps them alternately and reports the first iteration at which they disagree.
+use std::ops::ControlFlow;
+
+use super::Runner;
+use crate::{Calculation, Measure, State, TrellisError};
+
+/// How two runs were found to disagree
+#[derive(Clone, Debug, PartialEq)]
+pub enum DivergenceKind {
+    /// The measures differ by more than the tolerance
+    Measure { left: f64, right: f64 },
+    /// The parameter comparison reported a mismatch
+    Param,
+    /// One run terminated while the other did not
+    Termination { left: bool, right: bool },
+}
+
+/// The first point at which two runs disagree
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    /// The iteration at which the disagreement was detected
+    pub iteration: usize,
+    /// The nature of the disagreement
+    pub kind: DivergenceKind,
+}
+
+/// The result of a lockstep execution
+pub struct LockstepOutcome<O> {
+    /// The first divergence, or `None` if both runs agreed until termination
+    pub divergence: Option<Divergence>,
+    /// The output of the first runner
+    pub left: O,
+    /// The output of the second runner
+    pub right: O,
+}
+
+type ParamComparison<P> = Box<dyn Fn(&P, &P) -> bool>;
+
+/// Steps two runners alternately, stopping at the first divergence.
+///
+/// Both runners are finalised when the comparison stops, so attached observers see a complete
+/// run up to the point of divergence.
+pub struct Lockstep<C, P, S: State, R> {
+    left: Runner<C, P, S, R>,
+    right: Runner<C, P, S, R>,
+    tolerance: f64,
+    params_match: Option<ParamComparison<S::Param>>,
+}
+
+impl<C, P, S, R> Lockstep<C, P, S, R>
+where
+    C: Calculation<P, S>,
+    S: State,
+{
+    pub fn new(left: Runner<C, P, S, R>, right: Runner<C, P, S, R>) -> Self {
+        Self {
+            left,
+            right,
+            tolerance: 0.0,
+            params_match: None,
+        }
+    }
+
+    /// The largest absolute difference in measure which is not considered a divergence
+    #[must_use]
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Compare parameters after every iteration, the closure returning `true` if they agree
+    #[must_use]
+    pub fn compare_params<F>(mut self, params_match: F) -> Self
+    where
+        F: Fn(&S::Param, &S::Param) -> bool + 'static,
+    {
+        self.params_match = Some(Box::new(params_match));
+        self
+    }
+
+    fn compare(&self, left: &S, right: &S) -> Option<DivergenceKind> {
+        let (left_measure, right_measure) = (left.measure().real(), right.measure().real());
+        // A NaN on either side never compares within tolerance
+        let difference = (left_measure - right_measure).abs();
+        if difference.is_nan() || difference > self.tolerance {
+            return Some(DivergenceKind::Measure {
+                left: left_measure,
+                right: right_measure,
+            });
+        }
+        if let Some(params_match) = self.params_match.as_ref() {
+            let agree = match (left.get_param(), right.get_param()) {
+                (Some(left), Some(right)) => params_match(left, right),
+                (None, None) => true,
+                _ => false,
+            };
+            if !agree {
+                return Some(DivergenceKind::Param);
+            }
+        }
+        None
+    }
+
+    /// Run both calculations until they diverge or both terminate
+    pub fn run(mut self) -> Result<LockstepOutcome<C::Output>, TrellisError<C::Error>> {
+        let left_start_time = self.left.now().unwrap();
+        let right_start_time = self.right.now().unwrap();
+
+        let mut left = self.left.prepare()?;
+        let mut right = self.right.prepare()?;
+
+        let mut divergence = self.compare(&left, &right).map(|kind| Divergence {
+            iteration: left.current_iteration(),
+            kind,
+        });
+
+        while divergence.is_none() {
+            let left_step = self.left.advance(left, left_start_time.as_ref())?;
+            let right_step = self.right.advance(right, right_start_time.as_ref())?;
+            match (left_step, right_step) {
+                (ControlFlow::Continue(l), ControlFlow::Continue(r)) => {
+                    divergence = self.compare(&l, &r).map(|kind| Divergence {
+                        iteration: l.current_iteration(),
+                        kind,
+                    });
+                    (left, right) = (l, r);
+                }
+                (ControlFlow::Break(l), ControlFlow::Break(r)) => {
+                    (left, right) = (l, r);
+                    break;
+                }
+                (l, r) => {
+                    let kind = DivergenceKind::Termination {
+                        left: l.is_break(),
+                        right: r.is_break(),
+                    };
+                    (left, right) = (into_state(l), into_state(r));
+                    divergence = Some(Divergence {
+                        iteration: left.current_iteration().max(right.current_iteration()),
+                        kind,
+                    });
+                }
+            }
+        }
+
+        Ok(LockstepOutcome {
+            divergence,
+            left: self.left.finalise(left)?,
+            right: self.right.finalise(right)?,
+        })
+    }
+}
+
+fn into_state<S>(step: ControlFlow<S, S>) -> S {
+    match step {
+        ControlFlow::Continue(state) | ControlFlow::Break(state) => state,
+    }
+}
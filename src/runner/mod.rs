@@ -1,26 +1,188 @@
+mod boxed;
 mod builder;
+mod interleave;
+mod lockstep;
+mod plan;
+mod plugin;
+mod probe;
+mod queue;
+mod restart;
+mod thread;
+mod watchdog;
 
+use std::collections::VecDeque;
+use std::ops::ControlFlow;
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
 
 use hifitime::{Duration, Epoch};
-use tracing::instrument;
+use tracing::{error, field, info, instrument, warn, Span};
 
 use crate::{
-    controller::{set_handler, Control},
-    watchers::{Observable, ObserverSlice, ObserverVec, Stage},
+    clock::Clock,
+    controller::{set_handler, Control, Spawner},
+    ledger::Meter,
+    watchers::{
+        Attachment, FrequencySet, Naming, Observer, ObserverHandle, ObserverId, ObserverPlan,
+        ObserverSlice, ObserverVec, Stage,
+    },
 };
-use crate::{Calculation, Problem, Reason, State};
-pub use builder::GenerateBuilder;
+use crate::{
+    Calculation, CancelChecker, ContainerLimits, ErrorKind, Grade, Measure, Norm, Output, Problem,
+    Reason, ReportedKV, RunId, RunMetadata, RunProgress, RunnerError, State, Timestamp,
+    TrellisError, KV,
+};
+pub use boxed::{BoxedRunner, Run, Solver};
+pub use builder::{Builder, GenerateBuilder};
+pub use interleave::{Interleave, InterleaveError, InterleaveOutcome, PairedComparison, RunTrace};
+pub use lockstep::{Divergence, DivergenceKind, Lockstep, LockstepOutcome};
+pub use plan::Plan;
+pub use plugin::{Installer, Plugin};
+pub use probe::ProbeEstimate;
+pub use queue::{
+    Preemption, QueueError, QueueProgress, QueueSummary, RunQueue, Submitter, WorkerUtilisation,
+};
+pub use restart::{RestartAttempt, RestartPolicy};
+use thread::ThreadOptions;
+use watchdog::Watchdog;
+
+type Predicate<S> = Box<dyn Fn(&S) -> bool>;
+
+type Schedule = Box<dyn Fn(usize) -> f64>;
+
+type Change<S> = Box<dyn FnMut(&S) -> Option<f64>>;
+
+/// Iterations waiting to be delivered to observers in a batch
+struct Batch<S> {
+    size: usize,
+    snapshot: fn(&S) -> S,
+    pending: Vec<(S, Timestamp)>,
+}
+
+/// The measures of the most recent iterations and when they were recorded, kept if enabled on
+/// the builder
+struct History {
+    capacity: usize,
+    measures: VecDeque<f64>,
+    timestamps: VecDeque<Timestamp>,
+}
+
+impl History {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            measures: VecDeque::new(),
+            timestamps: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, measure: f64, timestamp: Timestamp) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.measures.len() == self.capacity {
+            self.measures.pop_front();
+            self.timestamps.pop_front();
+        }
+        self.measures.push_back(measure);
+        self.timestamps.push_back(timestamp);
+    }
+}
+
+/// What to do when an iteration of the calculation returns an error.
+///
+/// Recovering from a failed iteration restarts it from a copy of the state taken before it. Any
+/// changes the failed iteration made to the calculation itself are kept.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum IterationErrorPolicy {
+    /// Abort the run with the error
+    #[default]
+    Abort,
+    /// Repeat the iteration up to `max` more times before aborting.
+    ///
+    /// The runner waits `backoff` before the first retry, doubling the wait after each one.
+    Retry {
+        max: usize,
+        backoff: std::time::Duration,
+    },
+    /// Count the iteration as done, leaving the state as it was before it.
+    ///
+    /// A calculation which fails on every iteration then never terminates, unless the run is
+    /// stopped by a predicate or a controller.
+    SkipIteration,
+}
+
+/// How the last iteration was completed, when the runner recovers from iteration errors.
+///
+/// Inserted into the [`Extensions`](crate::Extensions) of states which hold them after every
+/// iteration of a runner built with
+/// [`Builder::on_iteration_error`](crate::Builder::on_iteration_error), for observers to read.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct IterationAttempts {
+    /// The number of times the iteration was attempted
+    pub attempts: usize,
+    /// Whether the iteration failed and was skipped
+    pub skipped: bool,
+    /// Failed attempts over the whole run
+    pub failures: usize,
+}
+
+/// Recovery from iteration errors, with the means of copying the state to retry from
+struct Recovery<S> {
+    policy: IterationErrorPolicy,
+    snapshot: fn(&S) -> S,
+    failures: usize,
+}
 
-pub type Error = Box<dyn std::error::Error>;
+/// Convergence on the relative change in the parameter between iterations
+struct ParamChange<S> {
+    tolerance: f64,
+    /// Measures the change from the last parameter seen, remembering the new one
+    change: Change<S>,
+}
+
+impl<S: State> ParamChange<S> {
+    fn new(tolerance: f64) -> Self
+    where
+        S::Param: Norm + Clone + 'static,
+    {
+        let mut previous: Option<S::Param> = None;
+        Self {
+            tolerance,
+            change: Box::new(move |state: &S| {
+                let param = state.get_param()?;
+                let change = previous
+                    .as_ref()
+                    .map(|previous| param.relative_change(previous));
+                previous = Some(param.clone());
+                change
+            }),
+        }
+    }
+}
+
+/// What to do when the measure reported by the state is invalid.
+///
+/// A NaN measure makes every comparison false, so an unguarded run may never terminate.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum InvalidMeasurePolicy {
+    /// Carry on iterating
+    Ignore,
+    /// Terminate the run with [`Reason::InvalidMeasure`]
+    #[default]
+    Terminate,
+    /// Abort the run with [`ErrorKind::InvalidMeasure`]
+    Error,
+}
 
 #[derive(Copy, Clone)]
 pub enum Caller {
     CtrlC,
     Controller,
+    Watchdog,
 }
 
 impl From<Caller> for Reason {
@@ -28,6 +190,7 @@ impl From<Caller> for Reason {
         match val {
             Caller::CtrlC => Reason::ControlC,
             Caller::Controller => Reason::Controller,
+            Caller::Watchdog => Reason::IterationTimedOut,
         }
     }
 }
@@ -43,6 +206,9 @@ impl Killswitch {
     }
 }
 
+/// The shortest time between two readings of the memory usage
+const MEMORY_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
 /// General purpose calculation runner
 pub struct Runner<C, P, S, R> {
     /// Calculation to be run
@@ -61,17 +227,186 @@ pub struct Runner<C, P, S, R> {
     controller: Option<R>,
     ///
     signals: Vec<Killswitch>,
+    /// Controllers added by plugins, started alongside the main controller
+    plugin_controllers: Vec<Spawner>,
+    /// Names of the plugins installed on the builder
+    plugins: Vec<&'static str>,
+    /// Values contributed to the metadata by plugins
+    annotations: KV,
     observers: ObserverVec<S>,
+    /// Fraction of the container memory limit above which a warning is emitted
+    memory_warning_threshold: Option<f64>,
+    /// Metadata describing the run, created when the run starts
+    metadata: Option<RunMetadata>,
+    /// Whether memory usage is currently above the warning threshold
+    memory_warning_issued: bool,
+    /// When memory usage was last read, on the run's monotonic clock
+    memory_checked: Option<std::time::Duration>,
+    /// Clones the state when a new best is found, if best state capture is enabled
+    keep_best: Option<fn(&S) -> S>,
+    /// Makes a state to hand observers in place of one lost to a panic, if the state has a default
+    fresh: Option<fn() -> S>,
+    /// Iterations waiting to be delivered to observers, if notifications are batched
+    batch: Option<Batch<S>>,
+    /// The state at the iteration with the best measure
+    best_state: Option<S>,
+    /// The measures of the most recent iterations, if they are recorded
+    history: Option<History>,
+    /// Where the run reads the time from
+    clock: Arc<dyn Clock>,
+    /// When the run started, on the clock's monotonic time
+    started: Option<std::time::Duration>,
+    /// User supplied stopping rules, checked before every iteration
+    predicates: Vec<Predicate<S>>,
+    /// The relative tolerance to use at each iteration, if it varies over the run
+    tolerance_schedule: Option<Schedule>,
+    /// Convergence on the change in the parameter, if enabled
+    param_change: Option<ParamChange<S>>,
+    /// How to handle an invalid measure
+    invalid_measure_policy: InvalidMeasurePolicy,
+    /// How to recover from a failed iteration, if at all
+    recovery: Option<Recovery<S>>,
+    /// When to restart a run which terminates without success
+    restart: Option<RestartPolicy<P, S>>,
+    /// The number of times the run has been restarted
+    restarts: usize,
+    /// Why the runner terminated the run, if it was the runner rather than the calculation
+    termination: Option<Reason>,
+    /// Whether a negative measure is invalid
+    non_negative_measure: bool,
+    /// The run this one continues
+    parent: Option<RunId>,
+    /// Identifier of the run, generated when the runner is finalised
+    run_id: RunId,
+    /// How far the run has got, recorded after every iteration
+    progress: Option<RunProgress>,
+    /// Iterations allowed after a kill signal while waiting for the best measure to improve
+    soft_cancel: Option<usize>,
+    /// Iterations remaining before a soft cancellation becomes a hard one
+    grace_remaining: Option<usize>,
+    /// The longest an iteration may take, if limited
+    iteration_timeout: Option<std::time::Duration>,
+    /// Times iterations once the runner is finalised, if they are limited
+    watchdog: Option<Watchdog>,
+    /// How the thread executing the run is configured
+    thread: ThreadOptions,
+    /// Best effort observers disabled because they could not start
+    observer_warnings: Vec<String>,
+    /// Observers attached and detached while the run is in progress
+    observer_handle: ObserverHandle<S>,
+    /// Time spent in the calculation and observers, for the resource ledger
+    meter: Meter,
 }
 
 impl<C, P, S, R> Runner<C, P, S, R> {
     fn now(&self) -> Result<Option<Epoch>, hifitime::errors::Errors> {
         if self.time {
-            return Ok(Some(Epoch::now()?));
+            return Ok(Some(self.clock.epoch()));
         }
         Ok(None)
     }
 
+    /// The identifier of the run, which is also recorded in its metadata and passed to the state
+    pub fn run_id(&self) -> &RunId {
+        &self.run_id
+    }
+
+    /// Warnings for best effort observers which were disabled because they could not start
+    pub fn observer_warnings(&self) -> &[String] {
+        &self.observer_warnings
+    }
+
+    /// Attach an observer to a finalised runner.
+    ///
+    /// The observer is started immediately, failing as [`Builder::finalise`] does if it cannot
+    /// start. The returned id detaches it again with [`Runner::detach_observer`].
+    pub fn attach_observer<OBS: Observer<S> + 'static>(
+        &mut self,
+        observer: OBS,
+        frequency: impl Into<FrequencySet>,
+    ) -> Result<ObserverId, RunnerError> {
+        self.start_and_attach(Naming::of::<OBS>(), observer, frequency.into())
+    }
+
+    /// Attach an observer to a finalised runner under `name`, as [`Runner::attach_observer`]
+    pub fn attach_named_observer<OBS: Observer<S> + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        observer: OBS,
+        frequency: impl Into<FrequencySet>,
+    ) -> Result<ObserverId, RunnerError> {
+        self.start_and_attach(Naming::Given(name.into()), observer, frequency.into())
+    }
+
+    fn start_and_attach<OBS: Observer<S> + 'static>(
+        &mut self,
+        naming: Naming,
+        observer: OBS,
+        frequency: FrequencySet,
+    ) -> Result<ObserverId, RunnerError> {
+        observer
+            .start()
+            .map_err(|e| RunnerError::ObserverUnavailable {
+                index: self.observers.len(),
+                reason: e.to_string(),
+            })?;
+        Ok(self
+            .observers
+            .attach_with(Arc::new(observer), naming, frequency, Attachment::Required))
+    }
+
+    /// Detach every observer named `name`, returning whether any was attached.
+    ///
+    /// Observers attached without a name are named after their type, numbered if several share
+    /// a type, as listed by [`Runner::list_observers`].
+    pub fn detach_by_name(&mut self, name: &str) -> bool {
+        self.observers.detach_name(name)
+    }
+
+    /// The observers attached to the runner, in the order they are notified
+    pub fn list_observers(&self) -> Vec<ObserverPlan> {
+        self.observers.describe()
+    }
+
+    /// Detach an observer attached with [`Runner::attach_observer`], returning whether it was
+    /// attached
+    pub fn detach_observer(&mut self, id: ObserverId) -> bool {
+        self.observers.detach_id(id)
+    }
+
+    /// A handle for attaching and detaching observers from another thread while the run is in
+    /// progress
+    pub fn observer_handle(&self) -> ObserverHandle<S> {
+        self.observer_handle.clone()
+    }
+
+    /// Make the changes requested through observer handles since the last iteration
+    fn apply_observer_changes(&mut self) {
+        let warnings = self.observer_handle.apply(&mut self.observers);
+        self.observer_warnings.extend(warnings);
+    }
+
+    fn start_observers(&mut self) -> Result<(), RunnerError> {
+        self.observer_warnings =
+            self.observers
+                .start()
+                .map_err(|(index, e)| RunnerError::ObserverUnavailable {
+                    index,
+                    reason: e.to_string(),
+                })?;
+        Ok(())
+    }
+
+    /// The current time, relative to the start of the run
+    fn timestamp(&self) -> Timestamp {
+        // The clock is started when the run is prepared, before any observer is notified
+        let now = self.clock.monotonic();
+        Timestamp {
+            elapsed: now.saturating_sub(self.started.unwrap_or(now)),
+            wall: self.clock.wall(),
+        }
+    }
+
     pub(crate) fn observers(&self) -> ObserverSlice<'_, S> {
         self.observers.as_slice()
     }
@@ -91,7 +426,56 @@ impl<C, P, S, R> Runner<C, P, S, R> {
         Ok(None)
     }
 
-    fn initialise_control_c(&mut self) -> Result<Arc<AtomicBool>, Error> {
+    fn detect_container_limits(&self) -> ContainerLimits {
+        let limits = ContainerLimits::detect();
+        if limits.is_limited() {
+            info!(
+                cpus = limits.cpus,
+                memory_bytes = limits.memory_bytes,
+                "running under container limits"
+            );
+        }
+        limits
+    }
+
+    // Warn once each time memory usage crosses the threshold, rather than on every iteration.
+    // Reading the usage costs a file read, which would dominate cheap iterations, so it is read
+    // at most once per `MEMORY_CHECK_INTERVAL`.
+    fn check_memory_usage(&mut self) {
+        let now = self.clock.monotonic();
+        if self
+            .memory_checked
+            .is_some_and(|checked| now.saturating_sub(checked) < MEMORY_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.memory_checked = Some(now);
+        let (Some(threshold), Some(limits)) = (
+            self.memory_warning_threshold,
+            self.metadata
+                .as_ref()
+                .and_then(|metadata| metadata.container_limits.as_ref()),
+        ) else {
+            return;
+        };
+        let Some(fraction) = limits.memory_usage_fraction() else {
+            return;
+        };
+        if fraction >= threshold {
+            if !self.memory_warning_issued {
+                warn!(
+                    usage = fraction,
+                    memory_bytes = limits.memory_bytes,
+                    "memory usage is approaching the container limit"
+                );
+                self.memory_warning_issued = true;
+            }
+        } else {
+            self.memory_warning_issued = false;
+        }
+    }
+
+    fn initialise_control_c(&mut self) -> Result<Arc<AtomicBool>, RunnerError> {
         let received_kill_signal_from_control_c = Arc::new(AtomicBool::new(false));
 
         // #[cfg(feature = "ctrlc")]
@@ -105,6 +489,42 @@ impl<C, P, S, R> Runner<C, P, S, R> {
         //
         Ok(received_kill_signal_from_control_c)
     }
+
+    fn initialise_plugin_controllers(&mut self) -> Result<(), RunnerError> {
+        for controller in self.plugin_controllers.drain(..) {
+            self.signals.push(Killswitch {
+                caller: Caller::Controller,
+                inner: controller
+                    .spawn()
+                    .map_err(RunnerError::ControllerSpawnFailed)?,
+            });
+        }
+        if let Some(timeout) = self.iteration_timeout {
+            let (watchdog, timed_out) =
+                Watchdog::spawn(timeout).map_err(RunnerError::ControllerSpawnFailed)?;
+            self.signals.push(Killswitch {
+                caller: Caller::Watchdog,
+                inner: timed_out,
+            });
+            self.watchdog = Some(watchdog);
+        }
+        self.problem.set_cancel_checker(self.cancel_checker());
+        Ok(())
+    }
+
+    /// A handle reporting whether any of the run's kill signals has been received.
+    ///
+    /// Only signals started by the time it is taken are observed, so take it from a finalised
+    /// runner. Calculations can take the same handle from
+    /// [`Problem::cancel_checker`](crate::Problem::cancel_checker).
+    pub fn cancel_checker(&self) -> CancelChecker {
+        CancelChecker::new(
+            self.signals
+                .iter()
+                .map(|signal| signal.inner.clone())
+                .collect(),
+        )
+    }
 }
 
 impl<C, P, S, R> Runner<C, P, S, R>
@@ -112,6 +532,19 @@ where
     C: Calculation<P, S>,
     S: State,
 {
+    /// Deliver the iterations waiting in the batch to the observers
+    fn flush_batch(&mut self) {
+        if let Some(batch) = self.batch.as_mut() {
+            if !batch.pending.is_empty() {
+                let pending = std::mem::take(&mut batch.pending);
+                self.meter.observe(&*self.clock, || {
+                    self.observers
+                        .notify_batch(self.calculation.ident(), &pending)
+                });
+            }
+        }
+    }
+
     fn kill_signal_received(&self) -> bool {
         self.signals.iter().any(|signal| signal.is_dead())
     }
@@ -123,98 +556,620 @@ where
             .map(|signal| signal.caller.into())
     }
 
+    /// Call into the calculation, converting a panic into an error.
+    ///
+    /// Observers are notified that the run was aborted before the error is returned, so they can
+    /// flush anything they have buffered.
+    fn contain<T>(
+        &mut self,
+        f: impl FnOnce(&mut C, &mut Problem<P>) -> Result<T, C::Error>,
+    ) -> Result<T, TrellisError<C::Error>> {
+        let (calculation, problem) = (&mut self.calculation, &mut self.problem);
+        let outcome = self.meter.compute(&*self.clock, || {
+            panic::catch_unwind(AssertUnwindSafe(|| f(calculation, problem)))
+        });
+        match outcome {
+            Ok(result) => Ok(result?),
+            Err(payload) => {
+                let message = if let Some(message) = payload.downcast_ref::<&str>() {
+                    (*message).to_owned()
+                } else if let Some(message) = payload.downcast_ref::<String>() {
+                    message.clone()
+                } else {
+                    "unknown panic payload".to_owned()
+                };
+                error!(calculation = C::NAME, %message, "calculation panicked");
+                // Observers have already wrapped up if the panic came from finalising the output
+                let finalised = self
+                    .metadata
+                    .as_ref()
+                    .is_some_and(|metadata| metadata.final_iteration.is_some());
+                if !finalised {
+                    self.notify_aborted();
+                }
+                Err(ErrorKind::Panicked(message).into())
+            }
+        }
+    }
+
+    /// Notify observers that the run was aborted, handing them a fresh state in place of the one
+    /// lost to the panic.
+    ///
+    /// Runners built with a state of their own have no fresh one to give, and observers are not
+    /// notified.
+    fn notify_aborted(&mut self) {
+        self.flush_batch();
+        if let Some(fresh) = self.fresh {
+            let mut state = fresh();
+            state.set_run_id(self.run_id.clone());
+            self.apply_observer_changes();
+            let timestamp = self.timestamp();
+            self.meter.observe(&*self.clock, || {
+                self.observers
+                    .notify(self.calculation.ident(), &state, Stage::Aborted, &timestamp)
+            });
+        } else {
+            warn!(
+                calculation = C::NAME,
+                "no fresh state to hand observers, the aborted run is not reported"
+            );
+        }
+        self.forget_run();
+    }
+
+    /// Drop what writers and observers kept about the run in process-wide tables, once the last
+    /// observers have read it
+    fn forget_run(&self) {
+        #[cfg(feature = "writing")]
+        {
+            crate::writers::CompressionTally::forget(&self.run_id);
+            crate::watchers::forget_outages(&self.run_id);
+        }
+    }
+
     #[instrument(name = "initialising runner", skip_all)]
-    fn initialise(&mut self, state: S) -> Result<S, C::Error> {
-        let mut state = self.calculation.initialise(&mut self.problem, state)?;
+    fn initialise(&mut self, state: S) -> Result<S, TrellisError<C::Error>> {
+        let mut state =
+            self.contain(|calculation, problem| calculation.initialise(problem, state))?;
 
         state = state.update();
+        self.attach_report(&mut state);
 
-        self.observers
-            .update(C::NAME, &state, Stage::Initialisation);
+        let timestamp = self.timestamp();
+        self.meter.observe(&*self.clock, || {
+            self.observers.notify(
+                self.calculation.ident(),
+                &state,
+                Stage::Initialisation,
+                &timestamp,
+            )
+        });
 
         Ok(state)
     }
 
-    #[instrument(name = "performing iteration", skip_all)]
-    fn once(&mut self, state: S, maybe_start_time: Option<&Epoch>) -> Result<S, C::Error> {
-        let _maybe_iteration_start_time = self.now().unwrap();
+    #[instrument(
+        name = "performing iteration",
+        skip_all,
+        fields(iteration, measure, best_measure)
+    )]
+    fn once(
+        &mut self,
+        state: S,
+        maybe_start_time: Option<&Epoch>,
+    ) -> Result<S, TrellisError<C::Error>> {
+        self.apply_observer_changes();
 
-        let mut state = self.calculation.next(&mut self.problem, state)?;
+        let state = self.apply_tolerance_schedule(state);
+        if let Some(watchdog) = self.watchdog.as_ref() {
+            watchdog.arm();
+        }
+        let stepped = self.step(state);
+        if let Some(watchdog) = self.watchdog.as_ref() {
+            watchdog.disarm();
+        }
+        let mut state = stepped?;
 
-        if let Some(total_duration) = self.duration_since(maybe_start_time).unwrap() {
+        let elapsed = self.duration_since(maybe_start_time).unwrap();
+        if let Some(total_duration) = elapsed {
             state.record_time(total_duration);
         }
         state.increment_iteration();
         state = state.update();
+        state = self.guard_measure(state)?;
+        state = self.check_param_change(state);
+        self.attach_report(&mut state);
 
-        self.observers.update(C::NAME, &state, Stage::Iteration);
+        // The clock is only read for the history and observers, which are all that take the time
+        let timestamp =
+            (self.history.is_some() || !self.observers.is_empty()).then(|| self.timestamp());
+        if let (Some(history), Some(timestamp)) = (self.history.as_mut(), timestamp) {
+            history.push(state.measure().real(), timestamp);
+        }
+        self.progress = Some(RunProgress {
+            iteration: state.current_iteration(),
+            measure: state.measure().real(),
+            best_measure: state.best_measure().real(),
+            elapsed,
+        });
+
+        self.check_memory_usage();
+
+        if let Some(clone) = self.keep_best {
+            if state.iterations_since_best() == 0 {
+                self.best_state = Some(clone(&state));
+            }
+        }
+
+        // Record the outcome on the iteration span, so subscribers exporting to OpenTelemetry
+        // carry them as span attributes
+        let span = Span::current();
+        span.record("iteration", state.current_iteration());
+        span.record("measure", field::display(state.measure()));
+        span.record("best_measure", field::display(state.best_measure()));
+
+        let Some(timestamp) = timestamp else {
+            return Ok(state);
+        };
+        match self.batch.as_mut() {
+            Some(batch) => {
+                batch.pending.push(((batch.snapshot)(&state), timestamp));
+                if batch.pending.len() >= batch.size {
+                    self.flush_batch();
+                }
+            }
+            None => self.meter.observe(&*self.clock, || {
+                self.observers.notify(
+                    self.calculation.ident(),
+                    &state,
+                    Stage::Iteration,
+                    &timestamp,
+                )
+            }),
+        }
 
         Ok(state)
     }
 
     #[instrument(name = "finalising runner", skip_all)]
-    fn finalise(&mut self, state: S) -> Result<C::Output, C::Error> {
-        let result = self.calculation.finalise(&mut self.problem, state)?;
+    fn finalise(&mut self, mut state: S) -> Result<C::Output, TrellisError<C::Error>> {
+        self.notify_finalisation(&mut state);
 
-        Ok(result)
+        self.contain(|calculation, problem| calculation.finalise(problem, state))
     }
 
-    /// Execute the runner
-    #[instrument(name = "running trellis computation", skip_all)]
-    pub fn run(mut self) -> Result<C::Output, C::Error> {
-        // Todo: Load checkpoints?
-        let start_time = self.now().unwrap();
+    /// Terminate the run, remembering why for observers in case the state does not record it
+    fn terminate(&mut self, state: S, reason: Reason) -> S {
+        self.termination = Some(reason.clone());
+        state.terminate_due_to(reason)
+    }
+
+    /// Terminate once the parameter has stopped changing, if convergence on it is enabled
+    fn check_param_change(&mut self, state: S) -> S {
+        let Some(param_change) = self.param_change.as_mut() else {
+            return state;
+        };
+        match (param_change.change)(&state) {
+            Some(change) if change < param_change.tolerance && !state.is_terminated() => {
+                info!(
+                    iteration = state.current_iteration(),
+                    change, "terminating as the parameter has converged"
+                );
+                self.terminate(state, Reason::Converged)
+            }
+            _ => state,
+        }
+    }
 
-        let mut state = self.state.take().unwrap();
+    /// Remember the parameter the run starts from, so the first iteration's change is measured
+    fn remember_param(&mut self, state: &S) {
+        if let Some(param_change) = self.param_change.as_mut() {
+            (param_change.change)(state);
+        }
+    }
 
-        // TODO: This only really matters if there is a checkpoint loaded, at the moment we have
-        // none so the check is redundant
-        state = if !state.is_initialised() {
-            self.initialise(state)?
-        } else {
-            state
+    /// Attach the values the calculation reports for `state`, for observers to read
+    fn attach_report(&self, state: &mut S) {
+        let reported = self.calculation.report(state);
+        let Some(extensions) = state.extensions_mut() else {
+            return;
+        };
+        // Replace the last report in place, and only insert one which has something to say, to
+        // spare cheap iterations an allocation
+        match extensions.get_mut::<ReportedKV>() {
+            Some(last) => *last = ReportedKV(reported),
+            None if !reported.is_empty() => {
+                extensions.insert(ReportedKV(reported));
+            }
+            None => {}
+        }
+    }
+
+    fn guard_measure(&mut self, state: S) -> Result<S, TrellisError<C::Error>> {
+        let measure = state.measure().real();
+        let valid = measure.is_finite() && !(self.non_negative_measure && measure < 0.0);
+        if valid {
+            return Ok(state);
+        }
+        match self.invalid_measure_policy {
+            InvalidMeasurePolicy::Ignore => Ok(state),
+            InvalidMeasurePolicy::Terminate => {
+                warn!(
+                    iteration = state.current_iteration(),
+                    measure, "terminating due to invalid measure"
+                );
+                Ok(self.terminate(state, Reason::InvalidMeasure))
+            }
+            InvalidMeasurePolicy::Error => Err(ErrorKind::InvalidMeasure {
+                iteration: state.current_iteration(),
+                measure,
+            }
+            .into()),
+        }
+    }
+
+    /// Grade the final state and notify observers that the run is complete
+    fn notify_finalisation(&mut self, state: &mut S) -> Grade {
+        if let Some(metadata) = self.metadata.as_mut() {
+            metadata.final_iteration = Some(state.current_iteration());
+        }
+
+        let grade = self.calculation.grade(state);
+        info!(calculation = C::NAME, %grade, "run complete");
+
+        self.flush_batch();
+        self.apply_observer_changes();
+        // Observers writing a report of the run read the ledger as it stands now
+        let ledger = self.resources(state);
+        if let Some(extensions) = state.extensions_mut() {
+            extensions.insert(ledger);
+        }
+        let state = &*state;
+        let reason = state
+            .termination_reason()
+            .or_else(|| self.termination.clone());
+        let timestamp = self.timestamp();
+        self.meter.observe(&*self.clock, || {
+            self.observers.notify_termination(
+                self.calculation.ident(),
+                state,
+                reason.as_ref(),
+                &timestamp,
+            );
+            self.observers.notify(
+                self.calculation.ident(),
+                state,
+                Stage::Finalisation,
+                &timestamp,
+            )
+        });
+        self.forget_run();
+
+        grade
+    }
+
+    /// Account for the resources the run has consumed so far
+    fn resources(&self, state: &S) -> crate::ResourceLedger {
+        self.meter.ledger(
+            self.progress
+                .and_then(|progress| progress.elapsed)
+                .map(|elapsed| elapsed.to_seconds()),
+            state.current_iteration(),
+            self.problem.evaluations(),
+        )
+    }
+
+    /// Take the state from the runner, initialising it if required
+    fn prepare(&mut self) -> Result<S, TrellisError<C::Error>> {
+        // A run continuing from a probe keeps the metadata created when the probe started
+        if self.metadata.is_none() {
+            let mut metadata =
+                RunMetadata::new(self.run_id.clone(), C::NAME, C::VERSION, self.parent.take());
+            metadata.container_limits = Some(self.detect_container_limits());
+            metadata.flags = self.problem.flags().describe();
+            metadata.plugins = self.plugins.iter().map(ToString::to_string).collect();
+            metadata.annotations = self.annotations.clone();
+            self.metadata = Some(metadata);
+            self.started = Some(self.clock.monotonic());
+            self.meter.start();
+            self.thread.apply();
+        }
+
+        let mut state = self.state.take().ok_or(ErrorKind::StateUnavailable)?;
+        state.set_run_id(self.run_id.clone());
+        if let (Some(extensions), Some(metadata)) = (state.extensions_mut(), self.metadata.as_ref())
+        {
+            extensions.insert(metadata.clone());
+        }
+
+        // A state carried over with `Builder::warm_start` has already been initialised
+        if !state.is_initialised() {
+            state = self.initialise(state)?;
+        }
+        self.remember_param(&state);
+        Ok(state)
+    }
+
+    /// Perform one step of the calculation, recovering from errors as configured
+    fn step(&mut self, state: S) -> Result<S, TrellisError<C::Error>> {
+        let Some((policy, snapshot)) = self
+            .recovery
+            .as_ref()
+            .filter(|recovery| recovery.policy != IterationErrorPolicy::Abort)
+            .map(|recovery| (recovery.policy, recovery.snapshot))
+        else {
+            return self.contain(|calculation, problem| calculation.next(problem, state));
         };
 
+        let mut attempts = 0;
+        let mut backoff = match policy {
+            IterationErrorPolicy::Retry { backoff, .. } => backoff,
+            _ => std::time::Duration::ZERO,
+        };
         loop {
-            if self.kill_signal_received() {
-                state = state.terminate_due_to(self.kill_cause().unwrap());
-                break;
+            attempts += 1;
+            let attempt = snapshot(&state);
+            let error =
+                match self.contain(|calculation, problem| calculation.next(problem, attempt)) {
+                    Ok(next) => return Ok(self.record_attempts(next, attempts, false)),
+                    // A panic is not recovered from
+                    Err(error) if error.calculation_error().is_none() => return Err(error),
+                    Err(error) => error,
+                };
+            let recovery = self.recovery.as_mut().unwrap();
+            recovery.failures += 1;
+            let iteration = state.current_iteration() + 1;
+            match policy {
+                IterationErrorPolicy::Retry { max, .. } if attempts <= max => {
+                    warn!(iteration, attempts, %error, "iteration failed, retrying");
+                    std::thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                IterationErrorPolicy::SkipIteration => {
+                    warn!(iteration, %error, "iteration failed, skipping it");
+                    return Ok(self.record_attempts(state, attempts, true));
+                }
+                _ => return Err(error),
             }
-            if state.is_terminated() {
-                break;
+        }
+    }
+
+    fn record_attempts(&self, mut state: S, attempts: usize, skipped: bool) -> S {
+        let failures = self
+            .recovery
+            .as_ref()
+            .map_or(0, |recovery| recovery.failures);
+        if let Some(extensions) = state.extensions_mut() {
+            extensions.insert(IterationAttempts {
+                attempts,
+                skipped,
+                failures,
+            });
+        }
+        state
+    }
+
+    /// Pass the scheduled tolerance for the coming iteration to the state
+    fn apply_tolerance_schedule(&self, mut state: S) -> S {
+        if let Some(schedule) = self.tolerance_schedule.as_ref() {
+            state.set_relative_tolerance(schedule(state.current_iteration() + 1));
+        }
+        state
+    }
+
+    /// Whether a soft cancellation allows another iteration after a kill signal.
+    ///
+    /// The run stops once an iteration in the grace period improves the best measure, or when the
+    /// grace period is exhausted.
+    fn continue_after_kill(&mut self, state: &S) -> bool {
+        let Some(limit) = self.soft_cancel else {
+            return false;
+        };
+        // A timed out run could overrun again, so it is not given a grace period
+        if self.kill_cause() == Some(Reason::IterationTimedOut) {
+            return false;
+        }
+        let remaining = match self.grace_remaining {
+            None => {
+                info!(
+                    calculation = C::NAME,
+                    max_iterations = limit,
+                    "kill signal received, finishing the current plateau"
+                );
+                limit
+            }
+            Some(_) if state.iterations_since_best() == 0 => return false,
+            Some(remaining) => remaining,
+        };
+        if remaining == 0 {
+            return false;
+        }
+        self.grace_remaining = Some(remaining - 1);
+        true
+    }
+
+    fn evaluation_budget_exhausted(&self, state: &S) -> bool {
+        state
+            .max_evaluations()
+            .is_some_and(|budget| self.problem.evaluations().unwrap_or(0) >= budget)
+    }
+
+    /// Perform the next iteration, or break if the run should terminate
+    fn advance(
+        &mut self,
+        state: S,
+        maybe_start_time: Option<&Epoch>,
+    ) -> Result<ControlFlow<S, S>, TrellisError<C::Error>> {
+        if self.kill_signal_received() && !self.continue_after_kill(&state) {
+            let cause = self.kill_cause().unwrap();
+            return Ok(ControlFlow::Break(self.terminate(state, cause)));
+        }
+        if state.is_terminated() {
+            return Ok(ControlFlow::Break(state));
+        }
+        if self.evaluation_budget_exhausted(&state) {
+            return Ok(ControlFlow::Break(
+                self.terminate(state, Reason::ExceededEvaluationBudget),
+            ));
+        }
+        if self.predicates.iter().any(|predicate| predicate(&state)) {
+            return Ok(ControlFlow::Break(
+                self.terminate(state, Reason::UserPredicate),
+            ));
+        }
+        Ok(ControlFlow::Continue(self.once(state, maybe_start_time)?))
+    }
+
+    /// Iterate until termination, returning the final state
+    fn iterate(&mut self) -> Result<S, TrellisError<C::Error>> {
+        self.iterate_to_termination()
+            .map_err(|e| e.with_progress(self.progress))
+    }
+
+    fn iterate_to_termination(&mut self) -> Result<S, TrellisError<C::Error>> {
+        // Todo: Load checkpoints?
+        let start_time = self.now().unwrap();
+
+        let mut state = self.prepare()?;
+
+        loop {
+            match self.advance(state, start_time.as_ref())? {
+                ControlFlow::Continue(next) => state = next,
+                ControlFlow::Break(last) => match self.restart(last)? {
+                    ControlFlow::Continue(next) => state = next,
+                    ControlFlow::Break(last) => return Ok(last),
+                },
             }
-            state = self.once(state, start_time.as_ref())?;
         }
+    }
 
-        let result = self.finalise(state)?;
+    /// Restart a run which terminated without success, or break if the restart policy does not
+    /// allow it
+    fn restart(&mut self, state: S) -> Result<ControlFlow<S, S>, TrellisError<C::Error>> {
+        let reason = state
+            .termination_reason()
+            .or_else(|| self.termination.clone());
+        let restarts = self.restarts;
+        let Some(reason) = reason.filter(|reason| {
+            self.restart.as_ref().is_some_and(|policy| {
+                restarts < policy.max_restarts() && policy.restarts_on(Some(reason))
+            })
+        }) else {
+            return Ok(ControlFlow::Break(state));
+        };
+
+        self.restarts += 1;
+        let iteration = state.current_iteration();
+        info!(
+            calculation = C::NAME,
+            attempt = self.restarts,
+            iteration,
+            %reason,
+            "restarting run"
+        );
+        self.flush_batch();
+        self.apply_observer_changes();
+        let timestamp = self.timestamp();
+        self.meter.observe(&*self.clock, || {
+            self.observers
+                .notify(self.calculation.ident(), &state, Stage::Restart, &timestamp)
+        });
+
+        self.termination = None;
+        let policy = self.restart.as_mut().unwrap();
+        let state = policy.restart(&mut self.problem, state.for_warm_start(), self.restarts);
+        let mut state = self
+            .contain(|calculation, problem| calculation.initialise(problem, state))?
+            .update();
+        self.attach_report(&mut state);
+        if let Some(extensions) = state.extensions_mut() {
+            extensions.insert(RestartAttempt {
+                attempt: self.restarts,
+                iteration,
+                reason,
+            });
+        }
+        self.remember_param(&state);
+        Ok(ControlFlow::Continue(state))
+    }
+
+    /// Execute the runner
+    #[instrument(
+        name = "running trellis computation",
+        skip_all,
+        fields(calculation = C::NAME, version = C::VERSION)
+    )]
+    pub fn run(mut self) -> Result<C::Output, TrellisError<C::Error>> {
+        let state = self.iterate()?;
+
+        let result = self
+            .finalise(state)
+            .map_err(|e| e.with_progress(self.progress))?;
 
         Ok(result)
     }
+
+    /// Execute the runner, returning the calculation, problem and state rather than finalising.
+    ///
+    /// This gives the caller access to everything the run produced, including the best state
+    /// when the runner was built with [`Builder::keep_best`].
+    #[instrument(
+        name = "running trellis computation",
+        skip_all,
+        fields(calculation = C::NAME, version = C::VERSION)
+    )]
+    pub fn run_to_output(mut self) -> Result<Output<C, P, S>, TrellisError<C::Error>> {
+        let mut state = self.iterate()?;
+
+        let grade = self.notify_finalisation(&mut state);
+        let resources = self.resources(&state);
+        let (history, timestamps) = self
+            .history
+            .map(|history| (history.measures.into(), history.timestamps.into()))
+            .unwrap_or_default();
+
+        Ok(Output::new(
+            self.problem,
+            self.calculation,
+            state,
+            grade,
+            self.metadata.take().unwrap(),
+        )
+        .with_best_state(self.best_state)
+        .with_history(history, timestamps)
+        .with_wall_time(self.progress.and_then(|progress| progress.elapsed))
+        .with_resources(resources))
+    }
 }
 
 impl<C, P, S, R> Runner<C, P, S, R>
 where
     R: Control + 'static,
 {
-    fn initialise_kill_signal_handler(&mut self) -> Result<Arc<AtomicBool>, Error> {
+    fn initialise_kill_signal_handler(&mut self) -> Result<Arc<AtomicBool>, RunnerError> {
         let received_kill_signal_from_controller = Arc::new(AtomicBool::new(false));
 
         // Clone the state as the value needs to move into the closure
         let state = received_kill_signal_from_controller.clone();
-        set_handler(self.controller.take().unwrap(), move || {
+        let controller = self
+            .controller
+            .take()
+            .ok_or(RunnerError::AlreadyFinalised)?;
+        set_handler(controller, move || {
             state.store(true, Ordering::SeqCst);
-        })?;
+        })
+        .map_err(RunnerError::ControllerSpawnFailed)?;
 
         Ok(received_kill_signal_from_controller)
     }
 }
 
 pub trait InitialiseRunner {
-    fn initialise_controllers(&mut self) -> Result<(), Error>;
+    fn initialise_controllers(&mut self) -> Result<(), RunnerError>;
 }
 
 impl<C, P, S> InitialiseRunner for Runner<C, P, S, ()> {
-    fn initialise_controllers(&mut self) -> Result<(), Error> {
+    fn initialise_controllers(&mut self) -> Result<(), RunnerError> {
         if self.control_c {
             let received_kill_signal_from_control_c = Killswitch {
                 caller: Caller::CtrlC,
@@ -222,7 +1177,7 @@ impl<C, P, S> InitialiseRunner for Runner<C, P, S, ()> {
             };
             self.signals = vec![received_kill_signal_from_control_c];
         }
-        Ok(())
+        self.initialise_plugin_controllers()
     }
 }
 
@@ -230,7 +1185,7 @@ impl<C, P, S, R> InitialiseRunner for Runner<C, P, S, R>
 where
     R: Control + 'static,
 {
-    fn initialise_controllers(&mut self) -> Result<(), Error> {
+    fn initialise_controllers(&mut self) -> Result<(), RunnerError> {
         if self.control_c {
             let received_kill_signal_from_control_c = Killswitch {
                 caller: Caller::CtrlC,
@@ -244,6 +1199,6 @@ where
             inner: self.initialise_kill_signal_handler()?,
         };
         self.signals.push(received_kill_signal_from_controller);
-        Ok(())
+        self.initialise_plugin_controllers()
     }
 }
@@ -0,0 +1,138 @@
+use std::fmt;
+
+use super::{InvalidMeasurePolicy, IterationErrorPolicy};
+use crate::{watchers::ObserverPlan, RunId, KV};
+
+/// A description of what a runner would do, produced by [`Builder::plan`](super::Builder::plan)
+/// without running any iterations.
+///
+/// Command line tools can print the plan for a `--dry-run` flag, and tests can assert on the
+/// configuration of a builder directly.
+#[derive(Clone, Debug)]
+pub struct Plan {
+    /// Name of the calculation
+    pub calculation: &'static str,
+    /// Configuration reported by the initial state, such as tolerances and iteration limits
+    pub state: KV,
+    /// Whether the state has already been initialised, as it is when warm starting
+    pub warm_start: bool,
+    /// The number of stopping predicates added with `terminate_if`
+    pub predicates: usize,
+    /// Whether the relative tolerance follows a schedule
+    pub tolerance_schedule: bool,
+    /// The relative change in the parameter below which the run converges, if set
+    pub param_tolerance: Option<f64>,
+    /// How an invalid measure is handled
+    pub invalid_measure_policy: InvalidMeasurePolicy,
+    /// How a failed iteration is handled
+    pub iteration_error_policy: IterationErrorPolicy,
+    /// The most times an unsuccessful run is restarted, if a restart policy is set
+    pub max_restarts: Option<usize>,
+    /// Whether a negative measure is invalid
+    pub non_negative_measure: bool,
+    /// Iterations allowed after a kill signal while waiting for an improvement
+    pub soft_cancel: Option<usize>,
+    /// The longest a single iteration may take, if limited
+    pub iteration_timeout: Option<std::time::Duration>,
+    /// The niceness the run thread is given, if set
+    pub niceness: Option<i32>,
+    /// The core the run thread is pinned to, if any
+    pub pinned_core: Option<usize>,
+    /// Whether population members are evaluated in parallel
+    pub parallel_evaluations: bool,
+    /// Whether the run can be cancelled with control-c
+    pub control_c: bool,
+    /// The type of the external controller, if one is attached
+    pub controller: Option<&'static str>,
+    /// Whether the run is timed
+    pub timed: bool,
+    /// Whether the state with the best measure is kept
+    pub keep_best: bool,
+    /// The number of iterations observers are notified of at once, if notifications are batched
+    pub batch_notifications: Option<usize>,
+    /// The run this one continues, if any
+    pub parent: Option<RunId>,
+    /// Feature flags set on the builder
+    pub flags: KV,
+    /// Names of the installed plugins
+    pub plugins: Vec<&'static str>,
+    /// The observers attached to the builder
+    pub observers: Vec<ObserverPlan>,
+    /// The number of process-wide default observers which will also be attached
+    pub default_observers: usize,
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.calculation)?;
+        for (key, value) in self.state.iter() {
+            writeln!(f, "  {key}: {value}")?;
+        }
+        if self.warm_start {
+            writeln!(f, "  warm start")?;
+        }
+        if let Some(parent) = self.parent.as_ref() {
+            writeln!(f, "  continues run {parent}")?;
+        }
+        for (name, value) in self.flags.iter() {
+            writeln!(f, "  flag {name}: {value}")?;
+        }
+        for plugin in &self.plugins {
+            writeln!(f, "  plugin {plugin}")?;
+        }
+        writeln!(f, "  predicates: {}", self.predicates)?;
+        if self.tolerance_schedule {
+            writeln!(f, "  scheduled tolerance")?;
+        }
+        if let Some(tolerance) = self.param_tolerance {
+            writeln!(f, "  parameter tolerance: {tolerance:e}")?;
+        }
+        writeln!(f, "  invalid measures: {:?}", self.invalid_measure_policy)?;
+        writeln!(f, "  iteration errors: {:?}", self.iteration_error_policy)?;
+        if let Some(max_restarts) = self.max_restarts {
+            writeln!(f, "  up to {max_restarts} restarts")?;
+        }
+        if self.non_negative_measure {
+            writeln!(f, "  negative measures are invalid")?;
+        }
+        if let Some(grace) = self.soft_cancel {
+            writeln!(f, "  soft cancel: up to {grace} iterations")?;
+        }
+        if let Some(timeout) = self.iteration_timeout {
+            writeln!(f, "  iterations time out after {timeout:?}")?;
+        }
+        if let Some(niceness) = self.niceness {
+            writeln!(f, "  thread niceness: {niceness}")?;
+        }
+        if let Some(core) = self.pinned_core {
+            writeln!(f, "  pinned to core {core}")?;
+        }
+        if self.parallel_evaluations {
+            writeln!(f, "  parallel evaluations")?;
+        }
+        writeln!(f, "  control-c: {}", self.control_c)?;
+        if let Some(controller) = self.controller {
+            writeln!(f, "  controller: {controller}")?;
+        }
+        writeln!(f, "  timed: {}", self.timed)?;
+        writeln!(f, "  keep best: {}", self.keep_best)?;
+        if let Some(iterations) = self.batch_notifications {
+            writeln!(f, "  notifications batched every {iterations} iterations")?;
+        }
+        writeln!(f, "  observers:")?;
+        for (index, observer) in self.observers.iter().enumerate() {
+            write!(f, "    {index}: {}, {}", observer.name, observer.frequency)?;
+            if !observer.required {
+                write!(f, ", best effort")?;
+            }
+            if let Some(path) = observer.output_path.as_ref() {
+                write!(f, ", writing to {}", path.display())?;
+            }
+            writeln!(f)?;
+        }
+        if self.default_observers > 0 {
+            writeln!(f, "    and {} default observers", self.default_observers)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+//! Reusable bundles of observers, stopping rules, controllers and metadata.
+//!
+//! Concerns which span several extension points, such as a lab's standard telemetry, ship as a
+//! single [`Plugin`] installed with [`Builder::with_plugin`](super::Builder::with_plugin)
+//! instead of a list of builder calls every user must repeat.
+use std::sync::Arc;
+
+use super::Predicate;
+use crate::{
+    controller::Spawner,
+    watchers::{Attachment, FrequencySet, Naming, Observer, ObserverVec},
+    Control, KV,
+};
+
+/// A bundle of extensions installed on a builder in one call
+pub trait Plugin<S> {
+    /// The name recorded in the metadata of runs the plugin is installed on
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Attach the plugin's observers, stopping rules and controllers
+    fn install(self, installer: &mut Installer<'_, S>);
+}
+
+/// The extension points available to a [`Plugin`] as it is installed
+pub struct Installer<'a, S> {
+    pub(super) name: &'static str,
+    pub(super) observers: &'a mut ObserverVec<S>,
+    pub(super) predicates: &'a mut Vec<Predicate<S>>,
+    pub(super) controllers: &'a mut Vec<Spawner>,
+    pub(super) annotations: &'a mut KV,
+}
+
+impl<S> Installer<'_, S> {
+    /// Attach an observer, as [`Builder::attach_observer`](super::Builder::attach_observer)
+    pub fn attach_observer<OBS: Observer<S> + 'static>(
+        &mut self,
+        observer: OBS,
+        frequency: impl Into<FrequencySet>,
+    ) -> &mut Self {
+        self.observers.attach_with(
+            Arc::new(observer),
+            Naming::of::<OBS>(),
+            frequency.into(),
+            Attachment::Required,
+        );
+        self
+    }
+
+    /// Attach an observer the run can do without, as
+    /// [`Builder::attach_best_effort_observer`](super::Builder::attach_best_effort_observer)
+    pub fn attach_best_effort_observer<OBS: Observer<S> + 'static>(
+        &mut self,
+        observer: OBS,
+        frequency: impl Into<FrequencySet>,
+    ) -> &mut Self {
+        self.observers.attach_with(
+            Arc::new(observer),
+            Naming::of::<OBS>(),
+            frequency.into(),
+            Attachment::BestEffort,
+        );
+        self
+    }
+
+    /// Terminate the run when `predicate` holds, as
+    /// [`Builder::terminate_if`](super::Builder::terminate_if)
+    pub fn terminate_if<F>(&mut self, predicate: F) -> &mut Self
+    where
+        F: Fn(&S) -> bool + 'static,
+    {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    /// Stop the run when `controller` sends a kill signal.
+    ///
+    /// Controllers added by plugins run alongside the builder's own controller, and the run
+    /// terminates with [`Reason::Controller`](crate::Reason::Controller) when any of them fires.
+    pub fn add_controller<R: Control + 'static>(&mut self, controller: R) -> &mut Self {
+        self.controllers.push(Spawner::new(controller));
+        self
+    }
+
+    /// Record a value in the metadata of the run, under a key prefixed with the plugin's name
+    pub fn annotate(&mut self, key: &str, value: impl std::fmt::Display) -> &mut Self {
+        self.annotations.push(format!("{}.{key}", self.name), value);
+        self
+    }
+}
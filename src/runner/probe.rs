@@ -0,0 +1,107 @@
+//! Estimating the cost of a run from its first few iterations.
+//!
+//! Schedulers deciding where to place a run can execute a handful of iterations with
+//! [`Runner::probe`], read off the time per iteration and the initial contraction of the
+//! measure, and extrapolate to the cost of reaching a target. The runner keeps its state, so a
+//! run judged worth finishing continues where the probe stopped.
+use std::ops::ControlFlow;
+
+use hifitime::Duration;
+
+use super::Runner;
+use crate::{Calculation, Measure, State, TrellisError};
+
+/// The cost of a run extrapolated from a probe
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ProbeEstimate {
+    /// The number of iterations performed by the probe
+    pub iterations: usize,
+    /// Mean wall time per probed iteration
+    pub per_iteration: Duration,
+    /// The mean factor by which the measure shrank per iteration, if it shrank at all
+    pub contraction: Option<f64>,
+    /// Whether the run terminated during the probe
+    pub terminated: bool,
+    /// Further iterations needed to reach the target, `None` if the measure is not shrinking
+    pub remaining_iterations: Option<usize>,
+    /// Wall time needed to reach the target at the probed cost per iteration
+    pub remaining_time: Option<Duration>,
+}
+
+impl<C, P, S, R> Runner<C, P, S, R>
+where
+    C: Calculation<P, S>,
+    S: State,
+{
+    /// Perform up to `iterations` iterations and estimate the cost of reducing the measure to
+    /// `target`.
+    ///
+    /// The estimate assumes the measure keeps shrinking at the mean rate seen during the probe,
+    /// which is conservative for methods which converge faster than linearly. The probed
+    /// iterations are observed as usual, and a later call to [`Runner::run`] continues from the
+    /// last probed iteration. If the calculation fails during the probe the state is lost, and
+    /// a later run returns [`ErrorKind::StateUnavailable`](crate::ErrorKind::StateUnavailable).
+    pub fn probe(
+        &mut self,
+        iterations: usize,
+        target: f64,
+    ) -> Result<ProbeEstimate, TrellisError<C::Error>> {
+        let start_time = self.now().unwrap();
+        let mut state = self.prepare()?;
+        let initial_measure = state.measure().real();
+        let initial_iteration = state.current_iteration();
+
+        let probe_start = self.clock.epoch();
+        let mut terminated = false;
+        for _ in 0..iterations {
+            match self
+                .advance(state, start_time.as_ref())
+                .map_err(|e| e.with_progress(self.progress))?
+            {
+                ControlFlow::Continue(next) => state = next,
+                ControlFlow::Break(last) => {
+                    state = last;
+                    terminated = true;
+                    break;
+                }
+            }
+        }
+        let elapsed = self.clock.epoch() - probe_start;
+
+        let probed = state.current_iteration() - initial_iteration;
+        let measure = state.measure().real();
+        let per_iteration = if probed > 0 {
+            elapsed / probed as f64
+        } else {
+            Duration::ZERO
+        };
+
+        // Mean contraction over the probe, ignoring measures which cannot be compared on a log
+        // scale
+        let contraction = (probed > 0
+            && initial_measure.is_finite()
+            && initial_measure > 0.0
+            && measure.is_finite()
+            && measure > 0.0)
+            .then(|| (measure / initial_measure).powf(1.0 / probed as f64))
+            .filter(|&rate| rate < 1.0);
+
+        let remaining_iterations = if measure <= target {
+            Some(0)
+        } else {
+            contraction.map(|rate| ((target / measure).ln() / rate.ln()).ceil() as usize)
+        };
+        let remaining_time = remaining_iterations.map(|remaining| per_iteration * remaining as f64);
+
+        self.state = Some(state);
+
+        Ok(ProbeEstimate {
+            iterations: probed,
+            per_iteration,
+            contraction,
+            terminated,
+            remaining_iterations,
+            remaining_time,
+        })
+    }
+}
@@ -0,0 +1,576 @@
+//! Executing many runs with bounded concurrency.
+//!
+//! Runners hold observers and closures which cannot be sent between threads, so the queue
+//! accepts jobs which build and execute their runner on the worker thread. Each job is handed a
+//! clone of the queue's [`Cancellation`], which it can attach to its runner as a controller so
+//! that cancelling the queue also stops the runs in flight.
+//!
+//! Runs are started in order of priority, and in the order they were pushed within a priority.
+//! Workers take the next run as soon as they finish the last, so sweeps whose runs vary widely in
+//! cost keep every worker busy rather than waiting on the slowest of a static chunk. Runs can
+//! also be pushed while the queue executes, through a [`Submitter`].
+//!
+//! Preemptible runs are stopped to make way for runs of higher priority which arrive while every
+//! worker is busy, and resumed from their checkpoints once a worker is free.
+//!
+//! A job which panics fails with [`QueueError::Panicked`] without disturbing the other runs.
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+use hifitime::{Duration, Epoch};
+
+use super::{Installer, Plugin};
+use crate::{
+    watchers::{Frequency, Observer, Stage},
+    CacheStats, Cancellation, Reason, Timestamp, WarmCache,
+};
+
+type Job<O, E> = Box<dyn FnMut(Cancellation, Preemption) -> Result<O, E> + Send>;
+
+// How often idle workers check whether the queue was cancelled
+const IDLE_POLL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Why a queued run failed
+#[derive(Debug, thiserror::Error)]
+pub enum QueueError<E> {
+    /// The job returned an error
+    #[error("{0}")]
+    Run(E),
+    /// The job panicked, with the message carried by the panic
+    #[error("run panicked: {0}")]
+    Panicked(String),
+}
+
+// A job which panics is reported as failed rather than poisoning the queue, so the queue's own
+// locks are only poisoned by a bug in the queue, after which its data is still consistent
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Execute a job, turning a panic into an error
+fn execute<O, E>(
+    job: &mut Job<O, E>,
+    cancellation: Cancellation,
+    preemption: Preemption,
+) -> Result<O, QueueError<E>> {
+    match panic::catch_unwind(AssertUnwindSafe(|| job(cancellation, preemption))) {
+        Ok(result) => result.map_err(QueueError::Run),
+        Err(payload) => {
+            let message = if let Some(message) = payload.downcast_ref::<&str>() {
+                (*message).to_owned()
+            } else if let Some(message) = payload.downcast_ref::<String>() {
+                message.clone()
+            } else {
+                "unknown panic payload".to_owned()
+            };
+            tracing::error!(%message, "queued run panicked");
+            Err(QueueError::Panicked(message))
+        }
+    }
+}
+
+/// Handed to a preemptible run each time the queue starts it.
+///
+/// Install it on the run's builder with [`Builder::with_plugin`](crate::Builder::with_plugin),
+/// which adds it as a controller, so the run terminates when the queue preempts it. The run
+/// should checkpoint as it finalises, for example with a [`Checkpointer`](crate::Checkpointer),
+/// and when [`Preemption::resumes`] is non-zero continue from the checkpoint with
+/// [`Builder::resume_from_checkpoint`](crate::Builder::resume_from_checkpoint).
+///
+/// The result of a run which stopped for the preemption is discarded, and the run resumed. A
+/// run which returns for any other reason, even after being asked to make way, is finished and
+/// keeps its result.
+#[derive(Clone)]
+pub struct Preemption {
+    signal: Cancellation,
+    resumes: usize,
+    /// Set once the run has stopped because it was preempted
+    stopped: Arc<AtomicBool>,
+}
+
+impl Preemption {
+    /// Whether the queue has asked the run to make way for one of higher priority
+    pub fn is_preempted(&self) -> bool {
+        self.signal.is_cancelled()
+    }
+
+    /// The number of times the run was preempted before this start
+    pub fn resumes(&self) -> usize {
+        self.resumes
+    }
+
+    /// Record that the run stopped because it was preempted, so it is resumed later.
+    ///
+    /// Called by the plugin when the runner terminates for the preemption. Jobs which watch
+    /// [`Preemption::is_preempted`] themselves call it before returning early.
+    pub fn mark_stopped(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+}
+
+impl<S> Plugin<S> for Preemption {
+    fn name(&self) -> &'static str {
+        "preemption"
+    }
+
+    fn install(self, installer: &mut Installer<'_, S>) {
+        installer.add_controller(self.signal.clone());
+        installer.attach_observer(self, Frequency::OnExit);
+    }
+}
+
+// Tells a run stopped by the preemption from one which terminated of its own accord
+impl<S> Observer<S> for Preemption {
+    fn observe(&self, _ident: &'static str, _subject: &S, _stage: Stage) {}
+
+    fn observe_termination(
+        &self,
+        _ident: &'static str,
+        _subject: &S,
+        reason: Option<&Reason>,
+        _timestamp: &Timestamp,
+    ) {
+        if self.is_preempted() && reason == Some(&Reason::Controller) {
+            self.mark_stopped();
+        }
+    }
+}
+
+/// Aggregate progress of the runs in a queue
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct QueueProgress {
+    /// The number of runs submitted to the queue
+    pub total: usize,
+    /// The number of runs which have started
+    pub started: usize,
+    /// The number of runs which returned successfully
+    pub succeeded: usize,
+    /// The number of runs which returned an error or panicked
+    pub failed: usize,
+    /// The number of times a run was preempted by one of higher priority
+    pub preempted: usize,
+}
+
+impl QueueProgress {
+    /// The number of runs which have returned
+    pub fn finished(&self) -> usize {
+        self.succeeded + self.failed
+    }
+}
+
+/// How much of a queue's execution a single worker spent running
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct WorkerUtilisation {
+    /// The number of runs the worker executed
+    pub runs: usize,
+    /// The time the worker spent executing runs
+    pub busy: Duration,
+}
+
+/// The results of a queue, alongside how the work was distributed
+pub struct QueueSummary<O, E> {
+    /// The result of each run in the order the runs were pushed, `None` if it never started
+    pub results: Vec<Option<Result<O, QueueError<E>>>>,
+    /// The utilisation of each worker
+    pub workers: Vec<WorkerUtilisation>,
+    /// Time taken to execute the queue
+    pub wall_time: Duration,
+    /// How often runs found what they needed in the queue's cache
+    pub cache: CacheStats,
+}
+
+impl<O, E> QueueSummary<O, E> {
+    /// The fraction of the wall time each worker spent executing runs
+    pub fn utilisation(&self) -> Vec<f64> {
+        let wall_time = self.wall_time.to_seconds();
+        self.workers
+            .iter()
+            .map(|worker| {
+                if wall_time > 0.0 {
+                    worker.busy.to_seconds() / wall_time
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+}
+
+struct Queued<O, E> {
+    priority: i32,
+    /// The position the run was pushed in
+    index: usize,
+    job: Job<O, E>,
+    preemptible: bool,
+    resumes: usize,
+}
+
+struct Running {
+    priority: i32,
+    index: usize,
+    preemptible: bool,
+    signal: Cancellation,
+}
+
+/// The runs waiting and in progress, shared by the workers and submitters
+struct Schedule<O, E> {
+    /// Highest priority first, and in the order the runs were pushed within a priority
+    waiting: VecDeque<Queued<O, E>>,
+    running: Vec<Running>,
+    results: Vec<Option<Result<O, QueueError<E>>>>,
+    /// Set once every run has returned, after which no more can be pushed
+    finished: bool,
+}
+
+impl<O, E> Schedule<O, E> {
+    fn enqueue(&mut self, queued: Queued<O, E>) {
+        let key = |queued: &Queued<O, E>| (Reverse(queued.priority), queued.index);
+        let position = self
+            .waiting
+            .partition_point(|waiting| key(waiting) < key(&queued));
+        self.waiting.insert(position, queued);
+    }
+
+    /// Signal preemptible runs to make way for waiting runs of higher priority, when there are
+    /// not enough workers free or being freed for them
+    fn preempt(&mut self, concurrency: usize) {
+        let mut freeing = concurrency.saturating_sub(self.running.len())
+            + self
+                .running
+                .iter()
+                .filter(|running| running.signal.is_cancelled())
+                .count();
+        for waiting in &self.waiting {
+            if freeing > 0 {
+                freeing -= 1;
+                continue;
+            }
+            // The lowest priority candidate, and the most recently pushed among those
+            let victim = self
+                .running
+                .iter()
+                .filter(|running| {
+                    running.preemptible
+                        && running.priority < waiting.priority
+                        && !running.signal.is_cancelled()
+                })
+                .min_by_key(|running| (running.priority, Reverse(running.index)));
+            let Some(victim) = victim else {
+                break;
+            };
+            victim.signal.cancel();
+        }
+    }
+}
+
+struct Shared<O, E> {
+    schedule: Mutex<Schedule<O, E>>,
+    /// Wakes idle workers when a run is pushed or returns
+    changed: Condvar,
+    concurrency: usize,
+    progress: Arc<Mutex<QueueProgress>>,
+}
+
+impl<O, E> Shared<O, E> {
+    /// Queue a run, returning false if the queue has already finished
+    fn push(&self, priority: i32, job: Job<O, E>, preemptible: bool) -> bool {
+        let mut schedule = lock(&self.schedule);
+        if schedule.finished {
+            return false;
+        }
+        let index = schedule.results.len();
+        schedule.results.push(None);
+        schedule.enqueue(Queued {
+            priority,
+            index,
+            job,
+            preemptible,
+            resumes: 0,
+        });
+        schedule.preempt(self.concurrency);
+        lock(&self.progress).total += 1;
+        self.changed.notify_all();
+        true
+    }
+}
+
+/// Wraps a job which runs once into one the queue can hold alongside preemptible jobs
+fn once<O, E, F>(job: F) -> Job<O, E>
+where
+    F: FnOnce(Cancellation) -> Result<O, E> + Send + 'static,
+{
+    let mut job = Some(job);
+    Box::new(move |cancellation, _| {
+        let job = job
+            .take()
+            .expect("runs which cannot be preempted are started once");
+        job(cancellation)
+    })
+}
+
+/// Pushes runs onto a queue, including while it executes.
+///
+/// Taken from [`RunQueue::submitter`], and cheap to clone, so jobs and other threads can add
+/// runs as they discover them. A run of higher priority than those in progress preempts one of
+/// them if every worker is busy, as described for the [`RunQueue`].
+pub struct Submitter<O, E>(Arc<Shared<O, E>>);
+
+impl<O, E> Clone for Submitter<O, E> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<O, E> Submitter<O, E> {
+    /// Add a run, as [`RunQueue::push_with_priority`].
+    ///
+    /// Returns false, dropping the job, if the queue has already finished executing.
+    pub fn push_with_priority<F>(&self, priority: i32, job: F) -> bool
+    where
+        F: FnOnce(Cancellation) -> Result<O, E> + Send + 'static,
+    {
+        self.0.push(priority, once(job), false)
+    }
+
+    /// Add a run which can be preempted, as [`RunQueue::push_preemptible`].
+    ///
+    /// Returns false, dropping the job, if the queue has already finished executing.
+    pub fn push_preemptible<F>(&self, priority: i32, job: F) -> bool
+    where
+        F: FnMut(Cancellation, Preemption) -> Result<O, E> + Send + 'static,
+    {
+        self.0.push(priority, Box::new(job), true)
+    }
+}
+
+/// Executes queued runs on a bounded number of threads.
+///
+/// Results are returned in the order the runs were pushed, whatever order they complete in.
+///
+/// Runs pushed with [`RunQueue::push_preemptible`] make way for runs of higher priority. When a
+/// run arrives, through a [`Submitter`] while the queue executes, and every worker is busy, the
+/// queue signals the lowest priority preemptible run below it through its [`Preemption`]. The
+/// run stops at its next iteration, checkpointing as it finalises, its worker starts the higher
+/// priority run, and the preempted run is queued again to resume from its checkpoint once a
+/// worker is free.
+pub struct RunQueue<O, E> {
+    shared: Arc<Shared<O, E>>,
+    cancellation: Cancellation,
+    cache: WarmCache,
+}
+
+impl<O, E> RunQueue<O, E>
+where
+    O: Send,
+    E: Send,
+{
+    /// A queue executing at most `concurrency` runs at once
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                schedule: Mutex::new(Schedule {
+                    waiting: VecDeque::new(),
+                    running: vec![],
+                    results: vec![],
+                    finished: false,
+                }),
+                changed: Condvar::new(),
+                concurrency: concurrency.max(1),
+                progress: Arc::new(Mutex::new(QueueProgress::default())),
+            }),
+            cancellation: Cancellation::new(),
+            cache: WarmCache::new(),
+        }
+    }
+
+    /// Add a run to the queue.
+    ///
+    /// The job should build its runner, typically passing the cancellation to
+    /// `Builder::with_controller`, and execute it.
+    pub fn push<F>(&mut self, job: F)
+    where
+        F: FnOnce(Cancellation) -> Result<O, E> + Send + 'static,
+    {
+        self.push_with_priority(0, job);
+    }
+
+    /// Add a run to the queue, to be started before any waiting run of lower priority
+    pub fn push_with_priority<F>(&mut self, priority: i32, job: F)
+    where
+        F: FnOnce(Cancellation) -> Result<O, E> + Send + 'static,
+    {
+        self.shared.push(priority, once(job), false);
+    }
+
+    /// Add a run which makes way for runs of higher priority pushed while it is in progress.
+    ///
+    /// The job is called each time the run is started or resumed, with a [`Preemption`] it should
+    /// install on its builder.
+    pub fn push_preemptible<F>(&mut self, priority: i32, job: F)
+    where
+        F: FnMut(Cancellation, Preemption) -> Result<O, E> + Send + 'static,
+    {
+        self.shared.push(priority, Box::new(job), true);
+    }
+
+    /// A handle for pushing runs onto the queue while it executes
+    pub fn submitter(&self) -> Submitter<O, E> {
+        Submitter(self.shared.clone())
+    }
+
+    /// The cancellation shared by every run in the queue.
+    ///
+    /// Once cancelled no further runs are started, and runs which attached the cancellation as
+    /// their controller terminate at their next iteration.
+    pub fn cancellation(&self) -> Cancellation {
+        self.cancellation.clone()
+    }
+
+    /// The cache shared by every run in the queue.
+    ///
+    /// Jobs capture a clone to look up setup shared with other runs, such as a factorisation,
+    /// while constructing their problem, or pass it to the calculation with
+    /// [`Builder::with_cache`](crate::Builder::with_cache). Its hits and misses are reported in
+    /// the [`QueueSummary`].
+    pub fn cache(&self) -> WarmCache {
+        self.cache.clone()
+    }
+
+    /// A handle to the aggregate progress, which can be polled while the queue runs
+    pub fn progress_handle(&self) -> Arc<Mutex<QueueProgress>> {
+        self.shared.progress.clone()
+    }
+
+    /// Execute every run, returning the results in the order the runs were pushed.
+    ///
+    /// Runs which were never started because the queue was cancelled are `None`.
+    pub fn run(self) -> Vec<Option<Result<O, QueueError<E>>>> {
+        self.run_with_summary().results
+    }
+
+    /// Take the next run to start, waiting while runs in progress may yet push or make way for
+    /// more. Returns `None` once the queue is cancelled or every run has returned.
+    fn next(&self) -> Option<(Queued<O, E>, Cancellation)> {
+        let shared = &*self.shared;
+        let mut schedule = lock(&shared.schedule);
+        loop {
+            if self.cancellation.is_cancelled() {
+                return None;
+            }
+            if let Some(queued) = schedule.waiting.pop_front() {
+                let signal = Cancellation::new();
+                schedule.running.push(Running {
+                    priority: queued.priority,
+                    index: queued.index,
+                    preemptible: queued.preemptible,
+                    signal: signal.clone(),
+                });
+                return Some((queued, signal));
+            }
+            if schedule.running.is_empty() {
+                schedule.finished = true;
+                shared.changed.notify_all();
+                return None;
+            }
+            schedule = shared
+                .changed
+                .wait_timeout(schedule, IDLE_POLL)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// Execute runs until there are none left to start
+    fn work(&self) -> WorkerUtilisation {
+        let shared = &*self.shared;
+        let mut utilisation = WorkerUtilisation::default();
+        while let Some((mut queued, signal)) = self.next() {
+            if queued.resumes == 0 {
+                lock(&shared.progress).started += 1;
+            }
+            let preemption = Preemption {
+                signal: signal.clone(),
+                resumes: queued.resumes,
+                stopped: Arc::new(AtomicBool::new(false)),
+            };
+
+            let started = Epoch::now().ok();
+            let result = execute(
+                &mut queued.job,
+                self.cancellation.clone(),
+                preemption.clone(),
+            );
+            if let (Some(started), Ok(now)) = (started, Epoch::now()) {
+                utilisation.busy += now - started;
+            }
+            utilisation.runs += 1;
+
+            // Only a run which stopped for the preemption is resumed, as one which returned anyway
+            // has its result. A run which panicked is finished whatever it was asked to do, as
+            // its job may have been left unusable
+            let preempted = preemption.is_stopped()
+                && !self.cancellation.is_cancelled()
+                && !matches!(result, Err(QueueError::Panicked(_)));
+            // Every controller thread waiting on the signal can now exit
+            signal.release();
+
+            let mut schedule = lock(&shared.schedule);
+            schedule
+                .running
+                .retain(|running| running.index != queued.index);
+            let mut progress = lock(&shared.progress);
+            if preempted {
+                progress.preempted += 1;
+                queued.resumes += 1;
+                schedule.enqueue(queued);
+            } else {
+                match result {
+                    Ok(_) => progress.succeeded += 1,
+                    Err(_) => progress.failed += 1,
+                }
+                schedule.results[queued.index] = Some(result);
+            }
+            shared.changed.notify_all();
+        }
+        utilisation
+    }
+
+    /// Execute every run, reporting the utilisation of each worker alongside the results
+    pub fn run_with_summary(self) -> QueueSummary<O, E> {
+        let start = Epoch::now().ok();
+
+        let workers = thread::scope(|scope| {
+            let workers = (0..self.shared.concurrency)
+                .map(|_| scope.spawn(|| self.work()))
+                .collect::<Vec<_>>();
+            // Panics in jobs are caught, so a worker only panics through a bug in the queue, and
+            // its utilisation is then lost
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap_or_default())
+                .collect()
+        });
+
+        // Every run has returned, so the threads waiting on the cancellation can exit
+        self.cancellation.release();
+        let mut schedule = lock(&self.shared.schedule);
+        schedule.finished = true;
+        QueueSummary {
+            results: std::mem::take(&mut schedule.results),
+            workers,
+            wall_time: match (start, Epoch::now()) {
+                (Some(start), Ok(now)) => now - start,
+                _ => Duration::ZERO,
+            },
+            cache: self.cache.stats(),
+        }
+    }
+}
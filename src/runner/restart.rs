@@ -0,0 +1,73 @@
+use crate::{Problem, Reason};
+
+type Restart<P, S> = Box<dyn FnMut(&mut Problem<P>, S, usize) -> S>;
+
+/// When and how to restart a run which terminates without success.
+///
+/// A run terminating for one of the policy's reasons is restarted from its final state, prepared
+/// with [`State::for_warm_start`](crate::State::for_warm_start) and then passed to the restart
+/// function along with the problem and the number of the attempt, starting from one. The
+/// function might shrink a step size, perturb the parameter or raise the iteration limit, which
+/// the state usually needs if the attempt is to run at all. The calculation initialises the
+/// returned state and the run carries on from there, counting iterations from where the failed
+/// attempt stopped.
+///
+/// By default a run is restarted when it exceeds its maximum iterations or is stopped by a
+/// predicate registered with [`Builder::terminate_if`](crate::Builder::terminate_if), such as a
+/// stall check. Runs stopped by a kill signal are never restarted.
+pub struct RestartPolicy<P, S> {
+    max_restarts: usize,
+    reasons: Vec<Reason>,
+    restart: Restart<P, S>,
+}
+
+impl<P, S> RestartPolicy<P, S> {
+    /// Restart up to `max_restarts` times, preparing each attempt with `restart`
+    pub fn new(
+        max_restarts: usize,
+        restart: impl FnMut(&mut Problem<P>, S, usize) -> S + 'static,
+    ) -> Self {
+        Self {
+            max_restarts,
+            reasons: vec![Reason::ExceededMaxIterations, Reason::UserPredicate],
+            restart: Box::new(restart),
+        }
+    }
+
+    /// Restart on termination for any of `reasons`, in place of the default reasons
+    #[must_use]
+    pub fn on(mut self, reasons: impl IntoIterator<Item = Reason>) -> Self {
+        self.reasons = reasons.into_iter().collect();
+        self
+    }
+
+    pub fn max_restarts(&self) -> usize {
+        self.max_restarts
+    }
+
+    pub(super) fn restarts_on(&self, reason: Option<&Reason>) -> bool {
+        match reason {
+            Some(Reason::ControlC | Reason::Controller | Reason::IterationTimedOut) | None => false,
+            Some(reason) => self.reasons.contains(reason),
+        }
+    }
+
+    pub(super) fn restart(&mut self, problem: &mut Problem<P>, state: S, attempt: usize) -> S {
+        (self.restart)(problem, state, attempt)
+    }
+}
+
+/// The attempt a restarted run is on.
+///
+/// Inserted into the [`Extensions`](crate::Extensions) of states which hold them when a runner
+/// built with [`Builder::restart_policy`](crate::Builder::restart_policy) restarts, for observers
+/// to read.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RestartAttempt {
+    /// The number of the attempt, the first restart being attempt one
+    pub attempt: usize,
+    /// The iteration at which the previous attempt terminated
+    pub iteration: usize,
+    /// Why the previous attempt terminated
+    pub reason: Reason,
+}
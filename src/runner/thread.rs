@@ -0,0 +1,76 @@
+use tracing::{info, warn};
+
+/// How the thread executing a run is configured, set with
+/// [`Builder::thread_niceness`](crate::Builder::thread_niceness) and
+/// [`Builder::pin_to_core`](crate::Builder::pin_to_core).
+///
+/// The settings are applied to whichever thread calls [`Runner::run`](crate::Runner::run), when
+/// the run starts, and stay in place once it returns. Runs executed on a shared pool, for
+/// example through `tokio::task::spawn_blocking`, leave the pool thread configured, so a
+/// dedicated thread is the better home for a configured run. Settings which cannot be applied
+/// are logged and otherwise ignored.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(super) struct ThreadOptions {
+    /// The niceness of the thread, from -20 for the highest priority to 19 for the lowest
+    pub(super) niceness: Option<i32>,
+    /// The core the thread is pinned to
+    pub(super) core: Option<usize>,
+}
+
+impl ThreadOptions {
+    pub(super) fn apply(&self) {
+        if let Some(niceness) = self.niceness {
+            set_niceness(niceness);
+        }
+        if let Some(core) = self.core {
+            pin_to_core(core);
+        }
+    }
+}
+
+/// Set the niceness of the calling thread.
+///
+/// Linux schedules threads individually, so only the calling thread is affected there. Other
+/// unix systems apply the niceness to the whole process.
+#[cfg(unix)]
+fn set_niceness(niceness: i32) {
+    // Safety: `setpriority` reads no memory, and `who` of zero refers to the caller
+    let result = unsafe { libc::setpriority(libc::PRIO_PROCESS as _, 0, niceness) };
+    if result == 0 {
+        info!(niceness, "set the niceness of the run thread");
+    } else {
+        let error = std::io::Error::last_os_error();
+        warn!(niceness, %error, "failed to set the niceness of the run thread");
+    }
+}
+
+#[cfg(not(unix))]
+fn set_niceness(niceness: i32) {
+    warn!(
+        niceness,
+        "thread niceness is not supported on this platform, ignoring it"
+    );
+}
+
+#[cfg(feature = "core_affinity")]
+fn pin_to_core(core: usize) {
+    let available = core_affinity::get_core_ids().unwrap_or_default();
+    match available.into_iter().find(|id| id.id == core) {
+        Some(id) if core_affinity::set_for_current(id) => {
+            info!(core, "pinned the run thread");
+        }
+        Some(_) => warn!(core, "failed to pin the run thread"),
+        None => warn!(
+            core,
+            "the core is not available to the run thread, leaving it unpinned"
+        ),
+    }
+}
+
+#[cfg(not(feature = "core_affinity"))]
+fn pin_to_core(core: usize) {
+    warn!(
+        core,
+        "pinning requires the `core_affinity` feature, leaving the run thread unpinned"
+    );
+}
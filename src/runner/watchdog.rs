@@ -0,0 +1,81 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+#[derive(Default)]
+struct Deadline {
+    /// When the iteration in progress times out, if one is in progress
+    at: Option<Instant>,
+    /// Set when the runner is dropped, so the thread can exit
+    stopped: bool,
+}
+
+/// Times iterations on a thread of its own, flipping a killswitch when one overruns.
+///
+/// The thread cannot interrupt the calculation. The run stops at the end of the overrunning
+/// iteration, or sooner if the calculation polls its [`CancelChecker`](crate::CancelChecker).
+pub(super) struct Watchdog {
+    timeout: Duration,
+    deadline: Arc<(Mutex<Deadline>, Condvar)>,
+}
+
+impl Watchdog {
+    /// Start the watchdog thread, returning it with the flag it sets on a timeout
+    pub(super) fn spawn(timeout: Duration) -> Result<(Self, Arc<AtomicBool>), std::io::Error> {
+        let deadline = Arc::new((Mutex::new(Deadline::default()), Condvar::new()));
+        let timed_out = Arc::new(AtomicBool::new(false));
+
+        let (watched, flag) = (deadline.clone(), timed_out.clone());
+        thread::Builder::new()
+            .name("iteration_watchdog".into())
+            .spawn(move || {
+                let (deadline, condvar) = &*watched;
+                let mut guard = deadline.lock().unwrap();
+                while !guard.stopped {
+                    guard = match guard.at {
+                        None => condvar.wait(guard).unwrap(),
+                        Some(at) => match at.checked_duration_since(Instant::now()) {
+                            Some(remaining) if !remaining.is_zero() => {
+                                condvar.wait_timeout(guard, remaining).unwrap().0
+                            }
+                            _ => {
+                                warn!(?timeout, "iteration timed out, stopping the run");
+                                flag.store(true, Ordering::SeqCst);
+                                guard.at = None;
+                                guard
+                            }
+                        },
+                    };
+                }
+            })?;
+
+        Ok((Self { timeout, deadline }, timed_out))
+    }
+
+    /// Start timing an iteration
+    pub(super) fn arm(&self) {
+        self.set(Some(Instant::now() + self.timeout));
+    }
+
+    /// Stop timing the iteration, which finished in time
+    pub(super) fn disarm(&self) {
+        self.set(None);
+    }
+
+    fn set(&self, at: Option<Instant>) {
+        let (deadline, condvar) = &*self.deadline;
+        deadline.lock().unwrap().at = at;
+        condvar.notify_one();
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        let (deadline, condvar) = &*self.deadline;
+        deadline.lock().unwrap().stopped = true;
+        condvar.notify_one();
+    }
+}
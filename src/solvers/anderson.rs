@@ -0,0 +1,222 @@
+use std::collections::VecDeque;
+
+use ndarray::{Array1, Array2};
+
+use crate::{Calculation, Grade, Problem, State, KV};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AndersonError<E: std::error::Error + 'static> {
+    #[error(transparent)]
+    Calculation(E),
+    #[error("the state gives no mutable access to its parameter")]
+    ParamUnavailable,
+}
+
+/// Anderson acceleration of a fixed-point style calculation.
+///
+/// Each iteration of the wrapped calculation is treated as one application of a map `G`, taking
+/// the parameter from `x` to `G(x)`. The adaptor remembers the last `window` iterations and
+/// replaces the new parameter with the combination of the remembered images whose residuals
+/// `G(x) - x` best cancel, which often converges far faster than the plain iteration. A window of
+/// zero leaves the calculation unchanged.
+///
+/// The parameter is read and written through [`State::get_param`] and
+/// [`State::get_param_mut`], converting to and from an `Array1<f64>`. The measure and termination
+/// are left to the wrapped calculation, which must not cache anything derived from the
+/// parameter between iterations.
+///
+/// ```ignore
+/// let calculation = AndersonAccelerated::new(FixedPoint::new(map)).window(3);
+/// ```
+pub struct AndersonAccelerated<C> {
+    calculation: C,
+    window: usize,
+    regularisation: f64,
+    /// The last images under the map, oldest first
+    images: VecDeque<Array1<f64>>,
+    /// The residuals of the remembered images
+    residuals: VecDeque<Array1<f64>>,
+}
+
+impl<C> AndersonAccelerated<C> {
+    /// Accelerate `calculation` with a window of five iterations
+    pub fn new(calculation: C) -> Self {
+        Self {
+            calculation,
+            window: 5,
+            regularisation: 1e-10,
+            images: VecDeque::new(),
+            residuals: VecDeque::new(),
+        }
+    }
+
+    /// Mix over the last `window` iterations
+    #[must_use]
+    pub fn window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Tikhonov regularisation of the mixing problem, relative to its scale.
+    ///
+    /// Larger values keep the mixing stable when the remembered residuals are nearly linearly
+    /// dependent, at the cost of acceleration.
+    #[must_use]
+    pub fn regularisation(mut self, regularisation: f64) -> Self {
+        self.regularisation = regularisation;
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.calculation
+    }
+
+    pub fn into_inner(self) -> C {
+        self.calculation
+    }
+
+    /// Remember the latest image and its residual, returning the mixed parameter
+    fn accelerate(&mut self, image: Array1<f64>, residual: Array1<f64>) -> Option<Array1<f64>> {
+        self.images.push_back(image);
+        self.residuals.push_back(residual);
+        if self.images.len() > self.window + 1 {
+            self.images.pop_front();
+            self.residuals.pop_front();
+        }
+        let differences = self.images.len() - 1;
+        if differences == 0 {
+            return None;
+        }
+
+        // Differences between successive residuals, as the columns of the least squares problem
+        let residual = self.residuals.back().unwrap();
+        let residual_deltas = (0..differences)
+            .map(|column| &self.residuals[column + 1] - &self.residuals[column])
+            .collect::<Vec<_>>();
+
+        // Least squares for the weights minimising the mixed residual, by the normal equations
+        let mut gram = Array2::zeros((differences, differences));
+        let mut rhs = Array1::zeros(differences);
+        for i in 0..differences {
+            for j in 0..differences {
+                gram[[i, j]] = residual_deltas[i].dot(&residual_deltas[j]);
+            }
+            rhs[i] = residual_deltas[i].dot(residual);
+        }
+        let scale = gram.diag().iter().copied().fold(0.0, f64::max);
+        for i in 0..differences {
+            gram[[i, i]] += self.regularisation * scale.max(f64::MIN_POSITIVE);
+        }
+
+        let Some(weights) = solve(gram, rhs) else {
+            // The residuals have become degenerate, so start the window again from here
+            self.images.drain(..differences);
+            self.residuals.drain(..differences);
+            return None;
+        };
+        let mut mixed = self.images[differences].clone();
+        for (column, weight) in weights.iter().enumerate() {
+            let image_delta = &self.images[column + 1] - &self.images[column];
+            mixed.scaled_add(-weight, &image_delta);
+        }
+        mixed.iter().all(|x| x.is_finite()).then_some(mixed)
+    }
+}
+
+/// Solve the square system `a x = b` by Gaussian elimination with partial pivoting
+fn solve(mut a: Array2<f64>, mut b: Array1<f64>) -> Option<Array1<f64>> {
+    let n = b.len();
+    for column in 0..n {
+        let pivot =
+            (column..n).max_by(|&i, &j| a[[i, column]].abs().total_cmp(&a[[j, column]].abs()))?;
+        if a[[pivot, column]] == 0.0 || !a[[pivot, column]].is_finite() {
+            return None;
+        }
+        if pivot != column {
+            for k in 0..n {
+                a.swap([pivot, k], [column, k]);
+            }
+            b.swap(pivot, column);
+        }
+        for row in column + 1..n {
+            let factor = a[[row, column]] / a[[column, column]];
+            for k in column..n {
+                a[[row, k]] -= factor * a[[column, k]];
+            }
+            b[row] -= factor * b[column];
+        }
+    }
+    let mut x = Array1::zeros(n);
+    for row in (0..n).rev() {
+        let sum = (row + 1..n).map(|k| a[[row, k]] * x[k]).sum::<f64>();
+        x[row] = (b[row] - sum) / a[[row, row]];
+    }
+    Some(x)
+}
+
+impl<C, P, S> Calculation<P, S> for AndersonAccelerated<C>
+where
+    C: Calculation<P, S>,
+    S: State,
+    S::Param: Clone + Into<Array1<f64>> + From<Array1<f64>>,
+{
+    type Error = AndersonError<C::Error>;
+    type Output = C::Output;
+    const NAME: &'static str = C::NAME;
+    const VERSION: &'static str = C::VERSION;
+
+    fn ident(&self) -> &'static str {
+        self.calculation.ident()
+    }
+
+    fn initialise(&mut self, problem: &mut Problem<P>, state: S) -> Result<S, Self::Error> {
+        self.images.clear();
+        self.residuals.clear();
+        self.calculation
+            .initialise(problem, state)
+            .map_err(AndersonError::Calculation)
+    }
+
+    fn next(&mut self, problem: &mut Problem<P>, state: S) -> Result<S, Self::Error> {
+        let value: Array1<f64> = state
+            .get_param()
+            .ok_or(AndersonError::ParamUnavailable)?
+            .clone()
+            .into();
+        let mut state = self
+            .calculation
+            .next(problem, state)
+            .map_err(AndersonError::Calculation)?;
+        if state.is_terminated() {
+            return Ok(state);
+        }
+
+        let param = state
+            .get_param_mut()
+            .ok_or(AndersonError::ParamUnavailable)?;
+        let image: Array1<f64> = param.clone().into();
+        let residual = &image - &value;
+        if let Some(mixed) = self.accelerate(image, residual) {
+            *param = mixed.into();
+        }
+        Ok(state)
+    }
+
+    fn finalise(&mut self, problem: &mut Problem<P>, state: S) -> Result<C::Output, Self::Error> {
+        self.calculation
+            .finalise(problem, state)
+            .map_err(AndersonError::Calculation)
+    }
+
+    fn grade(&self, state: &S) -> Grade {
+        self.calculation.grade(state)
+    }
+
+    /// Reports the number of past iterations mixed, as `anderson_depth`, after the values of the
+    /// wrapped calculation
+    fn report(&self, state: &S) -> KV {
+        self.calculation
+            .report(state)
+            .with("anderson_depth", self.images.len().saturating_sub(1))
+    }
+}
@@ -0,0 +1,288 @@
+use std::marker::PhantomData;
+
+use hifitime::Duration;
+
+use super::Vector;
+use crate::{Calculation, Extensions, Grade, Problem, Reason, RunId, State, KV};
+
+/// Results within this factor of the tolerance are graded as loosely converged
+pub(super) const LOOSE_FACTOR: f64 = 10.0;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FixedPointError {
+    #[error("no initial value was given, set one with `FixedPointState::with_param`")]
+    MissingParam,
+    #[error("damping must be in (0, 1], got {0}")]
+    InvalidDamping(f64),
+}
+
+/// Damped fixed-point, or Picard, iteration.
+///
+/// Finds `x` with `F(x) = x` by repeatedly applying the map, moving each iteration from `x` to
+/// `x + damping * (F(x) - x)`. Without damping this is plain iteration of the map, which
+/// converges when the map is a contraction. Damping can restore convergence when the plain
+/// iteration oscillates, at the cost of slower progress.
+///
+/// The measure is the residual `|F(x) - x|` of the value an iteration started from, in the
+/// Euclidean norm unless another is set with [`FixedPoint::norm`]. The run converges once it
+/// falls to the tolerance of the [`FixedPointState`]. Every application of the map is counted as
+/// an evaluation of the problem.
+///
+/// ```ignore
+/// let runner = FixedPoint::new(|x: &f64| x.cos())
+///     .build_for(())
+///     .configure(|state| state.with_param(1.0).tolerance(1e-12))
+///     .finalise()?;
+/// let root = runner.run()?.into_param();
+/// ```
+pub struct FixedPoint<X, F> {
+    map: F,
+    damping: f64,
+    norm: fn(&X, &X) -> f64,
+    _value: PhantomData<fn(&X) -> X>,
+}
+
+impl<X, F> FixedPoint<X, F>
+where
+    X: Vector,
+    F: Fn(&X) -> X,
+{
+    pub fn new(map: F) -> Self {
+        Self {
+            map,
+            damping: 1.0,
+            norm: X::distance,
+            _value: PhantomData,
+        }
+    }
+
+    /// Move only `damping` of the way to the image of the map at each iteration
+    #[must_use]
+    pub fn damping(mut self, damping: f64) -> Self {
+        self.damping = damping;
+        self
+    }
+
+    /// Measure residuals with `norm`, given the value and its image under the map
+    #[must_use]
+    pub fn norm(mut self, norm: fn(&X, &X) -> f64) -> Self {
+        self.norm = norm;
+        self
+    }
+
+    fn apply<P>(&self, problem: &mut Problem<P>, value: &X) -> X {
+        problem.record_evaluations(1);
+        (self.map)(value)
+    }
+}
+
+impl<P, X, F> Calculation<P, FixedPointState<X>> for FixedPoint<X, F>
+where
+    X: Vector,
+    F: Fn(&X) -> X,
+{
+    type Error = FixedPointError;
+    type Output = FixedPointState<X>;
+    const NAME: &'static str = "fixed point";
+
+    fn initialise(
+        &mut self,
+        problem: &mut Problem<P>,
+        mut state: FixedPointState<X>,
+    ) -> Result<FixedPointState<X>, Self::Error> {
+        if !(self.damping > 0.0 && self.damping <= 1.0) {
+            return Err(FixedPointError::InvalidDamping(self.damping));
+        }
+        let param = state.param.as_ref().ok_or(FixedPointError::MissingParam)?;
+        let image = self.apply(problem, param);
+        state.residual = (self.norm)(param, &image);
+        state.initialised = true;
+        Ok(state)
+    }
+
+    fn next(
+        &mut self,
+        problem: &mut Problem<P>,
+        mut state: FixedPointState<X>,
+    ) -> Result<FixedPointState<X>, Self::Error> {
+        // The value may have been changed since the last iteration, by an adaptor such as
+        // `AndersonAccelerated`, so its image is not carried over
+        let param = state.param.as_mut().ok_or(FixedPointError::MissingParam)?;
+        let image = self.apply(problem, param);
+        state.residual = (self.norm)(param, &image);
+        *param = param.lerp(&image, self.damping);
+        Ok(state.check_convergence())
+    }
+
+    fn finalise(
+        &mut self,
+        _problem: &mut Problem<P>,
+        state: FixedPointState<X>,
+    ) -> Result<Self::Output, Self::Error> {
+        Ok(state)
+    }
+
+    fn grade(&self, state: &FixedPointState<X>) -> Grade {
+        Grade::from_tolerance(state.residual, state.tolerance, LOOSE_FACTOR)
+    }
+}
+
+/// The state of an iteration towards a fixed point.
+///
+/// Used by [`FixedPoint`] and by the stationary linear solvers. The initial value is given with
+/// [`FixedPointState::with_param`], usually through
+/// [`Builder::configure`](crate::Builder::configure).
+#[derive(Clone, Debug)]
+pub struct FixedPointState<X> {
+    pub(super) param: Option<X>,
+    iteration: usize,
+    pub(super) residual: f64,
+    best_residual: f64,
+    best_iteration: usize,
+    pub(super) tolerance: f64,
+    max_iterations: usize,
+    time: Option<Duration>,
+    termination_reason: Option<Reason>,
+    run_id: Option<RunId>,
+    extensions: Extensions,
+    pub(super) initialised: bool,
+}
+
+impl<X> FixedPointState<X> {
+    #[must_use]
+    pub fn with_param(mut self, param: X) -> Self {
+        self.param = Some(param);
+        self
+    }
+
+    /// Converge once the residual falls to `tolerance`
+    #[must_use]
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    #[must_use]
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// The current value
+    pub fn param(&self) -> Option<&X> {
+        self.param.as_ref()
+    }
+
+    pub fn into_param(self) -> Option<X> {
+        self.param
+    }
+
+    /// The residual of the current value
+    pub fn residual(&self) -> f64 {
+        self.residual
+    }
+
+    /// Terminate if the residual is within the tolerance or this is the last iteration allowed
+    pub(super) fn check_convergence(self) -> Self {
+        if self.residual <= self.tolerance {
+            self.terminate_due_to(Reason::Converged)
+        } else if self.iteration + 1 >= self.max_iterations {
+            self.terminate_due_to(Reason::ExceededMaxIterations)
+        } else {
+            self
+        }
+    }
+}
+
+impl<X> Default for FixedPointState<X> {
+    fn default() -> Self {
+        Self {
+            param: None,
+            iteration: 0,
+            residual: f64::INFINITY,
+            best_residual: f64::INFINITY,
+            best_iteration: 0,
+            tolerance: f64::EPSILON.sqrt(),
+            max_iterations: 1000,
+            time: None,
+            termination_reason: None,
+            run_id: None,
+            extensions: Extensions::default(),
+            initialised: false,
+        }
+    }
+}
+
+impl<X> State for FixedPointState<X> {
+    type Float = f64;
+    type Param = X;
+
+    fn record_time(&mut self, duration: Duration) {
+        self.time = Some(duration);
+    }
+    fn duration(&self) -> Option<Duration> {
+        self.time
+    }
+    fn increment_iteration(&mut self) {
+        self.iteration += 1;
+    }
+    fn current_iteration(&self) -> usize {
+        self.iteration
+    }
+    fn update(mut self) -> Self {
+        if self.residual < self.best_residual {
+            self.best_residual = self.residual;
+            self.best_iteration = self.iteration;
+        }
+        self
+    }
+    fn is_initialised(&self) -> bool {
+        self.initialised
+    }
+    fn is_terminated(&self) -> bool {
+        self.termination_reason.is_some()
+    }
+    fn terminate_due_to(mut self, reason: Reason) -> Self {
+        self.termination_reason = Some(reason);
+        self
+    }
+    fn termination_reason(&self) -> Option<Reason> {
+        self.termination_reason.clone()
+    }
+    fn get_param(&self) -> Option<&X> {
+        self.param.as_ref()
+    }
+    fn get_param_mut(&mut self) -> Option<&mut X> {
+        self.param.as_mut()
+    }
+    fn measure(&self) -> f64 {
+        self.residual
+    }
+    fn best_measure(&self) -> f64 {
+        self.best_residual
+    }
+    fn iterations_since_best(&self) -> usize {
+        self.iteration - self.best_iteration
+    }
+    fn kv(&self) -> KV {
+        KV::new()
+            .with("tolerance", self.tolerance)
+            .with("max_iterations", self.max_iterations)
+    }
+    fn for_warm_start(mut self) -> Self {
+        self.termination_reason = None;
+        self
+    }
+    fn set_run_id(&mut self, run_id: RunId) {
+        self.run_id = Some(run_id);
+    }
+    fn run_id(&self) -> Option<&RunId> {
+        self.run_id.as_ref()
+    }
+    fn extensions(&self) -> Option<&Extensions> {
+        Some(&self.extensions)
+    }
+    fn extensions_mut(&mut self) -> Option<&mut Extensions> {
+        Some(&mut self.extensions)
+    }
+}
@@ -0,0 +1,221 @@
+use ndarray::{Array1, Array2};
+
+use super::{fixed_point::LOOSE_FACTOR, FixedPointState};
+use crate::{Calculation, Grade, Problem, State};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LinearError {
+    #[error("the matrix is {rows} by {columns}, but must be square")]
+    NotSquare { rows: usize, columns: usize },
+    #[error("the system has {rows} rows, but the {name} has length {len}")]
+    DimensionMismatch {
+        rows: usize,
+        name: &'static str,
+        len: usize,
+    },
+    #[error("the diagonal of the matrix is zero in row {0}")]
+    ZeroDiagonal(usize),
+    #[error("the relaxation factor must be in (0, 2), got {0}")]
+    InvalidRelaxation(f64),
+}
+
+/// The linear system `A x = b`, as the problem solved by [`Jacobi`] and [`Sor`]
+#[derive(Clone, Debug)]
+pub struct LinearSystem {
+    pub matrix: Array2<f64>,
+    pub rhs: Array1<f64>,
+}
+
+impl LinearSystem {
+    pub fn new(matrix: Array2<f64>, rhs: Array1<f64>) -> Self {
+        Self { matrix, rhs }
+    }
+
+    /// The residual `|b - A x|`, in the Euclidean norm
+    pub fn residual(&self, x: &Array1<f64>) -> f64 {
+        (&self.rhs - &self.matrix.dot(x))
+            .mapv(|r| r * r)
+            .sum()
+            .sqrt()
+    }
+
+    fn validate(&self) -> Result<(), LinearError> {
+        let (rows, columns) = self.matrix.dim();
+        if rows != columns {
+            return Err(LinearError::NotSquare { rows, columns });
+        }
+        if self.rhs.len() != rows {
+            return Err(LinearError::DimensionMismatch {
+                rows,
+                name: "right hand side",
+                len: self.rhs.len(),
+            });
+        }
+        match self
+            .matrix
+            .diag()
+            .iter()
+            .position(|&diagonal| diagonal == 0.0)
+        {
+            Some(row) => Err(LinearError::ZeroDiagonal(row)),
+            None => Ok(()),
+        }
+    }
+
+    /// `b_i - sum_{j != i} a_ij x_j`, the part of row `i` not involving `x_i`
+    fn off_diagonal(&self, row: usize, x: &Array1<f64>) -> f64 {
+        let coefficients = self.matrix.row(row);
+        self.rhs[row] - coefficients.dot(x) + coefficients[row] * x[row]
+    }
+}
+
+/// Validate the system and starting value, defaulting the value to zero
+fn initialise_linear(
+    system: &LinearSystem,
+    mut state: FixedPointState<Array1<f64>>,
+) -> Result<FixedPointState<Array1<f64>>, LinearError> {
+    system.validate()?;
+    let x = state
+        .param
+        .take()
+        .unwrap_or_else(|| Array1::zeros(system.rhs.len()));
+    if x.len() != system.rhs.len() {
+        return Err(LinearError::DimensionMismatch {
+            rows: system.rhs.len(),
+            name: "initial value",
+            len: x.len(),
+        });
+    }
+    state.residual = system.residual(&x);
+    state.param = Some(x);
+    state.initialised = true;
+    Ok(state)
+}
+
+/// The Jacobi method for linear systems.
+///
+/// Updates every component of `x` from the values of the others at the last iteration, so
+/// components can be computed independently. Converges for strictly diagonally dominant
+/// matrices, among others. The measure is the residual `|b - A x|`, and the run converges once it
+/// falls to the tolerance of the [`FixedPointState`]. The initial value defaults to zero.
+///
+/// ```ignore
+/// let runner = Jacobi
+///     .build_for(LinearSystem::new(matrix, rhs))
+///     .configure(|state| state.tolerance(1e-10))
+///     .finalise()?;
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Jacobi;
+
+impl Calculation<LinearSystem, FixedPointState<Array1<f64>>> for Jacobi {
+    type Error = LinearError;
+    type Output = FixedPointState<Array1<f64>>;
+    const NAME: &'static str = "jacobi";
+
+    fn initialise(
+        &mut self,
+        problem: &mut Problem<LinearSystem>,
+        state: FixedPointState<Array1<f64>>,
+    ) -> Result<FixedPointState<Array1<f64>>, Self::Error> {
+        initialise_linear(problem.as_ref(), state)
+    }
+
+    fn next(
+        &mut self,
+        problem: &mut Problem<LinearSystem>,
+        mut state: FixedPointState<Array1<f64>>,
+    ) -> Result<FixedPointState<Array1<f64>>, Self::Error> {
+        problem.record_evaluations(1);
+        let system = problem.as_ref();
+        let x = state
+            .param
+            .get_or_insert_with(|| Array1::zeros(system.rhs.len()));
+        *x = Array1::from_shape_fn(x.len(), |row| {
+            system.off_diagonal(row, x) / system.matrix[[row, row]]
+        });
+        state.residual = system.residual(x);
+        Ok(state.check_convergence())
+    }
+
+    fn finalise(
+        &mut self,
+        _problem: &mut Problem<LinearSystem>,
+        state: FixedPointState<Array1<f64>>,
+    ) -> Result<Self::Output, Self::Error> {
+        Ok(state)
+    }
+
+    fn grade(&self, state: &FixedPointState<Array1<f64>>) -> Grade {
+        Grade::from_tolerance(state.measure(), state.tolerance, LOOSE_FACTOR)
+    }
+}
+
+/// Successive over-relaxation for linear systems.
+///
+/// Updates the components of `x` in turn, each from the latest values of the others, and moves
+/// `relaxation` of the way to the updated value. A relaxation of one is the Gauss-Seidel method;
+/// values between one and two often converge considerably faster on the systems arising from
+/// discretised elliptic PDEs. Converges for symmetric positive definite matrices with any
+/// relaxation in `(0, 2)`. Measured as [`Jacobi`] is.
+#[derive(Copy, Clone, Debug)]
+pub struct Sor {
+    relaxation: f64,
+}
+
+impl Sor {
+    pub fn new(relaxation: f64) -> Self {
+        Self { relaxation }
+    }
+
+    pub fn gauss_seidel() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl Calculation<LinearSystem, FixedPointState<Array1<f64>>> for Sor {
+    type Error = LinearError;
+    type Output = FixedPointState<Array1<f64>>;
+    const NAME: &'static str = "sor";
+
+    fn initialise(
+        &mut self,
+        problem: &mut Problem<LinearSystem>,
+        state: FixedPointState<Array1<f64>>,
+    ) -> Result<FixedPointState<Array1<f64>>, Self::Error> {
+        if !(self.relaxation > 0.0 && self.relaxation < 2.0) {
+            return Err(LinearError::InvalidRelaxation(self.relaxation));
+        }
+        initialise_linear(problem.as_ref(), state)
+    }
+
+    fn next(
+        &mut self,
+        problem: &mut Problem<LinearSystem>,
+        mut state: FixedPointState<Array1<f64>>,
+    ) -> Result<FixedPointState<Array1<f64>>, Self::Error> {
+        problem.record_evaluations(1);
+        let system = problem.as_ref();
+        let x = state
+            .param
+            .get_or_insert_with(|| Array1::zeros(system.rhs.len()));
+        for row in 0..x.len() {
+            let updated = system.off_diagonal(row, x) / system.matrix[[row, row]];
+            x[row] += self.relaxation * (updated - x[row]);
+        }
+        state.residual = system.residual(x);
+        Ok(state.check_convergence())
+    }
+
+    fn finalise(
+        &mut self,
+        _problem: &mut Problem<LinearSystem>,
+        state: FixedPointState<Array1<f64>>,
+    ) -> Result<Self::Output, Self::Error> {
+        Ok(state)
+    }
+
+    fn grade(&self, state: &FixedPointState<Array1<f64>>) -> Grade {
+        Grade::from_tolerance(state.measure(), state.tolerance, LOOSE_FACTOR)
+    }
+}
@@ -0,0 +1,80 @@
+//! Ready-made calculations.
+//!
+//! These solve common problems directly, and double as worked examples of implementing
+//! [`Calculation`](crate::Calculation) and [`State`](crate::State).
+//!
+//! - [`FixedPoint`] iterates a map to its fixed point, with optional damping.
+//! - [`AndersonAccelerated`] accelerates fixed-point style calculations, with the `ndarray`
+//!   feature.
+//! - [`Bisection`] and [`Brent`] find a root of a scalar function within a bracket.
+//! - [`Jacobi`] and [`Sor`] solve linear systems, with the `ndarray` feature. Their parameter is
+//!   an `Array1<f64>`, so the solution can be watched converging with a `PlotGenerator`.
+#[cfg(feature = "ndarray")]
+mod anderson;
+mod fixed_point;
+#[cfg(feature = "ndarray")]
+mod linear;
+mod root;
+
+#[cfg(feature = "ndarray")]
+pub use anderson::{AndersonAccelerated, AndersonError};
+pub use fixed_point::{FixedPoint, FixedPointError, FixedPointState};
+#[cfg(feature = "ndarray")]
+pub use linear::{Jacobi, LinearError, LinearSystem, Sor};
+pub use root::{Bisection, BracketState, Brent, RootError};
+
+/// A value the solvers can iterate over.
+///
+/// Implemented for `f64` and `Vec<f64>`, and for the vector types of `ndarray` and `nalgebra`
+/// when their features are enabled.
+pub trait Vector: Clone {
+    /// The point `factor` of the way from `self` to `other`
+    fn lerp(&self, other: &Self, factor: f64) -> Self;
+    /// The Euclidean distance between two values
+    fn distance(&self, other: &Self) -> f64;
+}
+
+impl Vector for f64 {
+    fn lerp(&self, other: &Self, factor: f64) -> Self {
+        self + factor * (other - self)
+    }
+    fn distance(&self, other: &Self) -> f64 {
+        (self - other).abs()
+    }
+}
+
+impl Vector for Vec<f64> {
+    fn lerp(&self, other: &Self, factor: f64) -> Self {
+        self.iter()
+            .zip(other)
+            .map(|(a, b)| a.lerp(b, factor))
+            .collect()
+    }
+    fn distance(&self, other: &Self) -> f64 {
+        self.iter()
+            .zip(other)
+            .map(|(a, b)| (a - b).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl Vector for ndarray::Array1<f64> {
+    fn lerp(&self, other: &Self, factor: f64) -> Self {
+        self + &((other - self) * factor)
+    }
+    fn distance(&self, other: &Self) -> f64 {
+        (self - other).mapv(|x| x * x).sum().sqrt()
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl Vector for nalgebra::DVector<f64> {
+    fn lerp(&self, other: &Self, factor: f64) -> Self {
+        self + (other - self) * factor
+    }
+    fn distance(&self, other: &Self) -> f64 {
+        (self - other).norm()
+    }
+}
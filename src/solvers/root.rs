@@ -0,0 +1,430 @@
+use hifitime::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::{Calculation, Extensions, Problem, Reason, RunId, State, KV};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RootError {
+    #[error("no bracket was given, set one with `BracketState::bracket`")]
+    MissingBracket,
+    #[error(
+        "the bracket does not contain a sign change, f(lower) = {f_lower}, f(upper) = {f_upper}"
+    )]
+    NotBracketed { f_lower: f64, f_upper: f64 },
+}
+
+/// Evaluate the function at both ends of the bracket, checking it changes sign between them
+fn evaluate_bracket<P>(
+    function: impl Fn(f64) -> f64,
+    problem: &mut Problem<P>,
+    state: &mut BracketState,
+) -> Result<(), RootError> {
+    let (lower, upper) = state.bracket.ok_or(RootError::MissingBracket)?;
+    problem.record_evaluations(2);
+    let (f_lower, f_upper) = (function(lower), function(upper));
+    if f_lower.signum() == f_upper.signum() && f_lower != 0.0 && f_upper != 0.0 {
+        return Err(RootError::NotBracketed { f_lower, f_upper });
+    }
+    let (root, residual) = if f_lower.abs() < f_upper.abs() {
+        (lower, f_lower)
+    } else {
+        (upper, f_upper)
+    };
+    state.root = Some(root);
+    state.residual = residual.abs();
+    state.f_bracket = (f_lower, f_upper);
+    state.initialised = true;
+    Ok(())
+}
+
+/// Root finding by bisection.
+///
+/// Halves the bracket at every iteration, keeping the half over which the function changes sign.
+/// Slow but certain, converging for any continuous function. The measure is `|f(x)|` at the
+/// midpoint of the last bracket.
+///
+/// ```ignore
+/// let runner = Bisection::new(|x| x * x - 2.0)
+///     .build_for(())
+///     .configure(|state| state.bracket(0.0, 2.0))
+///     .finalise()?;
+/// ```
+pub struct Bisection<F> {
+    function: F,
+}
+
+impl<F: Fn(f64) -> f64> Bisection<F> {
+    pub fn new(function: F) -> Self {
+        Self { function }
+    }
+}
+
+impl<P, F: Fn(f64) -> f64> Calculation<P, BracketState> for Bisection<F> {
+    type Error = RootError;
+    type Output = BracketState;
+    const NAME: &'static str = "bisection";
+
+    fn initialise(
+        &mut self,
+        problem: &mut Problem<P>,
+        mut state: BracketState,
+    ) -> Result<BracketState, Self::Error> {
+        evaluate_bracket(&self.function, problem, &mut state)?;
+        Ok(state)
+    }
+
+    fn next(
+        &mut self,
+        problem: &mut Problem<P>,
+        mut state: BracketState,
+    ) -> Result<BracketState, Self::Error> {
+        let (lower, upper) = state.bracket.ok_or(RootError::MissingBracket)?;
+        let (f_lower, f_upper) = state.f_bracket;
+        let midpoint = 0.5 * (lower + upper);
+        problem.record_evaluations(1);
+        let f_midpoint = (self.function)(midpoint);
+
+        if f_midpoint.signum() == f_lower.signum() {
+            state.bracket = Some((midpoint, upper));
+            state.f_bracket = (f_midpoint, f_upper);
+        } else {
+            state.bracket = Some((lower, midpoint));
+            state.f_bracket = (f_lower, f_midpoint);
+        }
+        state.root = Some(midpoint);
+        state.residual = f_midpoint.abs();
+        Ok(state.check_convergence())
+    }
+
+    fn finalise(
+        &mut self,
+        _problem: &mut Problem<P>,
+        state: BracketState,
+    ) -> Result<Self::Output, Self::Error> {
+        Ok(state)
+    }
+}
+
+/// Root finding by Brent's method.
+///
+/// Combines inverse quadratic interpolation and the secant method with bisection, taking the
+/// fast interpolated step when it stays safely within the bracket and bisecting otherwise. It
+/// keeps the certainty of bisection while usually converging superlinearly. The measure is
+/// `|f(x)|` at the best estimate of the root.
+pub struct Brent<F> {
+    function: F,
+    /// The best estimate of the root, and the function there
+    b: (f64, f64),
+    /// The previous estimate
+    a: (f64, f64),
+    /// The far end of the bracket from `b`
+    c: (f64, f64),
+    /// The last step, and the one before it
+    step: f64,
+    previous_step: f64,
+    /// Whether `a`, `b` and `c` hold the current bracket, which they do not on a warm start or
+    /// resume as [`Calculation::initialise`] is skipped
+    started: bool,
+}
+
+impl<F: Fn(f64) -> f64> Brent<F> {
+    pub fn new(function: F) -> Self {
+        Self {
+            function,
+            b: (0.0, 0.0),
+            a: (0.0, 0.0),
+            c: (0.0, 0.0),
+            step: 0.0,
+            previous_step: 0.0,
+            started: false,
+        }
+    }
+
+    /// Rebuild the working points from the bracket recorded in the state
+    fn start(&mut self, state: &BracketState) -> Result<(), RootError> {
+        let (lower, upper) = state.bracket.ok_or(RootError::MissingBracket)?;
+        let (f_lower, f_upper) = state.f_bracket;
+        self.a = (lower, f_lower);
+        self.b = (upper, f_upper);
+        self.c = self.b;
+        self.step = 0.0;
+        self.previous_step = 0.0;
+        self.arrange();
+        self.started = true;
+        Ok(())
+    }
+
+    /// Keep the root bracketed between `b` and `c`, with `b` the end closer to it
+    fn arrange(&mut self) {
+        if self.b.1.signum() == self.c.1.signum() {
+            self.c = self.a;
+            self.step = self.b.0 - self.a.0;
+            self.previous_step = self.step;
+        }
+        if self.c.1.abs() < self.b.1.abs() {
+            self.a = self.b;
+            self.b = self.c;
+            self.c = self.a;
+        }
+    }
+
+    fn record(&self, mut state: BracketState) -> BracketState {
+        let (b, c) = (self.b.0, self.c.0);
+        state.bracket = Some((b.min(c), b.max(c)));
+        state.f_bracket = if b < c {
+            (self.b.1, self.c.1)
+        } else {
+            (self.c.1, self.b.1)
+        };
+        state.root = Some(b);
+        state.residual = self.b.1.abs();
+        state
+    }
+}
+
+impl<P, F: Fn(f64) -> f64> Calculation<P, BracketState> for Brent<F> {
+    type Error = RootError;
+    type Output = BracketState;
+    const NAME: &'static str = "brent";
+
+    fn initialise(
+        &mut self,
+        problem: &mut Problem<P>,
+        mut state: BracketState,
+    ) -> Result<BracketState, Self::Error> {
+        evaluate_bracket(&self.function, problem, &mut state)?;
+        self.start(&state)?;
+        Ok(self.record(state))
+    }
+
+    fn next(
+        &mut self,
+        problem: &mut Problem<P>,
+        state: BracketState,
+    ) -> Result<BracketState, Self::Error> {
+        if !self.started {
+            self.start(&state)?;
+        }
+        let (a, fa) = self.a;
+        let (b, fb) = self.b;
+        let (c, fc) = self.c;
+        let tolerance = 2.0 * f64::EPSILON * b.abs() + 0.5 * state.tolerance;
+        let midpoint = 0.5 * (c - b);
+
+        if self.previous_step.abs() >= tolerance && fa.abs() > fb.abs() {
+            // Interpolate, by the secant method when only two points are known and inverse
+            // quadratic interpolation otherwise
+            let s = fb / fa;
+            let (mut p, mut q) = if a == c {
+                (2.0 * midpoint * s, 1.0 - s)
+            } else {
+                let q = fa / fc;
+                let r = fb / fc;
+                (
+                    s * (2.0 * midpoint * q * (q - r) - (b - a) * (r - 1.0)),
+                    (q - 1.0) * (r - 1.0) * (s - 1.0),
+                )
+            };
+            if p > 0.0 {
+                q = -q;
+            }
+            p = p.abs();
+            let limit =
+                (3.0 * midpoint * q - (tolerance * q).abs()).min((self.previous_step * q).abs());
+            if 2.0 * p < limit {
+                self.previous_step = self.step;
+                self.step = p / q;
+            } else {
+                self.step = midpoint;
+                self.previous_step = midpoint;
+            }
+        } else {
+            self.step = midpoint;
+            self.previous_step = midpoint;
+        }
+
+        self.a = self.b;
+        let x = if self.step.abs() > tolerance {
+            b + self.step
+        } else {
+            b + tolerance.copysign(midpoint)
+        };
+        problem.record_evaluations(1);
+        self.b = (x, (self.function)(x));
+        self.arrange();
+        Ok(self.record(state).check_convergence())
+    }
+
+    fn finalise(
+        &mut self,
+        _problem: &mut Problem<P>,
+        state: BracketState,
+    ) -> Result<Self::Output, Self::Error> {
+        Ok(state)
+    }
+}
+
+/// The state of a bracketing root finder.
+///
+/// The initial bracket must be given with [`BracketState::bracket`], usually through
+/// [`Builder::configure`](crate::Builder::configure). The run converges once the bracket is
+/// narrower than twice the tolerance, or the function is exactly zero at the estimate of the
+/// root. The state can be checkpointed with a [`Checkpointer`](crate::Checkpointer).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BracketState {
+    bracket: Option<(f64, f64)>,
+    /// The function at each end of the bracket
+    f_bracket: (f64, f64),
+    root: Option<f64>,
+    residual: f64,
+    best_residual: f64,
+    best_iteration: usize,
+    iteration: usize,
+    tolerance: f64,
+    max_iterations: usize,
+    time: Option<Duration>,
+    termination_reason: Option<Reason>,
+    run_id: Option<RunId>,
+    #[serde(skip)]
+    extensions: Extensions,
+    initialised: bool,
+}
+
+impl BracketState {
+    /// Search for a root between `lower` and `upper`, over which the function changes sign
+    #[must_use]
+    pub fn bracket(mut self, lower: f64, upper: f64) -> Self {
+        self.bracket = Some((lower.min(upper), lower.max(upper)));
+        self
+    }
+
+    /// Converge once the root is located to within `tolerance`
+    #[must_use]
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    #[must_use]
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// The best estimate of the root
+    pub fn root(&self) -> Option<f64> {
+        self.root
+    }
+
+    /// The current bracket, within which the root lies
+    pub fn current_bracket(&self) -> Option<(f64, f64)> {
+        self.bracket
+    }
+
+    fn check_convergence(self) -> Self {
+        let width = self
+            .bracket
+            .map_or(f64::INFINITY, |(lower, upper)| upper - lower);
+        if self.residual == 0.0 || width <= 2.0 * self.tolerance {
+            self.terminate_due_to(Reason::Converged)
+        } else if self.iteration + 1 >= self.max_iterations {
+            self.terminate_due_to(Reason::ExceededMaxIterations)
+        } else {
+            self
+        }
+    }
+}
+
+impl Default for BracketState {
+    fn default() -> Self {
+        Self {
+            bracket: None,
+            f_bracket: (f64::NAN, f64::NAN),
+            root: None,
+            residual: f64::INFINITY,
+            best_residual: f64::INFINITY,
+            best_iteration: 0,
+            iteration: 0,
+            tolerance: f64::EPSILON.sqrt(),
+            max_iterations: 200,
+            time: None,
+            termination_reason: None,
+            run_id: None,
+            extensions: Extensions::default(),
+            initialised: false,
+        }
+    }
+}
+
+impl State for BracketState {
+    type Float = f64;
+    type Param = f64;
+
+    fn record_time(&mut self, duration: Duration) {
+        self.time = Some(duration);
+    }
+    fn duration(&self) -> Option<Duration> {
+        self.time
+    }
+    fn increment_iteration(&mut self) {
+        self.iteration += 1;
+    }
+    fn current_iteration(&self) -> usize {
+        self.iteration
+    }
+    fn update(mut self) -> Self {
+        if self.residual < self.best_residual {
+            self.best_residual = self.residual;
+            self.best_iteration = self.iteration;
+        }
+        self
+    }
+    fn is_initialised(&self) -> bool {
+        self.initialised
+    }
+    fn is_terminated(&self) -> bool {
+        self.termination_reason.is_some()
+    }
+    fn terminate_due_to(mut self, reason: Reason) -> Self {
+        self.termination_reason = Some(reason);
+        self
+    }
+    fn termination_reason(&self) -> Option<Reason> {
+        self.termination_reason.clone()
+    }
+    fn get_param(&self) -> Option<&f64> {
+        self.root.as_ref()
+    }
+    fn measure(&self) -> f64 {
+        self.residual
+    }
+    fn best_measure(&self) -> f64 {
+        self.best_residual
+    }
+    fn iterations_since_best(&self) -> usize {
+        self.iteration - self.best_iteration
+    }
+    fn kv(&self) -> KV {
+        let kv = KV::new().with("tolerance", self.tolerance);
+        match self.bracket {
+            Some((lower, upper)) => kv.with("bracket_width", upper - lower),
+            None => kv,
+        }
+    }
+    fn for_warm_start(mut self) -> Self {
+        self.termination_reason = None;
+        self
+    }
+    fn set_run_id(&mut self, run_id: RunId) {
+        self.run_id = Some(run_id);
+    }
+    fn run_id(&self) -> Option<&RunId> {
+        self.run_id.as_ref()
+    }
+    fn extensions(&self) -> Option<&Extensions> {
+        Some(&self.extensions)
+    }
+    fn extensions_mut(&mut self) -> Option<&mut Extensions> {
+        Some(&mut self.extensions)
+    }
+}
@@ -1,12 +1,128 @@
-use std::fmt::Display;
+use std::borrow::Cow;
+use std::fmt::{self, Display};
 
 use hifitime::Duration;
 use serde::{Deserialize, Serialize};
 
-pub trait TrellisFloat: Display + Serialize {}
+use crate::{Extensions, RunId, KV};
 
-impl TrellisFloat for f32 {}
-impl TrellisFloat for f64 {}
+/// Types which can be used as the measure of a calculation.
+///
+/// Smaller measures are better. Trellis reports measures, and makes the comparisons it needs
+/// for convergence and stall checks, through their real part and [`Measure::improves_on`]. Types
+/// carrying more information, such as dual numbers propagating derivatives, can therefore be
+/// used as measures without stripping the extra information at the loop boundary. Discrete
+/// objectives such as edit distances and counts are measures too, through the implementations
+/// for the integer types, which compare exactly rather than through their real part.
+///
+/// The measure is the associated `Float` type of a [`State`], which is named for the floating
+/// point measures it was first limited to.
+pub trait Measure: Display + Serialize {
+    /// The real part of the value, used for reporting and for comparisons against tolerances
+    fn real(&self) -> f64;
+
+    /// The worst possible measure, for a state to start its best measure from
+    fn worst() -> Self
+    where
+        Self: Sized;
+
+    /// The unit of the measure, shown in logs and plot axis labels
+    fn unit() -> Option<String> {
+        None
+    }
+
+    /// Whether `self` is a strictly better (smaller) measure than `other`
+    fn improves_on(&self, other: &Self) -> bool {
+        self.real() < other.real()
+    }
+}
+
+/// The former name of [`Measure`], from when measures were limited to floating point types
+pub use Measure as TrellisFloat;
+
+impl Measure for f32 {
+    fn real(&self) -> f64 {
+        f64::from(*self)
+    }
+
+    fn worst() -> Self {
+        f32::INFINITY
+    }
+}
+
+impl Measure for f64 {
+    fn real(&self) -> f64 {
+        *self
+    }
+
+    fn worst() -> Self {
+        f64::INFINITY
+    }
+}
+
+macro_rules! integer_measure {
+    ($($integer:ty),*) => {
+        $(
+            impl Measure for $integer {
+                fn real(&self) -> f64 {
+                    *self as f64
+                }
+
+                fn worst() -> Self {
+                    <$integer>::MAX
+                }
+
+                fn improves_on(&self, other: &Self) -> bool {
+                    self < other
+                }
+            }
+        )*
+    };
+}
+
+integer_measure!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// A measure of any [`num_traits::Float`], for float types trellis has no implementation for.
+///
+/// Software and reduced precision floats, such as `half::f16` or wrappers around arbitrary
+/// precision types, usually implement `Float` already. Wrapping them is enough to drive a run,
+/// without implementing [`Measure`] by hand. Measures are compared in the wrapped type, and
+/// reported through their nearest `f64`.
+#[cfg(feature = "num-traits")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct FloatMeasure<T>(pub T);
+
+#[cfg(feature = "num-traits")]
+impl<T: Display> Display for FloatMeasure<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "num-traits")]
+impl<T: Serialize> Serialize for FloatMeasure<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "num-traits")]
+impl<T> Measure for FloatMeasure<T>
+where
+    T: num_traits::Float + Display + Serialize,
+{
+    fn real(&self) -> f64 {
+        self.0.to_f64().unwrap_or(f64::NAN)
+    }
+
+    fn worst() -> Self {
+        Self(T::infinity())
+    }
+
+    fn improves_on(&self, other: &Self) -> bool {
+        self.0 < other.0
+    }
+}
 
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
 pub enum Status {
@@ -20,27 +136,140 @@ impl Default for Status {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Reason {
     ControlC,
     Controller,
     Converged,
     ExceededMaxIterations,
+    /// A predicate registered with `Builder::terminate_if` returned `true`
+    UserPredicate,
+    /// The measure was NaN, infinite or, if forbidden, negative
+    InvalidMeasure,
+    /// The problem was evaluated as many times as allowed by [`State::max_evaluations`]
+    ExceededEvaluationBudget,
+    /// An iteration took longer than the timeout set with `Builder::iteration_timeout`
+    IterationTimedOut,
+    /// A reason specific to the calculation, given to [`State::terminate_with_reason`]
+    User(Cow<'static, str>),
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::User(reason) => f.write_str(reason),
+            reason => fmt::Debug::fmt(reason, f),
+        }
+    }
 }
 
 pub trait State {
-    type Float: TrellisFloat;
+    type Float: Measure;
     type Param;
-    fn new() -> Self;
+    /// A fresh state, the [`Default`] one unless overridden.
+    ///
+    /// Used as the initial state of runners built with
+    /// [`GenerateBuilder::build_for`](crate::GenerateBuilder::build_for), handed to observers in
+    /// place of a state lost to a panic, and to describe the schema of checkpoints. States with
+    /// mandatory data need not have a default, and are given their initial state with
+    /// [`GenerateBuilder::build_with_state`](crate::GenerateBuilder::build_with_state).
+    ///
+    /// `new` was previously required, and is now only available to states with a default. A
+    /// state which implemented `new` alone must move its body into [`Default::default`] to keep
+    /// being built with `build_for` or checkpointed.
+    fn new() -> Self
+    where
+        Self: Default,
+    {
+        Self::default()
+    }
     fn record_time(&mut self, duration: Duration);
+    /// Time spent in the run so far, as last recorded with [`State::record_time`].
+    ///
+    /// States which do not keep the time can leave this as `None`.
+    fn duration(&self) -> Option<Duration> {
+        None
+    }
     fn increment_iteration(&mut self);
     fn current_iteration(&self) -> usize;
     fn update(self) -> Self;
     fn is_initialised(&self) -> bool;
     fn is_terminated(&self) -> bool;
     fn terminate_due_to(self, reason: Reason) -> Self;
+    /// Terminate for a reason specific to the calculation, such as a collapsed trust region
+    fn terminate_with_reason(self, reason: impl Into<Cow<'static, str>>) -> Self
+    where
+        Self: Sized,
+    {
+        self.terminate_due_to(Reason::User(reason.into()))
+    }
+    /// The reason the run terminated, if it has
+    fn termination_reason(&self) -> Option<Reason> {
+        None
+    }
     fn get_param(&self) -> Option<&Self::Param>;
+    /// Mutable access to the parameter, for adaptors which adjust it between iterations such as
+    /// [`AndersonAccelerated`](crate::solvers::AndersonAccelerated).
+    ///
+    /// The default gives no access.
+    fn get_param_mut(&mut self) -> Option<&mut Self::Param> {
+        None
+    }
     fn measure(&self) -> Self::Float;
     fn best_measure(&self) -> Self::Float;
     fn iterations_since_best(&self) -> usize;
+    /// Additional values to report to observers alongside the measures
+    fn kv(&self) -> KV {
+        KV::default()
+    }
+    /// Prepare a state from an earlier run to be continued by a new one.
+    ///
+    /// The default returns the state unchanged. States which record why they terminated should
+    /// clear it here, keeping the parameters, measures and best-measure bookkeeping.
+    fn for_warm_start(self) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
+    /// Set the relative tolerance used to decide convergence.
+    ///
+    /// Called before every iteration when the runner was built with a tolerance schedule. States
+    /// which use a fixed tolerance can ignore it.
+    fn set_relative_tolerance(&mut self, _tolerance: f64) {}
+    /// The number of problem evaluations the run may use.
+    ///
+    /// The run terminates with [`Reason::ExceededEvaluationBudget`] once the evaluations counted
+    /// with [`Problem::record_evaluations`](crate::Problem::record_evaluations) reach the budget.
+    /// The budget is checked between iterations, so the last iteration can overrun it. This suits
+    /// expensive objectives, where evaluations rather than iterations are the cost.
+    fn max_evaluations(&self) -> Option<u64> {
+        None
+    }
+    /// Store the identifier of the run, which is assigned before the state is initialised.
+    ///
+    /// States which do not record the identifier can ignore it.
+    fn set_run_id(&mut self, _run_id: RunId) {}
+    /// The identifier of the run the state belongs to, if recorded
+    fn run_id(&self) -> Option<&RunId> {
+        None
+    }
+    /// Data attached to the state by subsystems and plugins.
+    ///
+    /// States which hold an [`Extensions`] field return it here and from
+    /// [`State::extensions_mut`], making it available to anything with access to the state.
+    fn extensions(&self) -> Option<&Extensions> {
+        None
+    }
+    fn extensions_mut(&mut self) -> Option<&mut Extensions> {
+        None
+    }
+    /// A two-dimensional field computed by the calculation, such as the solution of a PDE.
+    ///
+    /// Rows run along the first axis of the grid and columns along the second. The field is
+    /// plotted by [`PlotGenerator::heatmap`](crate::PlotGenerator::heatmap).
+    #[cfg(feature = "plotting")]
+    fn field(&self) -> Option<ndarray::ArrayView2<'_, Self::Float>> {
+        None
+    }
 }
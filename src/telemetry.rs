@@ -0,0 +1,83 @@
+//! A batteries-included observability plugin.
+use std::path::PathBuf;
+
+use tracing::Level;
+
+use crate::{
+    watchers::{Frequency, Heartbeat, JsonLinesLogger, RunSummary, Tracer},
+    Installer, Plugin, State,
+};
+
+/// Standard telemetry for a run, installed with one call to
+/// [`Builder::with_plugin`](crate::Builder::with_plugin).
+///
+/// Writes to a directory:
+///
+/// - `heartbeat.json`, replaced on every iteration with the latest progress
+/// - `history.jsonl`, one line per iteration
+/// - `termination.json`, why and where the run stopped
+/// - `report.md`, the final [`Report`](crate::Report) with the measure history
+///
+/// and logs significant iterations through `tracing`. The history, termination record and report
+/// are recorded in the [artifact index](crate::artifacts) of the directory when the run finishes.
+///
+/// Creating the telemetry fails if the directory or the history file cannot be created. Once
+/// installed every part is best effort: a write which fails is logged as a warning and the run
+/// carries on.
+pub struct Telemetry {
+    dir: PathBuf,
+    history: JsonLinesLogger<std::io::BufWriter<fs_err::File>>,
+    trace_level: Option<Level>,
+    heartbeat: Frequency,
+}
+
+impl Telemetry {
+    /// Write telemetry below `dir`, creating it if it does not exist
+    pub fn in_dir(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        fs_err::create_dir_all(&dir)?;
+        Ok(Self {
+            history: JsonLinesLogger::to_file(dir.join("history.jsonl"))?,
+            dir,
+            trace_level: Some(Level::INFO),
+            heartbeat: Frequency::Always,
+        })
+    }
+
+    /// Set the level significant iterations are logged at, or disable logging with `None`
+    #[must_use]
+    pub fn with_trace_level(mut self, level: Option<Level>) -> Self {
+        self.trace_level = level;
+        self
+    }
+
+    /// Set how often the heartbeat file is replaced, by default every iteration
+    #[must_use]
+    pub fn with_heartbeat(mut self, frequency: Frequency) -> Self {
+        self.heartbeat = frequency;
+        self
+    }
+}
+
+impl<S: State + 'static> Plugin<S> for Telemetry {
+    fn name(&self) -> &'static str {
+        "telemetry"
+    }
+
+    fn install(self, installer: &mut Installer<'_, S>) {
+        if let Some(level) = self.trace_level {
+            installer.attach_best_effort_observer(Tracer::new(level), Frequency::Significant(0.1));
+        }
+        installer
+            .attach_best_effort_observer(
+                Heartbeat::new(self.dir.join("heartbeat.json")),
+                self.heartbeat,
+            )
+            .attach_best_effort_observer(self.history, Frequency::Always)
+            .attach_best_effort_observer(
+                RunSummary::new(&self.dir).recording(self.dir.join("history.jsonl")),
+                Frequency::Always,
+            )
+            .annotate("dir", self.dir.display());
+    }
+}
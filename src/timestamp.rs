@@ -0,0 +1,42 @@
+//! Timestamps attached to observations and history records.
+//!
+//! Each timestamp pairs the time elapsed since the run started, read from a monotonic clock so it
+//! never jumps backwards, with the wall-clock time. The monotonic part orders and spaces the
+//! records of one run reliably, while the wall-clock part aligns the records of runs on different
+//! machines on a common timeline.
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use hifitime::Epoch;
+use serde::{Deserialize, Serialize};
+
+/// When an observation was made
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Timestamp {
+    /// Time since the run started, from a monotonic clock
+    pub elapsed: Duration,
+    /// Wall-clock time of the observation
+    pub wall: SystemTime,
+}
+
+impl Timestamp {
+    /// The current time, relative to a run started at `start`
+    pub(crate) fn since(start: Instant) -> Self {
+        Self {
+            elapsed: start.elapsed(),
+            wall: SystemTime::now(),
+        }
+    }
+
+    /// The wall-clock time in seconds since the Unix epoch
+    pub fn unix_seconds(&self) -> f64 {
+        match self.wall.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs_f64(),
+            Err(before) => -before.duration().as_secs_f64(),
+        }
+    }
+
+    /// The wall-clock time as a hifitime epoch
+    pub fn epoch(&self) -> Epoch {
+        Epoch::from_unix_seconds(self.unix_seconds())
+    }
+}
@@ -0,0 +1,120 @@
+//! Dimensioned measures backed by [`uom`](https://crates.io/crates/uom) quantities.
+use serde::{Serialize, Serializer};
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use uom::si::{Dimension, Quantity, Unit, Units};
+use uom::typenum::Integer;
+
+use crate::Measure;
+
+/// A measure carrying SI units.
+///
+/// The measure is compared and serialised through its value in SI base units, while logs and plot
+/// axis labels carry the unit symbol, so dimensional information is not lost at the runner
+/// boundary.
+pub struct SiMeasure<D, U>(pub Quantity<D, U, f64>)
+where
+    D: Dimension + ?Sized,
+    U: Units<f64> + ?Sized;
+
+impl<D, U> SiMeasure<D, U>
+where
+    D: Dimension + ?Sized,
+    U: Units<f64> + ?Sized,
+{
+    /// The unit symbol in SI base units, for example `m s^-2`
+    pub fn symbol() -> String {
+        fn push<I: Integer, N: Unit>(symbol: &mut Vec<String>) {
+            match I::to_i32() {
+                0 => {}
+                1 => symbol.push(N::abbreviation().to_owned()),
+                exponent => symbol.push(format!("{}^{}", N::abbreviation(), exponent)),
+            }
+        }
+
+        let mut symbol = vec![];
+        push::<D::M, U::mass>(&mut symbol);
+        push::<D::L, U::length>(&mut symbol);
+        push::<D::T, U::time>(&mut symbol);
+        push::<D::I, U::electric_current>(&mut symbol);
+        push::<D::Th, U::thermodynamic_temperature>(&mut symbol);
+        push::<D::N, U::amount_of_substance>(&mut symbol);
+        push::<D::J, U::luminous_intensity>(&mut symbol);
+        symbol.join(" ")
+    }
+}
+
+impl<D, U> From<Quantity<D, U, f64>> for SiMeasure<D, U>
+where
+    D: Dimension + ?Sized,
+    U: Units<f64> + ?Sized,
+{
+    fn from(quantity: Quantity<D, U, f64>) -> Self {
+        Self(quantity)
+    }
+}
+
+impl<D, U> Clone for SiMeasure<D, U>
+where
+    D: Dimension + ?Sized,
+    U: Units<f64> + ?Sized,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D, U> Copy for SiMeasure<D, U>
+where
+    D: Dimension + ?Sized,
+    U: Units<f64> + ?Sized,
+{
+}
+
+impl<D, U> Display for SiMeasure<D, U>
+where
+    D: Dimension + ?Sized,
+    U: Units<f64> + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = Self::symbol();
+        if symbol.is_empty() {
+            write!(f, "{}", self.0.value)
+        } else {
+            write!(f, "{} {}", self.0.value, symbol)
+        }
+    }
+}
+
+impl<D, U> Serialize for SiMeasure<D, U>
+where
+    D: Dimension + ?Sized,
+    U: Units<f64> + ?Sized,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.value.serialize(serializer)
+    }
+}
+
+impl<D, U> Measure for SiMeasure<D, U>
+where
+    D: Dimension + ?Sized,
+    U: Units<f64> + ?Sized,
+{
+    fn real(&self) -> f64 {
+        self.0.value
+    }
+
+    fn worst() -> Self {
+        Self(Quantity {
+            dimension: PhantomData,
+            units: PhantomData,
+            value: f64::INFINITY,
+        })
+    }
+
+    fn unit() -> Option<String> {
+        let symbol = Self::symbol();
+        (!symbol.is_empty()).then_some(symbol)
+    }
+}
@@ -0,0 +1,85 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+
+use crate::{
+    watchers::{ObservationError, Observer, Stage},
+    writers::{ArrayFormat, ArrayParam, Compression, FsyncPolicy, Writer},
+    State,
+};
+
+/// Writes array parameters as `.npy` or CSV files, keeping their shape.
+///
+/// Unlike [`FileWriter`](crate::FileWriter) the parameter does not need to implement
+/// `Serialize`: any [`ArrayParam`], which includes `ndarray` and `nalgebra` vectors and
+/// matrices when their features are enabled, can be written directly.
+pub struct ArrayWriter {
+    writer: RefCell<Writer>,
+    format: ArrayFormat,
+}
+
+impl ArrayWriter {
+    pub fn new(dir: PathBuf, identifier: String, format: ArrayFormat) -> Self {
+        Self {
+            writer: RefCell::new(Writer::new(dir, identifier).unwrap()),
+            format,
+        }
+    }
+
+    /// Set when written files are synced to disk, by default only when the run is finalised
+    #[must_use]
+    pub fn with_fsync_policy(self, policy: FsyncPolicy) -> Self {
+        self.writer.borrow_mut().set_fsync_policy(policy);
+        self
+    }
+
+    /// Compress written arrays, adding the suffix of the compression to their file names
+    #[must_use]
+    pub fn with_compression(self, compression: Compression) -> Self {
+        self.writer.borrow_mut().compress_params(compression);
+        self
+    }
+
+    /// Encrypt written arrays with `key`, adding `.enc` to their file names
+    #[cfg(feature = "encryption")]
+    #[must_use]
+    pub fn with_encryption(self, key: crate::EncryptionKey) -> Self {
+        self.writer.borrow_mut().encrypt_with(key);
+        self
+    }
+
+    fn observe_iteration<S>(&self, state: &S) -> Result<(), ObservationError>
+    where
+        S: State,
+        <S as State>::Param: ArrayParam,
+    {
+        if let Some(param) = state.get_param() {
+            let identifier = format!("{}", state.current_iteration());
+            self.writer
+                .borrow_mut()
+                .write_array(self.format, &identifier, param)
+                .map_err(|e| ObservationError::Writer(Box::new(e)))?;
+        }
+        Ok(())
+    }
+}
+
+impl<S> Observer<S> for ArrayWriter
+where
+    S: State,
+    <S as State>::Param: ArrayParam,
+{
+    fn observe(&self, _ident: &'static str, subject: &S, stage: Stage) {
+        if let Some(run_id) = subject.run_id() {
+            self.writer.borrow_mut().scope_to_run(run_id);
+        }
+        match stage {
+            Stage::Iteration => self.observe_iteration(subject),
+            _ => Ok(()),
+        }
+        .unwrap()
+    }
+
+    fn output_path(&self) -> Option<PathBuf> {
+        Some(self.writer.borrow().directory().to_owned())
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::mpsc;
+
+use crate::watchers::{FallbackClock, Observer, Stage};
+use crate::{RunId, State, Timestamp, KV};
+
+/// An observation, decoupled from the state it was taken from
+#[derive(Clone, Debug)]
+pub struct ObservationEvent<F> {
+    /// Name of the calculation
+    pub ident: &'static str,
+    /// The run the observation was taken from, if the state records it
+    pub run_id: Option<RunId>,
+    /// The iteration at which the observation was made
+    pub iteration: usize,
+    /// The measure at the iteration
+    pub measure: F,
+    /// The stage of the run
+    pub stage: Stage,
+    /// When the observation was made
+    pub timestamp: Timestamp,
+    /// Additional values reported by the state
+    pub kv: KV,
+}
+
+/// The sending half of a channel which can receive observation events
+pub trait EventSink<F> {
+    /// Send an event, returning `false` if it could not be delivered
+    fn send_event(&self, event: ObservationEvent<F>) -> bool;
+}
+
+impl<F> EventSink<F> for mpsc::Sender<ObservationEvent<F>> {
+    fn send_event(&self, event: ObservationEvent<F>) -> bool {
+        self.send(event).is_ok()
+    }
+}
+
+impl<F> EventSink<F> for mpsc::SyncSender<ObservationEvent<F>> {
+    // Never block the calculation on a slow consumer
+    fn send_event(&self, event: ObservationEvent<F>) -> bool {
+        self.try_send(event).is_ok()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<F> EventSink<F> for tokio::sync::mpsc::Sender<ObservationEvent<F>> {
+    // Never block the calculation on a slow consumer
+    fn send_event(&self, event: ObservationEvent<F>) -> bool {
+        self.try_send(event).is_ok()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<F> EventSink<F> for tokio::sync::mpsc::UnboundedSender<ObservationEvent<F>> {
+    fn send_event(&self, event: ObservationEvent<F>) -> bool {
+        self.send(event).is_ok()
+    }
+}
+
+/// Streams every observation down a channel.
+///
+/// This decouples consumers such as user interfaces or network services from the thread running
+/// the calculation. Bounded channels never block the calculation: when they are full the event
+/// is dropped. Events sent after the receiver has been dropped are discarded.
+pub struct ChannelObserver<T> {
+    sender: T,
+    clock: FallbackClock,
+}
+
+impl<T> ChannelObserver<T> {
+    pub fn new(sender: T) -> Self {
+        Self {
+            sender,
+            clock: FallbackClock::default(),
+        }
+    }
+}
+
+impl<S: State, T: EventSink<S::Float>> Observer<S> for ChannelObserver<T> {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        self.observe_at(ident, subject, stage, &self.clock.timestamp(stage));
+    }
+
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
+        let _ = self.sender.send_event(ObservationEvent {
+            ident,
+            run_id: subject.run_id().cloned(),
+            iteration: subject.current_iteration(),
+            measure: subject.measure(),
+            stage,
+            timestamp: *timestamp,
+            kv: KV::of(subject),
+        });
+    }
+}
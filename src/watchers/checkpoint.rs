@@ -0,0 +1,161 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+    artifacts, checkpoint,
+    watchers::{ObservationError, Observer, Stage},
+    writers::{Compression, FsyncPolicy},
+    RunMetadata, State,
+};
+
+/// Writes the state to a checkpoint file, replacing the previous checkpoint on every observation.
+///
+/// The frequency the observer is attached with sets how often checkpoints are taken. A run
+/// interrupted part way through can be continued from the last checkpoint with
+/// [`Builder::resume_from_checkpoint`](crate::Builder::resume_from_checkpoint).
+///
+/// Checkpoints record the calculation which wrote them, so they are only resumed by the same
+/// calculation. Attach the checkpointer with
+/// [`Builder::attach_checkpointer`](crate::Builder::attach_checkpointer) to record it, otherwise it
+/// is only known for states which carry [`RunMetadata`] in their extensions.
+pub struct Checkpointer {
+    path: PathBuf,
+    fsync_policy: FsyncPolicy,
+    compression: Compression,
+    /// The name and version of the calculation, if given
+    calculation: Option<(&'static str, &'static str)>,
+    /// Whether a checkpoint has been written without knowing the calculation
+    unrecorded: AtomicBool,
+    #[cfg(feature = "encryption")]
+    encryption: Option<crate::EncryptionKey>,
+}
+
+impl Checkpointer {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            fsync_policy: FsyncPolicy::default(),
+            compression: Compression::None,
+            calculation: None,
+            unrecorded: AtomicBool::new(false),
+            #[cfg(feature = "encryption")]
+            encryption: None,
+        }
+    }
+
+    /// Record `name` and `version` as the calculation which wrote the checkpoints
+    #[must_use]
+    pub fn for_calculation(mut self, name: &'static str, version: &'static str) -> Self {
+        self.calculation = Some((name, version));
+        self
+    }
+
+    /// Set when checkpoints are synced to disk, by default only the final checkpoint
+    #[must_use]
+    pub fn with_fsync_policy(mut self, policy: FsyncPolicy) -> Self {
+        self.fsync_policy = policy;
+        self
+    }
+
+    /// Compress checkpoints, which pays off for states holding large parameters
+    #[must_use]
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Encrypt checkpoints with `key`.
+    ///
+    /// Encrypted checkpoints are resumed with
+    /// [`Builder::resume_from_encrypted_checkpoint`](crate::Builder::resume_from_encrypted_checkpoint).
+    #[cfg(feature = "encryption")]
+    #[must_use]
+    pub fn with_encryption(mut self, key: crate::EncryptionKey) -> Self {
+        self.encryption = Some(key);
+        self
+    }
+
+    /// The calculation which produced `state`, empty if it is not known
+    fn calculation<'a, S: State>(
+        &'a self,
+        ident: &'static str,
+        state: &'a S,
+    ) -> (&'a str, &'a str) {
+        if let Some(calculation) = self.calculation {
+            return calculation;
+        }
+        if let Some(metadata) = state
+            .extensions()
+            .and_then(|extensions| extensions.get::<RunMetadata>())
+        {
+            return (&metadata.calculation, &metadata.calculation_version);
+        }
+        if !self.unrecorded.swap(true, Ordering::Relaxed) {
+            tracing::warn!(
+                calculation = ident,
+                "checkpoints do not record the calculation, so resuming cannot check it"
+            );
+        }
+        ("", "")
+    }
+
+    fn save<S: State + Serialize + Default>(
+        &self,
+        ident: &'static str,
+        state: &S,
+        sync: bool,
+    ) -> Result<(), checkpoint::CheckpointError> {
+        let calculation = self.calculation(ident, state);
+        #[cfg(feature = "encryption")]
+        if let Some(key) = self.encryption.as_ref() {
+            return checkpoint::save_encrypted(
+                state,
+                &self.path,
+                calculation,
+                self.compression,
+                key,
+                sync,
+            );
+        }
+        checkpoint::save(state, &self.path, calculation, self.compression, sync)
+    }
+}
+
+impl<S: State + Serialize + Default> Observer<S> for Checkpointer {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        if stage == Stage::Initialisation {
+            return;
+        }
+        let sync = match self.fsync_policy {
+            FsyncPolicy::Never => false,
+            FsyncPolicy::Final => stage == Stage::Finalisation,
+            FsyncPolicy::Always => true,
+        };
+        if let Err(e) = self.save(ident, subject, sync) {
+            tracing::warn!(calculation = ident, error = %e, "failed to write checkpoint");
+            return;
+        }
+        let dir = self.path.parent().unwrap_or(Path::new(""));
+        let run_id = subject.run_id().map(|run_id| run_id.as_str());
+        if let Err(e) = artifacts::record(dir, &self.path, run_id) {
+            tracing::warn!(calculation = ident, error = %e, "failed to record checkpoint");
+        }
+    }
+
+    fn start(&self) -> Result<(), ObservationError> {
+        match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => {
+                Err(ObservationError::Unavailable(format!(
+                    "checkpoint directory {} does not exist",
+                    parent.display()
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn output_path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+}
@@ -0,0 +1,226 @@
+//! A live dashboard served over HTTP.
+//!
+//! [`Dashboard`] serves two routes from a background thread: `/` returns a page which polls the
+//! run and redraws its convergence chart, and `/state` returns the current state of the run as
+//! JSON for scripts and other tools.
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use tiny_http::{Header, Response, Server};
+
+use crate::{
+    watchers::{Observer, Stage},
+    Measure, RunId, State,
+};
+
+/// The number of measures kept for the chart unless set with [`Dashboard::with_history_limit`]
+const DEFAULT_HISTORY_LIMIT: usize = 10_000;
+
+/// The state of the run, as served at `/state`
+#[derive(Clone, Debug, Default, Serialize)]
+struct Snapshot {
+    calculation: Option<&'static str>,
+    run_id: Option<RunId>,
+    stage: Option<Stage>,
+    iteration: usize,
+    measure: Option<f64>,
+    best_measure: Option<f64>,
+    iterations_since_best: usize,
+    /// Iteration and measure pairs, oldest first
+    history: VecDeque<(usize, f64)>,
+}
+
+/// Serves the progress of a run to a browser.
+///
+/// The server is started when the dashboard is bound and stops when it is dropped, which is when
+/// the runner it is attached to is dropped.
+pub struct Dashboard {
+    snapshot: Arc<Mutex<Snapshot>>,
+    history_limit: usize,
+    server: Arc<Server>,
+    address: SocketAddr,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Dashboard {
+    /// Start serving on `address`.
+    ///
+    /// Bind to port 0 to let the operating system choose a free port, and read it back with
+    /// [`Dashboard::local_addr`].
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let server = Server::http(address).map_err(io::Error::other)?;
+        let address = server
+            .server_addr()
+            .to_ip()
+            .ok_or_else(|| io::Error::other("dashboard must be bound to an IP address"))?;
+        let server = Arc::new(server);
+        let snapshot = Arc::new(Mutex::new(Snapshot::default()));
+
+        let handle = {
+            let server = server.clone();
+            let snapshot = snapshot.clone();
+            std::thread::Builder::new()
+                .name("trellis-dashboard".to_owned())
+                .spawn(move || serve(&server, &snapshot))?
+        };
+
+        Ok(Self {
+            snapshot,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            server,
+            address,
+            handle: Some(handle),
+        })
+    }
+
+    /// Keep at most `limit` measures for the chart, discarding the oldest
+    #[must_use]
+    pub fn with_history_limit(mut self, limit: usize) -> Self {
+        self.history_limit = limit.max(1);
+        self
+    }
+
+    /// The address the dashboard is served on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl<S: State> Observer<S> for Dashboard {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        let mut snapshot = self.snapshot.lock().unwrap();
+        if let Stage::Initialisation = stage {
+            *snapshot = Snapshot::default();
+        }
+        let measure = subject.measure().real();
+        if let Stage::Iteration = stage {
+            if snapshot.history.len() == self.history_limit {
+                snapshot.history.pop_front();
+            }
+            snapshot
+                .history
+                .push_back((subject.current_iteration(), measure));
+        }
+        snapshot.calculation = Some(ident);
+        snapshot.run_id = subject.run_id().cloned();
+        snapshot.stage = Some(stage);
+        snapshot.iteration = subject.current_iteration();
+        snapshot.measure = Some(measure);
+        snapshot.best_measure = Some(subject.best_measure().real());
+        snapshot.iterations_since_best = subject.iterations_since_best();
+    }
+}
+
+fn serve(server: &Server, snapshot: &Mutex<Snapshot>) {
+    for request in server.incoming_requests() {
+        let (body, content_type) = match request.url() {
+            "/" => (PAGE.to_owned(), "text/html; charset=utf-8"),
+            "/state" => {
+                let snapshot = snapshot.lock().unwrap().clone();
+                match serde_json::to_string(&snapshot) {
+                    Ok(json) => (json, "application/json"),
+                    Err(e) => {
+                        tracing::warn!(error = %e, "failed to serialize dashboard state");
+                        let _ = request.respond(Response::empty(500));
+                        continue;
+                    }
+                }
+            }
+            _ => {
+                let _ = request.respond(Response::empty(404));
+                continue;
+            }
+        };
+        let header = Header::from_bytes("Content-Type", content_type)
+            .expect("content type must be a valid header");
+        // The browser may have gone away, which does not concern the run
+        let _ = request.respond(Response::from_string(body).with_header(header));
+    }
+}
+
+const PAGE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>trellis</title>
+<style>
+body { font-family: sans-serif; margin: 2em; background: #111; color: #ddd; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+td { padding: 0.2em 1em 0.2em 0; }
+canvas { background: #1b1b1b; }
+</style>
+</head>
+<body>
+<h2 id="calculation">waiting for a run</h2>
+<table id="summary"></table>
+<canvas id="chart" width="900" height="500"></canvas>
+<script>
+const rows = [
+  ["run", s => s.run_id],
+  ["stage", s => s.stage],
+  ["iteration", s => s.iteration],
+  ["measure", s => s.measure === null ? null : s.measure.toExponential(4)],
+  ["best measure", s => s.best_measure === null ? null : s.best_measure.toExponential(4)],
+  ["iterations since best", s => s.iterations_since_best],
+];
+
+function draw(history) {
+  const canvas = document.getElementById("chart");
+  const ctx = canvas.getContext("2d");
+  ctx.clearRect(0, 0, canvas.width, canvas.height);
+  const points = history.filter(([, y]) => y > 0).map(([x, y]) => [x, Math.log10(y)]);
+  if (points.length < 2) return;
+  const xs = points.map(p => p[0]), ys = points.map(p => p[1]);
+  const [x0, x1] = [Math.min(...xs), Math.max(...xs)];
+  const [y0, y1] = [Math.floor(Math.min(...ys)), Math.ceil(Math.max(...ys))];
+  const pad = 50, w = canvas.width - 2 * pad, h = canvas.height - 2 * pad;
+  const px = x => pad + (x - x0) / Math.max(x1 - x0, 1) * w;
+  const py = y => pad + h - (y - y0) / Math.max(y1 - y0, 1) * h;
+  ctx.strokeStyle = "#444";
+  ctx.fillStyle = "#aaa";
+  for (let y = y0; y <= y1; y++) {
+    ctx.beginPath(); ctx.moveTo(pad, py(y)); ctx.lineTo(pad + w, py(y)); ctx.stroke();
+    ctx.fillText("1e" + y, 5, py(y) + 4);
+  }
+  ctx.fillText(x0, pad, pad + h + 20);
+  ctx.fillText(x1, pad + w - 20, pad + h + 20);
+  ctx.strokeStyle = "#4caf50";
+  ctx.beginPath();
+  points.forEach(([x, y], i) => i === 0 ? ctx.moveTo(px(x), py(y)) : ctx.lineTo(px(x), py(y)));
+  ctx.stroke();
+}
+
+async function refresh() {
+  try {
+    const state = await (await fetch("/state")).json();
+    if (state.calculation !== null) {
+      document.getElementById("calculation").textContent = state.calculation;
+    }
+    document.getElementById("summary").innerHTML = rows
+      .map(([name, value]) => `<tr><td>${name}</td><td>${value(state) ?? "-"}</td></tr>`)
+      .join("");
+    draw(state.history);
+  } catch (e) {
+    document.getElementById("calculation").textContent = "dashboard unreachable";
+  }
+}
+
+refresh();
+setInterval(refresh, 1000);
+</script>
+</body>
+</html>
+"##;
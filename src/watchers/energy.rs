@@ -0,0 +1,183 @@
+use std::cell::{Cell, RefCell};
+use std::sync::{Arc, Mutex};
+
+use hifitime::{Duration, Epoch};
+use tracing::info;
+
+use crate::watchers::{Observer, Stage};
+
+/// Where energy readings come from
+pub enum EnergySource {
+    /// The Linux powercap interface to Intel/AMD RAPL counters, summed over all packages
+    Rapl,
+    /// A user supplied power meter, returning the cumulative energy consumed in joules
+    Callback(Box<dyn Fn() -> Option<f64> + Send>),
+}
+
+impl EnergySource {
+    fn read(&self) -> Option<Vec<Counter>> {
+        match self {
+            Self::Rapl => rapl::read(),
+            Self::Callback(callback) => callback().map(|joules| {
+                vec![Counter {
+                    joules,
+                    wraps_at: None,
+                }]
+            }),
+        }
+    }
+}
+
+/// A single cumulative energy counter
+#[derive(Copy, Clone, Debug)]
+struct Counter {
+    joules: f64,
+    /// The value at which the counter wraps back to zero, if it does
+    wraps_at: Option<f64>,
+}
+
+impl Counter {
+    fn since(&self, last: &Counter) -> f64 {
+        match self.wraps_at {
+            Some(wraps_at) if self.joules < last.joules => wraps_at - last.joules + self.joules,
+            _ => self.joules - last.joules,
+        }
+    }
+}
+
+/// Energy consumed by a run
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct EnergyReport {
+    /// Total energy consumed in joules
+    pub joules: f64,
+    /// Wall time over which the energy was measured
+    pub wall_time: Duration,
+}
+
+/// Samples an energy counter on every observation, reporting the total consumed by the run.
+///
+/// The total is logged when the run is finalised, and can be read afterwards through the handle
+/// returned by [`EnergyMeter::report_handle`].
+pub struct EnergyMeter {
+    source: EnergySource,
+    last: RefCell<Option<Vec<Counter>>>,
+    start: Cell<Option<Epoch>>,
+    report: Arc<Mutex<EnergyReport>>,
+    warned: Cell<bool>,
+}
+
+impl EnergyMeter {
+    pub fn new(source: EnergySource) -> Self {
+        Self {
+            source,
+            last: RefCell::new(None),
+            start: Cell::new(None),
+            report: Arc::new(Mutex::new(EnergyReport::default())),
+            warned: Cell::new(false),
+        }
+    }
+
+    /// A handle to the running total, which remains valid after the run completes
+    pub fn report_handle(&self) -> Arc<Mutex<EnergyReport>> {
+        self.report.clone()
+    }
+
+    fn sample(&self) {
+        let Some(counters) = self.source.read() else {
+            if !self.warned.replace(true) {
+                tracing::warn!("energy counter is unavailable, energy will not be reported");
+            }
+            return;
+        };
+
+        let mut report = self.report.lock().unwrap();
+        if let Some(last) = self.last.borrow().as_ref() {
+            let joules = counters
+                .iter()
+                .zip(last)
+                .map(|(counter, last)| counter.since(last))
+                .sum::<f64>();
+            report.joules += joules;
+            crate::ledger::record_energy(joules);
+        }
+        if let (Some(start), Ok(now)) = (self.start.get(), Epoch::now()) {
+            report.wall_time = now - start;
+        }
+        self.last.replace(Some(counters));
+    }
+}
+
+impl<S> Observer<S> for EnergyMeter {
+    fn observe(&self, ident: &'static str, _subject: &S, stage: Stage) {
+        match stage {
+            Stage::Initialisation => {
+                self.start.set(Epoch::now().ok());
+                self.sample();
+            }
+            Stage::Iteration => self.sample(),
+            Stage::Restart | Stage::Termination => {}
+            Stage::Finalisation | Stage::Aborted => {
+                self.sample();
+                let report = *self.report.lock().unwrap();
+                info!(
+                    calculation = ident,
+                    energy_joules = report.joules,
+                    wall_time = %report.wall_time,
+                    "energy consumed"
+                );
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod rapl {
+    use super::Counter;
+    use std::fs;
+    use std::path::Path;
+
+    const POWERCAP: &str = "/sys/class/powercap";
+
+    fn read_joules(path: &Path) -> Option<f64> {
+        let microjoules: f64 = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+        Some(microjoules * 1e-6)
+    }
+
+    // Top level domains (`intel-rapl:0`, `intel-rapl:1`, ...) are the packages, subdomains
+    // (`intel-rapl:0:0`) are already included in their package's counter
+    pub(super) fn read() -> Option<Vec<Counter>> {
+        let mut packages = fs::read_dir(POWERCAP)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .map(|name| name.to_string_lossy())
+                    .is_some_and(|name| {
+                        name.starts_with("intel-rapl:") && name.matches(':').count() == 1
+                    })
+            })
+            .collect::<Vec<_>>();
+        packages.sort();
+
+        let counters = packages
+            .iter()
+            .map(|path| {
+                Some(Counter {
+                    joules: read_joules(&path.join("energy_uj"))?,
+                    wraps_at: read_joules(&path.join("max_energy_range_uj")),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        (!counters.is_empty()).then_some(counters)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod rapl {
+    use super::Counter;
+
+    pub(super) fn read() -> Option<Vec<Counter>> {
+        None
+    }
+}
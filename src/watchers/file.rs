@@ -4,7 +4,7 @@ use std::path::PathBuf;
 
 use crate::{
     watchers::{ObservationError, Observer, Stage, Target},
-    writers::{WriteToFileSerializer, Writeable, Writer},
+    writers::{Compression, FsyncPolicy, Rotation, WriteToFileSerializer, Writeable, Writer},
     State,
 };
 
@@ -32,19 +32,64 @@ impl<'a, P: Serialize> Writeable for WriteableItem<'a, P> {
 }
 
 impl FileWriter {
+    /// Write the target below `dir`.
+    ///
+    /// Parameters are written with `serializer`. Measures are written as a CSV trace, compressed
+    /// once complete if the serializer compresses.
     pub fn new(
         dir: PathBuf,
         identifier: String,
         serializer: WriteToFileSerializer,
         target: Target,
     ) -> Self {
+        let mut writer = Writer::new(dir, identifier).unwrap();
+        writer.compress_traces(serializer.compression());
+        writer.compress_params(serializer.compression());
         Self {
-            writer: RefCell::new(Writer::new(dir, identifier).unwrap()),
+            writer: RefCell::new(writer),
             serializer,
             target,
         }
     }
 
+    /// Set when written files are synced to disk, by default only when the run is finalised
+    #[must_use]
+    pub fn with_fsync_policy(self, policy: FsyncPolicy) -> Self {
+        self.writer.borrow_mut().set_fsync_policy(policy);
+        self
+    }
+
+    /// Compress written parameters and completed measure traces, in place of the compression of
+    /// the serializer
+    #[must_use]
+    pub fn with_compression(self, compression: Compression) -> Self {
+        self.writer.borrow_mut().compress_traces(compression);
+        self.writer.borrow_mut().compress_params(compression);
+        self
+    }
+
+    /// Split the measure trace into chunks, listed with their iteration ranges in
+    /// `measure.index.csv`.
+    ///
+    /// Long runs can then tail the latest chunk or delete older ones. Only the measure trace is
+    /// rotated, as parameters are already written to one file per iteration.
+    #[must_use]
+    pub fn rotate_every(self, rotation: Rotation) -> Self {
+        self.writer.borrow_mut().rotate_every(rotation);
+        self
+    }
+
+    /// Encrypt written parameters with `key`, adding `.enc` to their file names.
+    ///
+    /// Parameters are compressed before they are encrypted, and read back with
+    /// [`decrypt`](crate::decrypt). The measure trace holds no parameters and stays plaintext.
+    #[cfg(feature = "encryption")]
+    #[must_use]
+    pub fn with_encryption(self, key: crate::EncryptionKey) -> Self {
+        self.writer.borrow_mut().encrypt_with(key);
+        self
+    }
+
     #[must_use]
     pub(crate) fn with_writeable_identifier(self, identifier: String) -> Self {
         self.writer
@@ -60,12 +105,19 @@ where
     <S as State>::Param: Serialize,
 {
     fn observe(&self, _ident: &'static str, subject: &S, stage: Stage) {
+        if let Some(run_id) = subject.run_id() {
+            self.writer.borrow_mut().scope_to_run(run_id);
+        }
         match stage {
             Stage::Iteration => self.observe_iteration(subject),
             _ => Ok(()),
         }
         .unwrap()
     }
+
+    fn output_path(&self) -> Option<PathBuf> {
+        Some(self.writer.borrow().directory().to_owned())
+    }
 }
 
 /// `WriteToFile` only implements `observer_iter` and not `observe_init` to avoid saving the
@@ -0,0 +1,139 @@
+//! Attaching and detaching observers while a run is in progress.
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use super::{Attachment, FrequencySet, Naming, Observer, ObserverVec};
+
+/// Identifies an observer attached to a run, so it can be detached later
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ObserverId(u64);
+
+impl ObserverId {
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+enum Change<S> {
+    Attach {
+        id: ObserverId,
+        observer: Box<dyn Observer<S> + Send>,
+        naming: Naming,
+        frequency: FrequencySet,
+    },
+    Detach(ObserverId),
+    DetachByName(String),
+}
+
+/// A handle for attaching and detaching observers while a run is in progress.
+///
+/// Handles are cheap to clone and can be sent to other threads, for example to turn on verbose
+/// logging when a run looks stuck. Changes take effect before the next iteration, so an observer
+/// attached part way through a run is not notified of the initialisation. Observers attached
+/// through a handle must be `Send`, and are started when they are attached: one which cannot
+/// start is disabled, with a warning recorded in
+/// [`Runner::observer_warnings`](crate::Runner::observer_warnings).
+pub struct ObserverHandle<S> {
+    changes: Arc<Mutex<Vec<Change<S>>>>,
+}
+
+impl<S> Clone for ObserverHandle<S> {
+    fn clone(&self) -> Self {
+        Self {
+            changes: self.changes.clone(),
+        }
+    }
+}
+
+impl<S> ObserverHandle<S> {
+    pub(crate) fn new() -> Self {
+        Self {
+            changes: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    /// Attach an observer before the next iteration, named after its type
+    pub fn attach<OBS: Observer<S> + Send + 'static>(
+        &self,
+        observer: OBS,
+        frequency: impl Into<FrequencySet>,
+    ) -> ObserverId {
+        self.attach_as(Naming::of::<OBS>(), observer, frequency.into())
+    }
+
+    /// Attach an observer under `name` before the next iteration
+    pub fn attach_named<OBS: Observer<S> + Send + 'static>(
+        &self,
+        name: impl Into<String>,
+        observer: OBS,
+        frequency: impl Into<FrequencySet>,
+    ) -> ObserverId {
+        self.attach_as(Naming::Given(name.into()), observer, frequency.into())
+    }
+
+    fn attach_as<OBS: Observer<S> + Send + 'static>(
+        &self,
+        naming: Naming,
+        observer: OBS,
+        frequency: FrequencySet,
+    ) -> ObserverId {
+        let id = ObserverId::next();
+        self.changes.lock().unwrap().push(Change::Attach {
+            id,
+            observer: Box::new(observer),
+            naming,
+            frequency,
+        });
+        id
+    }
+
+    /// Detach an observer before the next iteration.
+    ///
+    /// Detaching an observer which is not attached does nothing.
+    pub fn detach(&self, id: ObserverId) {
+        self.changes.lock().unwrap().push(Change::Detach(id));
+    }
+
+    /// Detach every observer named `name` before the next iteration
+    pub fn detach_by_name(&self, name: impl Into<String>) {
+        self.changes
+            .lock()
+            .unwrap()
+            .push(Change::DetachByName(name.into()));
+    }
+
+    /// Make the requested changes to `observers`, returning a warning for each observer which
+    /// could not start
+    pub(crate) fn apply(&self, observers: &mut ObserverVec<S>) -> Vec<String> {
+        let changes = std::mem::take(&mut *self.changes.lock().unwrap());
+        let mut warnings = vec![];
+        for change in changes {
+            match change {
+                Change::Attach {
+                    id,
+                    observer,
+                    naming,
+                    frequency,
+                } => {
+                    if let Err(e) = observer.start() {
+                        tracing::warn!(observer = id.0, error = %e, "disabling observer");
+                        warnings.push(format!("observer {} disabled: {e}", id.0));
+                        continue;
+                    }
+                    let observer: Arc<dyn Observer<S> + Send> = Arc::from(observer);
+                    observers.attach_as(id, observer, naming, frequency, Attachment::Required);
+                }
+                Change::Detach(id) => {
+                    observers.detach_id(id);
+                }
+                Change::DetachByName(name) => {
+                    observers.detach_name(&name);
+                }
+            }
+        }
+        warnings
+    }
+}
@@ -0,0 +1,136 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::{
+    watchers::{FallbackClock, ObservationError, Observer, Stage},
+    Reason, RunId, State, Timestamp,
+};
+
+/// Writes one self-describing JSON object per observation.
+///
+/// Each line carries the calculation name, run identifier if the state records one, stage,
+/// iteration, measure, best measure, seconds elapsed since initialisation on a monotonic clock
+/// and the wall-clock time in seconds since the Unix epoch, along with the termination reason once
+/// the run has one and any fields added through [`JsonLinesLogger::with_field`]. A log can be
+/// replayed with [`ReplayCalculation`](crate::ReplayCalculation). The output is independent of
+/// any logging framework and can be loaded directly with `jq` or `pandas.read_json(...,
+/// lines=True)`. A line which cannot be written is logged as a warning and skipped, leaving the
+/// run to continue.
+pub struct JsonLinesLogger<W: Write> {
+    writer: RefCell<W>,
+    fields: Map<String, Value>,
+    clock: FallbackClock,
+}
+
+#[derive(Serialize)]
+struct Event<'a, F> {
+    calculation: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_id: Option<&'a RunId>,
+    stage: Stage,
+    iteration: usize,
+    measure: F,
+    best_measure: F,
+    elapsed: f64,
+    timestamp: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<Reason>,
+    #[serde(flatten)]
+    fields: &'a Map<String, Value>,
+}
+
+impl<W: Write> JsonLinesLogger<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: RefCell::new(writer),
+            fields: Map::new(),
+            clock: FallbackClock::default(),
+        }
+    }
+
+    /// Add a field which is written with every event, for example a run label.
+    ///
+    /// Fails if the value cannot be represented as JSON, such as a map with non-string keys.
+    pub fn with_field<V: Serialize>(
+        mut self,
+        key: impl Into<String>,
+        value: V,
+    ) -> Result<Self, serde_json::Error> {
+        let value = serde_json::to_value(value)?;
+        self.fields.insert(key.into(), value);
+        Ok(self)
+    }
+
+    fn write_event<S: State>(
+        &self,
+        ident: &'static str,
+        state: &S,
+        stage: Stage,
+        reason: Option<Reason>,
+        timestamp: &Timestamp,
+    ) -> Result<(), ObservationError> {
+        let event = Event {
+            calculation: ident,
+            run_id: state.run_id(),
+            stage,
+            iteration: state.current_iteration(),
+            measure: state.measure(),
+            best_measure: state.best_measure(),
+            elapsed: timestamp.elapsed.as_secs_f64(),
+            timestamp: timestamp.unix_seconds(),
+            reason: reason.or_else(|| state.termination_reason()),
+            fields: &self.fields,
+        };
+
+        let mut writer = self.writer.borrow_mut();
+        serde_json::to_writer(&mut *writer, &event)
+            .map_err(|e| ObservationError::Writer(Box::new(e)))?;
+        writeln!(writer).map_err(|e| ObservationError::Writer(Box::new(e)))?;
+        // Flush every line, so the log is complete up to the last observation if the run dies
+        writer
+            .flush()
+            .map_err(|e| ObservationError::Writer(Box::new(e)))
+    }
+}
+
+impl JsonLinesLogger<BufWriter<fs_err::File>> {
+    /// Log to a file at `path`, truncating it if it exists
+    pub fn to_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::new(BufWriter::new(fs_err::File::create(
+            path.as_ref(),
+        )?)))
+    }
+}
+
+impl<W: Write, S: State> Observer<S> for JsonLinesLogger<W> {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        self.observe_at(ident, subject, stage, &self.clock.timestamp(stage));
+    }
+
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
+        if let Err(e) = self.write_event(ident, subject, stage, None, timestamp) {
+            tracing::warn!(calculation = ident, error = %e, "failed to write JSON lines event");
+        }
+    }
+
+    fn observe_termination(
+        &self,
+        ident: &'static str,
+        subject: &S,
+        reason: Option<&Reason>,
+        timestamp: &Timestamp,
+    ) {
+        if let Err(e) = self.write_event(
+            ident,
+            subject,
+            Stage::Termination,
+            reason.cloned(),
+            timestamp,
+        ) {
+            tracing::warn!(calculation = ident, error = %e, "failed to write JSON lines event");
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt;
+
+use log::Level;
+
+use crate::state::{Measure, State};
+use crate::watchers::{FallbackClock, Observer, Stage};
+use crate::{Reason, Timestamp};
+
+/// A logger using the [`log`](https://crates.io/crates/log) facade as backend.
+///
+/// Each iteration is logged as a single line under the `trellis` target, such as
+///
+/// ```text
+/// newton iteration 12: measure 3.052e-5, best 3.052e-5, 0 since best, 0.024 s
+/// ```
+///
+/// so applications logging through `env_logger` or similar see progress without any setup.
+pub struct LogLogger {
+    level: Level,
+    clock: FallbackClock,
+}
+
+impl LogLogger {
+    pub fn new(level: Level) -> Self {
+        if matches!(level, Level::Error | Level::Warn) {
+            panic!("we won't emit non-error messages at ERROR or WARN...");
+        }
+        Self {
+            level,
+            clock: FallbackClock::default(),
+        }
+    }
+}
+
+const TARGET: &str = "trellis";
+
+/// Formats the unit of the measure after a value, or nothing for measures without one
+struct UnitSuffix(Option<String>);
+
+impl fmt::Display for UnitSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.as_deref() {
+            Some(unit) => write!(f, " {unit}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl LogLogger {
+    fn log_termination(&self, ident: &str, reason: Option<&Reason>) {
+        match reason {
+            Some(reason) => log::log!(target: TARGET, self.level, "terminated: {ident}: {reason}"),
+            None => log::log!(target: TARGET, self.level, "terminated: {ident}"),
+        }
+    }
+}
+
+impl<S: State> Observer<S> for LogLogger {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        self.observe_at(ident, subject, stage, &self.clock.timestamp(stage));
+    }
+
+    fn observe_termination(
+        &self,
+        ident: &'static str,
+        _subject: &S,
+        reason: Option<&Reason>,
+        _timestamp: &Timestamp,
+    ) {
+        self.log_termination(ident, reason);
+    }
+
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
+        // Skip reading the state when the line would be discarded anyway
+        if !log::log_enabled!(target: TARGET, self.level) {
+            return;
+        }
+        let elapsed = timestamp.elapsed.as_secs_f64();
+        match stage {
+            Stage::Initialisation => log::log!(target: TARGET, self.level, "initialising: {ident}"),
+            Stage::Aborted => log::log!(target: TARGET, self.level, "aborted: {ident}"),
+            Stage::Restart => log::log!(
+                target: TARGET,
+                self.level,
+                "restarting: {ident} after {} iterations",
+                subject.current_iteration()
+            ),
+            Stage::Termination => {
+                self.log_termination(ident, subject.termination_reason().as_ref())
+            }
+            Stage::Finalisation => log::log!(
+                target: TARGET,
+                self.level,
+                "finalising: {ident} after {} iterations, best {:.3e}, {elapsed:.3} s",
+                subject.current_iteration(),
+                subject.best_measure().real(),
+            ),
+            Stage::Iteration => {
+                let unit = UnitSuffix(S::Float::unit());
+                log::log!(
+                    target: TARGET,
+                    self.level,
+                    "{ident} iteration {}: measure {:.3e}{unit}, best {:.3e}{unit}, {} since best, {elapsed:.3} s",
+                    subject.current_iteration(),
+                    subject.measure().real(),
+                    subject.best_measure().real(),
+                    subject.iterations_since_best(),
+                );
+            }
+        }
+    }
+}
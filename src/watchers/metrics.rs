@@ -0,0 +1,33 @@
+use metrics::{counter, gauge};
+
+use crate::state::{Measure, State};
+use crate::watchers::{Observer, Stage};
+
+/// Publishes the progress of a run through the [`metrics`](https://crates.io/crates/metrics)
+/// facade.
+///
+/// Every iteration updates the `trellis.iteration` counter and the `trellis.measure`,
+/// `trellis.best_measure` and `trellis.iterations_since_best` gauges, each labelled with the name
+/// of the calculation. Any installed recorder (Prometheus, StatsD, ...) picks them up.
+#[derive(Clone, Debug, Default)]
+pub struct MetricsPublisher {}
+
+impl MetricsPublisher {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<S: State> Observer<S> for MetricsPublisher {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        if let Stage::Iteration = stage {
+            counter!("trellis.iteration", "calculation" => ident)
+                .absolute(subject.current_iteration() as u64);
+            gauge!("trellis.measure", "calculation" => ident).set(subject.measure().real());
+            gauge!("trellis.best_measure", "calculation" => ident)
+                .set(subject.best_measure().real());
+            gauge!("trellis.iterations_since_best", "calculation" => ident)
+                .set(subject.iterations_since_best() as f64);
+        }
+    }
+}
@@ -1,8 +1,27 @@
-use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
 
+use hifitime::Duration;
+
+use crate::{Measure, Reason, State, Timestamp};
+
+#[cfg(feature = "writing")]
+mod array;
+#[cfg(feature = "writing")]
+mod checkpoint;
 #[cfg(feature = "writing")]
 mod file;
 
+#[cfg(feature = "writing")]
+pub use array::ArrayWriter;
+
+#[cfg(feature = "writing")]
+pub use checkpoint::Checkpointer;
+
 #[cfg(feature = "writing")]
 pub use file::FileWriter;
 
@@ -11,29 +30,355 @@ mod plot;
 #[cfg(feature = "plotting")]
 pub use plot::PlotGenerator;
 
+mod channel;
+pub use channel::{ChannelObserver, EventSink, ObservationEvent};
+
+#[cfg(feature = "dashboard")]
+mod dashboard;
+#[cfg(feature = "dashboard")]
+pub use dashboard::Dashboard;
+
+#[cfg(feature = "energy")]
+mod energy;
+#[cfg(feature = "energy")]
+pub use energy::{EnergyMeter, EnergyReport, EnergySource};
+
+#[cfg(feature = "writing")]
+mod jsonl;
+#[cfg(feature = "writing")]
+pub use jsonl::JsonLinesLogger;
+
+#[cfg(feature = "log")]
+mod log;
+#[cfg(feature = "log")]
+pub use log::LogLogger;
+
+#[cfg(feature = "notify")]
+mod notify;
+#[cfg(feature = "notify")]
+pub use notify::NotifyOnExit;
+
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "metrics")]
+pub use metrics::MetricsPublisher;
+
+#[cfg(feature = "otel")]
+mod otel;
+#[cfg(feature = "otel")]
+pub use otel::OtelMetrics;
+
+#[cfg(feature = "tokio")]
+mod progress;
+#[cfg(feature = "tokio")]
+pub(crate) use progress::ProgressPublisher;
+#[cfg(feature = "tokio")]
+pub use progress::ProgressSnapshot;
+
+#[cfg(feature = "parquet")]
+mod parquet;
+#[cfg(feature = "parquet")]
+pub use parquet::ParquetWriter;
+
+mod registry;
+
+mod ring;
+pub use ring::RingFile;
+
+#[cfg(feature = "writing")]
+mod remote;
+#[cfg(feature = "writing")]
+pub use remote::{DispatchError, RemoteDispatcher, RemoteForwarder, RemoteState};
+
+#[cfg(feature = "writing")]
+mod suspend;
+#[cfg(feature = "writing")]
+pub(crate) use suspend::{forget_outages, outages, SinkHealth};
+#[cfg(feature = "writing")]
+pub use suspend::{Autosuspend, Outage, SuspendPolicy};
+
+#[cfg(feature = "static-plots")]
+mod static_plot;
+#[cfg(feature = "static-plots")]
+pub use static_plot::{StaticPlotFormat, StaticPlotGenerator};
+
+#[cfg(feature = "writing")]
+mod telemetry;
+#[cfg(feature = "writing")]
+pub use telemetry::{Heartbeat, RunSummary};
+
+#[cfg(feature = "webhook")]
+mod webhook;
+#[cfg(feature = "webhook")]
+pub use webhook::WebhookObserver;
+
+#[cfg(feature = "slog")]
+mod slog;
+#[cfg(feature = "slog")]
+pub use slog::SlogLogger;
+
+pub use registry::{clear_default_observers, register_default_observer, QUIET_ENV_VAR};
+pub(crate) use registry::{default_observer_count, default_observers};
+
 mod tracing;
-pub use tracing::Tracer;
+pub use tracing::{Tracer, TracerConfig};
+
+mod handle;
+pub use handle::{ObserverHandle, ObserverId};
+
+mod mutable;
+pub use mutable::{Mutable, ObserverMut};
+
+mod snapshot;
+pub use snapshot::{Snapshot, SnapshotAdapter, SnapshotObserver};
 
 pub enum Target {
     Param,
     Measure,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Stage {
     Initialisation,
     Finalisation,
     Iteration,
+    /// The run has stopped iterating, notified before finalisation through
+    /// [`Observer::observe_termination`] along with the reason
+    Termination,
+    /// The run terminated without success and is being restarted, as allowed by the runner's
+    /// [`RestartPolicy`](crate::RestartPolicy).
+    ///
+    /// Observers are handed the state the failed attempt terminated with. The run carries on
+    /// iterating from the restarted state, without another initialisation.
+    Restart,
+    /// The run was cut short by a panic in the calculation.
+    ///
+    /// Observers are notified of this in place of finalisation. The calculation's state was lost
+    /// with the panic, so the state they are handed is a fresh one carrying only the run
+    /// identifier.
+    Aborted,
+}
+
+/// Whether a run can go ahead without an observer whose backend is unavailable
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) enum Attachment {
+    /// Finalising the runner fails if the observer cannot start
+    #[default]
+    Required,
+    /// The observer is disabled with a warning if it cannot start
+    BestEffort,
+}
+
+/// What an attached observer is called
+#[derive(Clone, Debug)]
+pub(crate) enum Naming {
+    /// A name chosen by the user
+    Given(String),
+    /// A name generated from the type of the observer
+    Generated(&'static str),
+}
+
+impl Naming {
+    pub(crate) fn of<OBS: ?Sized>() -> Self {
+        Self::Generated(std::any::type_name::<OBS>())
+    }
+}
+
+/// How an attached observer is configured, as reported by [`Builder::plan`](crate::Builder::plan)
+/// and [`Runner::list_observers`](crate::Runner::list_observers)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObserverPlan {
+    /// The name the observer was attached with, or one generated from its type
+    pub name: String,
+    /// How often the observer is notified at each stage
+    pub frequency: FrequencySet,
+    /// Whether the run fails to start if the observer cannot
+    pub required: bool,
+    /// Where the observer writes its output, if known
+    pub output_path: Option<PathBuf>,
+}
+
+/// An observer attached to a run, with the frequency at which it is notified
+pub(crate) struct Attached<S> {
+    id: ObserverId,
+    name: String,
+    observer: Arc<dyn Observer<S>>,
+    frequency: FrequencySet,
+    attachment: Attachment,
+    /// The measure at the last iteration the observer was notified of
+    last_measure: Cell<Option<f64>>,
+    /// When the observer was last notified, measured from the start of the run
+    last_observed: Cell<Option<std::time::Duration>>,
+}
+
+impl<S> Clone for Attached<S> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            name: self.name.clone(),
+            observer: self.observer.clone(),
+            frequency: self.frequency,
+            attachment: self.attachment,
+            last_measure: self.last_measure.clone(),
+            last_observed: self.last_observed.clone(),
+        }
+    }
+}
+
+impl<S: State> Attached<S> {
+    /// Whether the observer's frequency calls for this observation, recording it if so
+    fn selects(&self, subject: &S, stage: Stage, timestamp: &Timestamp) -> bool {
+        let since_last = self
+            .last_observed
+            .get()
+            .map(|last| timestamp.elapsed.saturating_sub(last));
+        if !self
+            .frequency
+            .should_observe(subject, stage, self.last_measure.get(), since_last)
+        {
+            return false;
+        }
+        self.last_observed.set(Some(timestamp.elapsed));
+        if let Stage::Iteration = stage {
+            self.last_measure.set(Some(subject.measure().real()));
+        }
+        true
+    }
+
+    fn notify(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
+        if !self.selects(subject, stage, timestamp) {
+            return;
+        }
+        self.observer.observe_at(ident, subject, stage, timestamp);
+    }
+}
+
+/// Timestamps observations made without one, timing from the last initialisation observed
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FallbackClock(Cell<Option<Instant>>);
+
+impl FallbackClock {
+    pub(crate) fn timestamp(&self, stage: Stage) -> Timestamp {
+        let start = match (stage, self.0.get()) {
+            (Stage::Initialisation, _) | (_, None) => Instant::now(),
+            (_, Some(start)) => start,
+        };
+        self.0.set(Some(start));
+        Timestamp::since(start)
+    }
 }
 
 #[derive(Clone)]
-#[allow(clippy::type_complexity)]
-pub(crate) struct ObserverVec<S>(Vec<(Arc<Mutex<dyn Observer<S>>>, Frequency)>);
+pub(crate) struct ObserverVec<S>(Vec<Attached<S>>);
 
 impl<S> ObserverVec<S> {
     pub(crate) fn len(&self) -> usize {
         self.0.len()
     }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn attach_with(
+        &mut self,
+        observer: Arc<dyn Observer<S>>,
+        naming: Naming,
+        frequency: FrequencySet,
+        attachment: Attachment,
+    ) -> ObserverId {
+        let id = ObserverId::next();
+        self.attach_as(id, observer, naming, frequency, attachment);
+        id
+    }
+
+    pub(crate) fn attach_as(
+        &mut self,
+        id: ObserverId,
+        observer: Arc<dyn Observer<S>>,
+        naming: Naming,
+        frequency: FrequencySet,
+        attachment: Attachment,
+    ) {
+        let name = self.name(naming);
+        self.0.push(Attached {
+            id,
+            name,
+            observer,
+            frequency,
+            attachment,
+            last_measure: Cell::new(None),
+            last_observed: Cell::new(None),
+        });
+    }
+
+    // A generated name is the type name of the observer, numbered if another observer already has
+    // it, as in `FileWriter` and then `FileWriter-2`
+    fn name(&self, naming: Naming) -> String {
+        let type_name = match naming {
+            Naming::Given(name) => return name,
+            Naming::Generated(type_name) => type_name,
+        };
+        let base = type_name.split('<').next().unwrap_or(type_name);
+        let base = base.rsplit("::").next().unwrap_or(base);
+        let taken = |name: &str| self.0.iter().any(|attached| attached.name == name);
+        if !taken(base) {
+            return base.to_owned();
+        }
+        (2..)
+            .map(|number| format!("{base}-{number}"))
+            .find(|name| !taken(name))
+            .unwrap()
+    }
+
+    /// Detach every observer with the given name, returning whether any was attached
+    pub(crate) fn detach_name(&mut self, name: &str) -> bool {
+        let len = self.0.len();
+        self.0.retain(|attached| attached.name != name);
+        self.0.len() != len
+    }
+
+    /// Detach the observer with the given id, returning whether it was attached
+    pub(crate) fn detach_id(&mut self, id: ObserverId) -> bool {
+        let len = self.0.len();
+        self.0.retain(|attached| attached.id != id);
+        self.0.len() != len
+    }
+
+    /// Describe every attached observer, in the order they are notified
+    pub(crate) fn describe(&self) -> Vec<ObserverPlan> {
+        self.0
+            .iter()
+            .map(|attached| ObserverPlan {
+                name: attached.name.clone(),
+                frequency: attached.frequency,
+                required: attached.attachment == Attachment::Required,
+                output_path: attached.observer.output_path(),
+            })
+            .collect()
+    }
+
+    /// Start every observer, detaching best effort observers which fail.
+    ///
+    /// Returns a warning for each observer detached, or the position and error of the first
+    /// required observer which failed.
+    pub(crate) fn start(&mut self) -> Result<Vec<String>, (usize, ObservationError)> {
+        let mut warnings = vec![];
+        let mut started = Vec::with_capacity(self.0.len());
+        for (position, attached) in self.0.drain(..).enumerate() {
+            let result = attached.observer.start();
+            match (result, attached.attachment) {
+                (Ok(()), _) => started.push(attached),
+                (Err(e), Attachment::Required) => return Err((position, e)),
+                (Err(e), Attachment::BestEffort) => {
+                    ::tracing::warn!(observer = position, error = %e, "disabling observer");
+                    warnings.push(format!("observer {position} disabled: {e}"));
+                }
+            }
+        }
+        self.0 = started;
+        Ok(warnings)
+    }
 }
 
 impl<S> Default for ObserverVec<S> {
@@ -48,17 +393,150 @@ impl<S> ObserverVec<S> {
     }
 }
 
-#[allow(clippy::type_complexity)]
-pub(crate) struct ObserverSlice<'a, S>(&'a [(Arc<Mutex<dyn Observer<S>>>, Frequency)]);
+pub(crate) struct ObserverSlice<'a, S>(&'a [Attached<S>]);
 
+impl<S: State> ObserverVec<S> {
+    /// Notify every observer whose frequency calls for an observation at this point of the run
+    pub(crate) fn notify(
+        &self,
+        ident: &'static str,
+        subject: &S,
+        stage: Stage,
+        timestamp: &Timestamp,
+    ) {
+        self.0
+            .iter()
+            .for_each(|attached| attached.notify(ident, subject, stage, timestamp));
+    }
+
+    /// Notify every observer selected by its frequency that the run has terminated
+    pub(crate) fn notify_termination(
+        &self,
+        ident: &'static str,
+        subject: &S,
+        reason: Option<&Reason>,
+        timestamp: &Timestamp,
+    ) {
+        for attached in &self.0 {
+            if attached.selects(subject, Stage::Termination, timestamp) {
+                attached
+                    .observer
+                    .observe_termination(ident, subject, reason, timestamp);
+            }
+        }
+    }
+
+    /// Notify every observer of the iterations in `iterations` its frequency calls for, in one
+    /// batch per observer
+    pub(crate) fn notify_batch(&self, ident: &'static str, iterations: &[(S, Timestamp)]) {
+        // One buffer serves every observer, as each only borrows the batch while observing it
+        let mut batch = Vec::with_capacity(iterations.len());
+        for attached in &self.0 {
+            batch.clear();
+            batch.extend(
+                iterations
+                    .iter()
+                    .filter(|(subject, timestamp)| {
+                        attached.selects(subject, Stage::Iteration, timestamp)
+                    })
+                    .map(|(subject, timestamp)| Observation {
+                        subject,
+                        stage: Stage::Iteration,
+                        timestamp: *timestamp,
+                    }),
+            );
+            if !batch.is_empty() {
+                attached.observer.observe_batch(ident, &batch);
+            }
+        }
+    }
+}
+
+/// An observation delivered as part of a batch by [`Observer::observe_batch`]
+pub struct Observation<'a, S> {
+    /// The state at the observed iteration
+    pub subject: &'a S,
+    pub stage: Stage,
+    /// When the observation was made
+    pub timestamp: Timestamp,
+}
+
+impl<S> Clone for Observation<'_, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S> Copy for Observation<'_, S> {}
+
+/// Receives notifications as a run progresses.
+///
+/// Observers are notified through a shared reference without locking, so one which records
+/// anything keeps it in a `Cell` or `RefCell`, or implements [`ObserverMut`] and is attached
+/// wrapped in [`Mutable`]. An observer which should work with any calculation can implement
+/// [`SnapshotObserver`] instead, and be attached wrapped in a [`SnapshotAdapter`].
 pub trait Observer<S> {
     fn observe(&self, ident: &'static str, subject: &S, stage: Stage);
+
+    /// Observe, given the time the observation was made.
+    ///
+    /// The runner notifies observers through this method. The default discards the timestamp
+    /// and calls [`Observer::observe`], so only observers which record times need implement it.
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, _timestamp: &Timestamp) {
+        self.observe(ident, subject, stage)
+    }
+
+    /// Observe several iterations at once, in the order they were made.
+    ///
+    /// The runner delivers iterations in batches when built with
+    /// [`Builder::batch_notifications`](crate::Builder::batch_notifications). The default
+    /// observes each in turn with [`Observer::observe_at`]; observers which can handle a batch
+    /// more cheaply, such as by writing it in one go, can override it.
+    fn observe_batch(&self, ident: &'static str, batch: &[Observation<'_, S>]) {
+        for observation in batch {
+            self.observe_at(
+                ident,
+                observation.subject,
+                observation.stage,
+                &observation.timestamp,
+            );
+        }
+    }
+
+    /// Observe the end of the iterations, with the reason the run terminated.
+    ///
+    /// The runner calls this once, just before finalisation. The reason is the one recorded by
+    /// the state or, for states which do not record it, the one the runner terminated the run
+    /// for, so loggers and writers can tell whether the run converged, stalled or was cancelled.
+    /// The default observes [`Stage::Termination`] with [`Observer::observe_at`].
+    fn observe_termination(
+        &self,
+        ident: &'static str,
+        subject: &S,
+        _reason: Option<&Reason>,
+        timestamp: &Timestamp,
+    ) {
+        self.observe_at(ident, subject, Stage::Termination, timestamp)
+    }
+
+    /// Check the observer's backend is usable, called once when the runner is finalised.
+    ///
+    /// Observers writing to a display, a network endpoint or the filesystem can fail here rather
+    /// than partway through a run.
+    fn start(&self) -> Result<(), ObservationError> {
+        Ok(())
+    }
+
+    /// Where the observer writes its output, if it writes to the filesystem
+    fn output_path(&self) -> Option<PathBuf> {
+        None
+    }
 }
 
 pub trait Observable<S> {
     type Observer;
     fn update(&self, ident: &'static str, subject: &S, stage: Stage);
-    fn attach(&mut self, observer: Self::Observer, frequency: Frequency);
+    fn attach(&mut self, observer: Self::Observer, frequency: FrequencySet);
     fn detach(&mut self, observer: Self::Observer);
 }
 
@@ -69,18 +547,22 @@ pub(crate) struct Subject<D> {
 }
 
 impl<S> Observable<S> for ObserverVec<S> {
-    type Observer = Arc<Mutex<dyn Observer<S>>>;
+    type Observer = Arc<dyn Observer<S>>;
     fn update(&self, ident: &'static str, subject: &S, stage: Stage) {
         self.0
             .iter()
-            .map(|o| o.0.lock().unwrap())
-            .for_each(|o| o.observe(ident, subject, stage));
+            .for_each(|o| o.observer.observe(ident, subject, stage));
     }
-    fn attach(&mut self, observer: Self::Observer, frequency: Frequency) {
-        self.0.push((observer, frequency));
+    fn attach(&mut self, observer: Self::Observer, frequency: FrequencySet) {
+        self.attach_with(
+            observer,
+            Naming::Generated("observer"),
+            frequency,
+            Attachment::Required,
+        );
     }
     fn detach(&mut self, observer: Self::Observer) {
-        self.0.retain(|f| !Arc::ptr_eq(&f.0, &observer));
+        self.0.retain(|f| !Arc::ptr_eq(&f.observer, &observer));
     }
 }
 
@@ -88,14 +570,87 @@ impl<S> Observable<S> for ObserverVec<S> {
 pub enum ObservationError {
     #[error("error in writer")]
     Writer(Box<dyn std::error::Error + 'static>), // We don't wrap the actual error, as we don't want to import the deps unless requested
+    #[error("observer backend unavailable: {0}")]
+    Unavailable(String),
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Frequency {
     Never,
     Always,
     Every(usize),
     OnExit,
+    /// Observe only iterations where something notable happened.
+    ///
+    /// An iteration is observed when it finds a new best, or when the measure has improved by
+    /// more than the given fraction since the last observed iteration. Initialisation and
+    /// finalisation are always observed, so long flat stretches produce no output while the
+    /// interesting iterations are never missed.
+    Significant(f64),
+    /// Observe at most one iteration in every window of the given length.
+    ///
+    /// Suited to runs of millions of fast iterations, where any fixed iteration count is either
+    /// too frequent early on or too sparse later. Initialisation and finalisation are always
+    /// observed.
+    EveryDuration(Duration),
+    /// Observe iterations which are powers of `base`, such as 1, 2, 4, 8 and so on for a base of
+    /// two.
+    ///
+    /// Output thins out as the run progresses, so the early iterations are seen in detail while a
+    /// long run still produces a bounded amount. Initialisation and finalisation are always
+    /// observed.
+    Exponential {
+        base: usize,
+    },
+}
+
+impl Frequency {
+    fn should_observe<S: State>(
+        &self,
+        state: &S,
+        stage: Stage,
+        last_measure: Option<f64>,
+        since_last: Option<std::time::Duration>,
+    ) -> bool {
+        match (self, stage) {
+            (Self::Never, _) => false,
+            (Self::Always, _) => true,
+            (Self::OnExit, stage) => matches!(
+                stage,
+                Stage::Termination | Stage::Finalisation | Stage::Aborted
+            ),
+            (
+                _,
+                Stage::Initialisation
+                | Stage::Restart
+                | Stage::Termination
+                | Stage::Finalisation
+                | Stage::Aborted,
+            ) => true,
+            (Self::Every(n), Stage::Iteration) => {
+                state.current_iteration().is_multiple_of((*n).max(1))
+            }
+            (Self::Significant(fraction), Stage::Iteration) => {
+                let measure = state.measure().real();
+                state.iterations_since_best() == 0
+                    || last_measure.is_none_or(|last| last - measure > fraction * last.abs())
+            }
+            (Self::EveryDuration(window), Stage::Iteration) => {
+                since_last.is_none_or(|since_last| Duration::from(since_last) >= *window)
+            }
+            (Self::Exponential { base }, Stage::Iteration) => {
+                let base = (*base).max(2);
+                let mut iteration = state.current_iteration();
+                if iteration == 0 {
+                    return false;
+                }
+                while iteration.is_multiple_of(base) {
+                    iteration /= base;
+                }
+                iteration == 1
+            }
+        }
+    }
 }
 
 impl Default for Frequency {
@@ -103,3 +658,57 @@ impl Default for Frequency {
         Self::Never
     }
 }
+
+/// How often an observer is notified at each stage of a run.
+///
+/// An observer might be notified on every initialisation and wrap-up but only every hundredth
+/// iteration. Each stage is decided by its own frequency, so the `init` frequency only matters
+/// at initialisation and so on. A single [`Frequency`] converts into a set using it for every
+/// stage, which is how observers are usually attached.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct FrequencySet {
+    pub init: Frequency,
+    pub iteration: Frequency,
+    pub wrap_up: Frequency,
+}
+
+impl FrequencySet {
+    fn should_observe<S: State>(
+        &self,
+        state: &S,
+        stage: Stage,
+        last_measure: Option<f64>,
+        since_last: Option<std::time::Duration>,
+    ) -> bool {
+        let frequency = match stage {
+            Stage::Initialisation | Stage::Restart => self.init,
+            Stage::Iteration => self.iteration,
+            Stage::Termination | Stage::Finalisation | Stage::Aborted => self.wrap_up,
+        };
+        frequency.should_observe(state, stage, last_measure, since_last)
+    }
+}
+
+impl From<Frequency> for FrequencySet {
+    fn from(frequency: Frequency) -> Self {
+        Self {
+            init: frequency,
+            iteration: frequency,
+            wrap_up: frequency,
+        }
+    }
+}
+
+impl fmt::Display for FrequencySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.init == self.iteration && self.iteration == self.wrap_up {
+            write!(f, "{:?}", self.iteration)
+        } else {
+            write!(
+                f,
+                "init {:?}, iteration {:?}, wrap-up {:?}",
+                self.init, self.iteration, self.wrap_up
+            )
+        }
+    }
+}
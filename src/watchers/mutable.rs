@@ -0,0 +1,94 @@
+//! Observers which mutate themselves when notified.
+//!
+//! Observers are notified through a shared reference, so the runner can dispatch to them without
+//! locking. Most keep their running state in a `Cell` or `RefCell`. An observer which is simpler
+//! to write against `&mut self` can implement [`ObserverMut`] instead and be attached wrapped in
+//! [`Mutable`].
+use std::cell::RefCell;
+use std::path::PathBuf;
+
+use crate::{Reason, Timestamp};
+
+use super::{ObservationError, Observer, Stage};
+
+/// An observer which takes exclusive access to itself when notified
+pub trait ObserverMut<S> {
+    fn observe_mut(&mut self, ident: &'static str, subject: &S, stage: Stage);
+
+    /// Observe, given the time the observation was made, as [`Observer::observe_at`]
+    fn observe_at_mut(
+        &mut self,
+        ident: &'static str,
+        subject: &S,
+        stage: Stage,
+        _timestamp: &Timestamp,
+    ) {
+        self.observe_mut(ident, subject, stage)
+    }
+
+    /// Observe the end of the iterations, as [`Observer::observe_termination`]
+    fn observe_termination_mut(
+        &mut self,
+        ident: &'static str,
+        subject: &S,
+        _reason: Option<&Reason>,
+        timestamp: &Timestamp,
+    ) {
+        self.observe_at_mut(ident, subject, Stage::Termination, timestamp)
+    }
+
+    /// Check the observer's backend is usable, as [`Observer::start`]
+    fn start_mut(&mut self) -> Result<(), ObservationError> {
+        Ok(())
+    }
+
+    /// Where the observer writes its output, if it writes to the filesystem
+    fn output_path(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Adapts an [`ObserverMut`] to be attached as an observer
+pub struct Mutable<O>(RefCell<O>);
+
+impl<O> Mutable<O> {
+    pub fn new(observer: O) -> Self {
+        Self(RefCell::new(observer))
+    }
+
+    pub fn into_inner(self) -> O {
+        self.0.into_inner()
+    }
+}
+
+impl<S, O: ObserverMut<S>> Observer<S> for Mutable<O> {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        self.0.borrow_mut().observe_mut(ident, subject, stage);
+    }
+
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
+        self.0
+            .borrow_mut()
+            .observe_at_mut(ident, subject, stage, timestamp);
+    }
+
+    fn observe_termination(
+        &self,
+        ident: &'static str,
+        subject: &S,
+        reason: Option<&Reason>,
+        timestamp: &Timestamp,
+    ) {
+        self.0
+            .borrow_mut()
+            .observe_termination_mut(ident, subject, reason, timestamp);
+    }
+
+    fn start(&self) -> Result<(), ObservationError> {
+        self.0.borrow_mut().start_mut()
+    }
+
+    fn output_path(&self) -> Option<PathBuf> {
+        self.0.borrow().output_path()
+    }
+}
@@ -0,0 +1,149 @@
+use std::cell::RefCell;
+use std::process::Command;
+
+use crate::watchers::{FallbackClock, Observer, Stage};
+use crate::{Measure, Reason, State, Timestamp};
+
+/// Announces that a run has finished, with a desktop notification or a shell command.
+///
+/// When the run is finalised the notification shows the calculation, why the run terminated and
+/// how long it took, which is handy for runs lasting hours. The hook, if set, is run with `sh -c`
+/// (`cmd /C` on Windows) and given the same details in the environment variables
+/// `TRELLIS_CALCULATION`, `TRELLIS_REASON`, `TRELLIS_ELAPSED`, `TRELLIS_ITERATION` and
+/// `TRELLIS_MEASURE`. A run aborted by a panic is announced with the reason `panicked`, without
+/// the iteration and measure. Failing to notify logs a warning rather than failing the run.
+pub struct NotifyOnExit {
+    desktop: bool,
+    hook: Option<String>,
+    /// Why the run terminated, as given by the runner
+    reason: RefCell<Option<Reason>>,
+    clock: FallbackClock,
+}
+
+impl Default for NotifyOnExit {
+    fn default() -> Self {
+        Self {
+            desktop: true,
+            hook: None,
+            reason: RefCell::new(None),
+            clock: FallbackClock::default(),
+        }
+    }
+}
+
+impl NotifyOnExit {
+    /// Show a desktop notification when the run finishes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `command` when the run finishes, as well as any desktop notification
+    #[must_use]
+    pub fn with_hook(mut self, command: impl Into<String>) -> Self {
+        self.hook = Some(command.into());
+        self
+    }
+
+    /// Only run the hook, without showing a desktop notification
+    #[must_use]
+    pub fn without_desktop(mut self) -> Self {
+        self.desktop = false;
+        self
+    }
+
+    /// Announce the end of the run, with the final iteration and best measure if they are known
+    fn notify(
+        &self,
+        ident: &'static str,
+        reason: &str,
+        outcome: Option<(usize, f64)>,
+        timestamp: &Timestamp,
+    ) {
+        let elapsed = hifitime::Duration::from(timestamp.elapsed);
+
+        if self.desktop {
+            let (summary, body) = match outcome {
+                Some((iteration, measure)) => (
+                    format!("{ident} finished"),
+                    format!(
+                        "{reason} after {iteration} iterations in {elapsed}\nbest measure {measure:.3e}"
+                    ),
+                ),
+                None => (format!("{ident} aborted"), format!("{reason} after {elapsed}")),
+            };
+            let shown = notify_rust::Notification::new()
+                .summary(&summary)
+                .body(&body)
+                .show();
+            if let Err(e) = shown {
+                tracing::warn!(calculation = ident, error = %e, "failed to show notification");
+            }
+        }
+
+        if let Some(hook) = self.hook.as_deref() {
+            let mut command = if cfg!(windows) {
+                let mut command = Command::new("cmd");
+                command.args(["/C", hook]);
+                command
+            } else {
+                let mut command = Command::new("sh");
+                command.args(["-c", hook]);
+                command
+            };
+            command
+                .env("TRELLIS_CALCULATION", ident)
+                .env("TRELLIS_REASON", reason)
+                .env(
+                    "TRELLIS_ELAPSED",
+                    timestamp.elapsed.as_secs_f64().to_string(),
+                );
+            if let Some((iteration, measure)) = outcome {
+                command
+                    .env("TRELLIS_ITERATION", iteration.to_string())
+                    .env("TRELLIS_MEASURE", measure.to_string());
+            }
+            let status = command.status();
+            match status {
+                Ok(status) if status.success() => {}
+                Ok(status) => {
+                    tracing::warn!(calculation = ident, %status, "notification hook failed");
+                }
+                Err(e) => {
+                    tracing::warn!(calculation = ident, error = %e, "failed to run notification hook");
+                }
+            }
+        }
+    }
+}
+
+impl<S: State> Observer<S> for NotifyOnExit {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        self.observe_at(ident, subject, stage, &self.clock.timestamp(stage));
+    }
+
+    fn observe_termination(
+        &self,
+        _ident: &'static str,
+        _subject: &S,
+        reason: Option<&Reason>,
+        _timestamp: &Timestamp,
+    ) {
+        *self.reason.borrow_mut() = reason.cloned();
+    }
+
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
+        match stage {
+            Stage::Finalisation => {
+                let reason = subject
+                    .termination_reason()
+                    .or_else(|| self.reason.borrow().clone())
+                    .map_or_else(|| "not terminated".to_owned(), |reason| reason.to_string());
+                let outcome = (subject.current_iteration(), subject.best_measure().real());
+                self.notify(ident, &reason, Some(outcome), timestamp);
+            }
+            Stage::Aborted => self.notify(ident, "panicked", None, timestamp),
+            Stage::Termination => {}
+            Stage::Initialisation | Stage::Iteration | Stage::Restart => {}
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use opentelemetry::{
+    global,
+    metrics::{Counter, Gauge, Meter},
+    KeyValue,
+};
+
+use crate::state::{Measure, State};
+use crate::watchers::{Observer, Stage};
+
+/// Records the progress of a run with OpenTelemetry metric instruments.
+///
+/// The runner already emits a span for the run and a child span for every iteration, carrying the
+/// iteration, measure and best measure as fields. Those reach OpenTelemetry through a
+/// `tracing-opentelemetry` layer; this observer adds the equivalent metric instruments.
+pub struct OtelMetrics {
+    iterations: Counter<u64>,
+    measure: Gauge<f64>,
+    best_measure: Gauge<f64>,
+}
+
+impl OtelMetrics {
+    /// Create instruments on the global meter provider
+    pub fn new() -> Self {
+        Self::from_meter(&global::meter("trellis"))
+    }
+
+    /// Create instruments on a user supplied meter
+    pub fn from_meter(meter: &Meter) -> Self {
+        Self {
+            iterations: meter
+                .u64_counter("trellis.iteration")
+                .with_description("Iterations completed")
+                .build(),
+            measure: meter
+                .f64_gauge("trellis.measure")
+                .with_description("Measure at the latest iteration")
+                .build(),
+            best_measure: meter
+                .f64_gauge("trellis.best_measure")
+                .with_description("Best measure found so far")
+                .build(),
+        }
+    }
+}
+
+impl Default for OtelMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: State> Observer<S> for OtelMetrics {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        if let Stage::Iteration = stage {
+            let attributes = [KeyValue::new("calculation", ident)];
+            self.iterations.add(1, &attributes);
+            self.measure.record(subject.measure().real(), &attributes);
+            self.best_measure
+                .record(subject.best_measure().real(), &attributes);
+        }
+    }
+}
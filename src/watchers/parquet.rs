@@ -0,0 +1,144 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+
+use crate::{
+    watchers::{FallbackClock, ObservationError, Observer, Stage},
+    Measure, State, Timestamp, KV,
+};
+
+/// One buffered iteration
+struct Record {
+    iteration: u64,
+    measure: f64,
+    best_measure: f64,
+    elapsed: f64,
+    timestamp: f64,
+    kv: Vec<(String, String)>,
+}
+
+/// Writes the trace of a run to a Parquet file when the run is finalised.
+///
+/// Every iteration becomes a row with columns for the iteration, measure, best measure, seconds
+/// elapsed since the run started and wall-clock time in seconds since the Unix epoch, followed by
+/// a string column for every key the state reported through [`State::kv`]. Keys missing from an
+/// iteration are null. The file loads directly with `polars.read_parquet` or
+/// `pandas.read_parquet`.
+pub struct ParquetWriter {
+    path: PathBuf,
+    records: RefCell<Vec<Record>>,
+    clock: FallbackClock,
+}
+
+impl ParquetWriter {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            records: RefCell::new(vec![]),
+            clock: FallbackClock::default(),
+        }
+    }
+
+    fn to_batch(&self) -> Result<RecordBatch, arrow_schema::ArrowError> {
+        let records = self.records.borrow();
+
+        // Columns for the values reported by the state, in the order keys were first seen
+        let mut keys: Vec<&str> = vec![];
+        for record in records.iter() {
+            for (key, _) in &record.kv {
+                if !keys.contains(&key.as_str()) {
+                    keys.push(key);
+                }
+            }
+        }
+
+        let mut fields = vec![
+            Field::new("iteration", DataType::UInt64, false),
+            Field::new("measure", DataType::Float64, false),
+            Field::new("best_measure", DataType::Float64, false),
+            Field::new("elapsed", DataType::Float64, false),
+            Field::new("timestamp", DataType::Float64, false),
+        ];
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from_iter_values(
+                records.iter().map(|r| r.iteration),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                records.iter().map(|r| r.measure),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                records.iter().map(|r| r.best_measure),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                records.iter().map(|r| r.elapsed),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                records.iter().map(|r| r.timestamp),
+            )),
+        ];
+        for key in keys {
+            fields.push(Field::new(key, DataType::Utf8, true));
+            columns.push(Arc::new(StringArray::from_iter(records.iter().map(|r| {
+                r.kv.iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, value)| value.as_str())
+            }))));
+        }
+
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+    }
+
+    fn write(&self) -> Result<(), ObservationError> {
+        let batch = self
+            .to_batch()
+            .map_err(|e| ObservationError::Writer(Box::new(e)))?;
+        let file =
+            std::fs::File::create(&self.path).map_err(|e| ObservationError::Writer(Box::new(e)))?;
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), None)
+            .map_err(|e| ObservationError::Writer(Box::new(e)))?;
+        writer
+            .write(&batch)
+            .map_err(|e| ObservationError::Writer(Box::new(e)))?;
+        writer
+            .close()
+            .map_err(|e| ObservationError::Writer(Box::new(e)))?;
+        Ok(())
+    }
+}
+
+impl<S: State> Observer<S> for ParquetWriter {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        self.observe_at(ident, subject, stage, &self.clock.timestamp(stage));
+    }
+
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
+        match stage {
+            Stage::Initialisation => self.records.borrow_mut().clear(),
+            Stage::Restart | Stage::Termination => {}
+            Stage::Iteration => self.records.borrow_mut().push(Record {
+                iteration: subject.current_iteration() as u64,
+                measure: subject.measure().real(),
+                best_measure: subject.best_measure().real(),
+                elapsed: timestamp.elapsed.as_secs_f64(),
+                timestamp: timestamp.unix_seconds(),
+                kv: KV::of(subject)
+                    .iter()
+                    .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                    .collect(),
+            }),
+            Stage::Finalisation | Stage::Aborted => {
+                if let Err(e) = self.write() {
+                    tracing::warn!(calculation = ident, error = %e, "failed to write trace");
+                }
+            }
+        }
+    }
+
+    fn output_path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+}
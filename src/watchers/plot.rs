@@ -1,7 +1,7 @@
-use crate::plotters::{PlotConfig, PlottableLine, Plotter};
-use crate::state::{State, TrellisFloat};
+use crate::plotters::{PlotConfig, PlottableHeatmap, PlottableLine, Plotter};
+use crate::state::{Measure, State};
 use crate::watchers::{ObservationError, Observer, Stage};
-use ndarray::{Array1, ArrayView1};
+use ndarray::{Array1, ArrayView1, ArrayView2};
 use std::cell::RefCell;
 use std::path::PathBuf;
 
@@ -9,7 +9,17 @@ use super::Target;
 
 pub struct PlotGenerator<R: PartialOrd> {
     plotter: RefCell<Plotter<R>>,
-    target: Target,
+    target: Plotted<R>,
+}
+
+/// What a [`PlotGenerator`] plots
+enum Plotted<R> {
+    Param,
+    Measure,
+    /// The field exposed by [`State::field`], over a second grid axis
+    Field {
+        nodes: Array1<R>,
+    },
 }
 
 struct Item<R> {
@@ -27,9 +37,29 @@ impl<'a, R> PlottableLine<'a, R> for Item<R> {
     }
 }
 
+struct FieldItem<'n, R> {
+    identifier: String,
+    nodes: ArrayView1<'n, R>,
+    field: ArrayView2<'n, R>,
+}
+
+impl<'a, 'n: 'a, R> PlottableHeatmap<'a, R> for FieldItem<'n, R> {
+    fn identifier(&'a self) -> &'a str {
+        &self.identifier
+    }
+
+    fn independent_variable(&'a self) -> ArrayView1<'a, R> {
+        self.nodes.view()
+    }
+
+    fn heatmap(&'a self) -> ArrayView2<'a, R> {
+        self.field.view()
+    }
+}
+
 impl<R> PlotGenerator<R>
 where
-    R: Clone + Default + PartialOrd + TrellisFloat + 'static,
+    R: Clone + Default + PartialOrd + Measure + 'static,
 {
     pub fn param(
         dir: PathBuf,
@@ -40,14 +70,40 @@ where
     ) -> Self {
         Self {
             plotter: Plotter::new(dir, identifier, config, Some(nodes)).into(),
-            target,
+            target: match target {
+                Target::Param => Plotted::Param,
+                Target::Measure => Plotted::Measure,
+            },
         }
     }
 
-    pub fn measure(dir: PathBuf, identifier: String, config: PlotConfig<R>) -> Self {
+    /// Plot the field exposed by [`State::field`] as a heatmap.
+    ///
+    /// The rows of the field lie on `x_nodes` and its columns on `y_nodes`. Each observation
+    /// replaces the previous heatmap, so attach the generator with
+    /// [`Frequency::Every`](crate::Frequency::Every) to follow the field through a run.
+    pub fn heatmap(
+        dir: PathBuf,
+        identifier: String,
+        config: PlotConfig<R>,
+        x_nodes: ArrayView1<'_, R>,
+        y_nodes: ArrayView1<'_, R>,
+    ) -> Self {
+        Self {
+            plotter: Plotter::new(dir, identifier, config, Some(x_nodes)).into(),
+            target: Plotted::Field {
+                nodes: y_nodes.to_owned(),
+            },
+        }
+    }
+
+    pub fn measure(dir: PathBuf, identifier: String, mut config: PlotConfig<R>) -> Self {
+        if let Some(unit) = R::unit() {
+            config.y_label = format!("{} [{}]", config.y_label, unit);
+        }
         Self {
             plotter: Plotter::new(dir, identifier, config, None).into(),
-            target: Target::Measure,
+            target: Plotted::Measure,
         }
     }
 }
@@ -56,15 +112,23 @@ impl<S: State, R> Observer<S> for PlotGenerator<R>
 where
     S: State<Float = R>,
     <S as State>::Param: Clone + Into<Array1<R>>,
-    R: Clone + Default + PartialOrd + TrellisFloat + 'static,
+    R: Clone + Default + PartialOrd + Measure + 'static,
 {
     fn observe(&self, _ident: &'static str, subject: &S, stage: Stage) {
         match stage {
             Stage::Iteration => self.observe_iteration(subject),
-            _ => Ok(()),
+            Stage::Finalisation | Stage::Aborted => {
+                self.plotter.borrow_mut().flush();
+                Ok(())
+            }
+            Stage::Initialisation | Stage::Restart | Stage::Termination => Ok(()),
         }
         .unwrap()
     }
+
+    fn output_path(&self) -> Option<PathBuf> {
+        Some(self.plotter.borrow().output_path().clone())
+    }
 }
 
 /// `WriteToFile` only implements `observer_iter` and not `observe_init` to avoid saving the
@@ -72,15 +136,15 @@ where
 /// state, otherwise it will skip saving silently.
 impl<R> PlotGenerator<R>
 where
-    R: Clone + Default + PartialOrd + TrellisFloat + 'static,
+    R: Clone + Default + PartialOrd + Measure + 'static,
 {
     fn observe_iteration<S>(&self, state: &S) -> Result<(), ObservationError>
     where
         S: State<Float = R>,
         <S as State>::Param: Clone + Into<Array1<R>>,
     {
-        match self.target {
-            Target::Param => {
+        match &self.target {
+            Plotted::Param => {
                 if let Some(param) = state.get_param() {
                     let iter = state.current_iteration();
                     let item = Item {
@@ -91,12 +155,23 @@ where
                     plotter.plot_line(&item).unwrap();
                 }
             }
-            Target::Measure => {
+            Plotted::Measure => {
                 let iteration = state.current_iteration();
                 let measure = state.measure();
                 let mut plotter = self.plotter.borrow_mut();
                 plotter.plot_point(iteration, measure).unwrap();
             }
+            Plotted::Field { nodes } => {
+                if let Some(field) = state.field() {
+                    let item = FieldItem {
+                        identifier: format!("{}", state.current_iteration()),
+                        nodes: nodes.view(),
+                        field,
+                    };
+                    let mut plotter = self.plotter.borrow_mut();
+                    plotter.plot_heatmap(&item).unwrap();
+                }
+            }
         }
         Ok(())
     }
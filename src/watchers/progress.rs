@@ -0,0 +1,76 @@
+use std::cell::Cell;
+
+use hifitime::{Duration, Epoch};
+use tokio::sync::watch;
+
+use crate::state::State;
+use crate::watchers::{Observer, Stage};
+
+/// A lightweight summary of the run, published after every iteration
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgressSnapshot<F> {
+    /// The current iteration
+    pub iteration: usize,
+    /// The measure at the current iteration, `None` before the first iteration completes
+    pub measure: Option<F>,
+    /// The best measure seen so far, `None` before the first iteration completes
+    pub best_measure: Option<F>,
+    /// Wall time since the runner was initialised
+    pub elapsed: Duration,
+}
+
+impl<F> Default for ProgressSnapshot<F> {
+    fn default() -> Self {
+        Self {
+            iteration: 0,
+            measure: None,
+            best_measure: None,
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+/// Observer which publishes a [`ProgressSnapshot`] into a watch channel
+pub(crate) struct ProgressPublisher<F> {
+    sender: watch::Sender<ProgressSnapshot<F>>,
+    start: Cell<Option<Epoch>>,
+}
+
+impl<F> ProgressPublisher<F> {
+    pub(crate) fn new() -> (Self, watch::Receiver<ProgressSnapshot<F>>) {
+        let (sender, receiver) = watch::channel(ProgressSnapshot::default());
+        (
+            Self {
+                sender,
+                start: Cell::new(None),
+            },
+            receiver,
+        )
+    }
+
+    fn elapsed(&self) -> Duration {
+        match (self.start.get(), Epoch::now().ok()) {
+            (Some(start), Some(now)) => now - start,
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+impl<F, S: State<Float = F>> Observer<S> for ProgressPublisher<F> {
+    fn observe(&self, _ident: &'static str, subject: &S, stage: Stage) {
+        match stage {
+            Stage::Initialisation => self.start.set(Epoch::now().ok()),
+            Stage::Iteration => {
+                // The send only fails when every receiver has been dropped, in which case nobody
+                // is listening and there is nothing to do
+                let _ = self.sender.send(ProgressSnapshot {
+                    iteration: subject.current_iteration(),
+                    measure: Some(subject.measure()),
+                    best_measure: Some(subject.best_measure()),
+                    elapsed: self.elapsed(),
+                });
+            }
+            Stage::Restart | Stage::Termination | Stage::Finalisation | Stage::Aborted => {}
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Process-wide default observers.
+//!
+//! Applications which want consistent telemetry across many call sites can register observer
+//! factories once at start-up. Every runner finalised afterwards for a matching state type
+//! attaches a fresh instance of each registered observer, unless the builder was marked
+//! `quiet` or the `TRELLIS_QUIET` environment variable is set.
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::watchers::{FrequencySet, Naming, Observer};
+
+/// Environment variable which, when set, suppresses all registered default observers
+pub const QUIET_ENV_VAR: &str = "TRELLIS_QUIET";
+
+type Factory<S> = Box<dyn Fn() -> (Arc<dyn Observer<S>>, Naming, FrequencySet) + Send + Sync>;
+
+type Registry = RwLock<HashMap<TypeId, Vec<Box<dyn Any + Send + Sync>>>>;
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Register an observer to be attached to every subsequently built runner over state `S`.
+///
+/// The factory is called once per runner, so each run receives its own observer instance.
+pub fn register_default_observer<S, OBS, F>(factory: F, frequency: impl Into<FrequencySet>)
+where
+    S: 'static,
+    OBS: Observer<S> + 'static,
+    F: Fn() -> OBS + Send + Sync + 'static,
+{
+    let frequency = frequency.into();
+    let factory: Factory<S> = Box::new(move || {
+        let observer: Arc<dyn Observer<S>> = Arc::new(factory());
+        (observer, Naming::of::<OBS>(), frequency)
+    });
+    registry()
+        .write()
+        .unwrap()
+        .entry(TypeId::of::<S>())
+        .or_default()
+        .push(Box::new(factory));
+}
+
+/// Remove all registered default observers
+pub fn clear_default_observers() {
+    registry().write().unwrap().clear();
+}
+
+/// The number of default observers which would be attached to a runner over state `S`
+pub(crate) fn default_observer_count<S: 'static>() -> usize {
+    if std::env::var_os(QUIET_ENV_VAR).is_some() {
+        return 0;
+    }
+    registry()
+        .read()
+        .unwrap()
+        .get(&TypeId::of::<S>())
+        .map_or(0, |factories| {
+            factories
+                .iter()
+                .filter(|factory| factory.is::<Factory<S>>())
+                .count()
+        })
+}
+
+/// Instantiate the default observers registered for state `S`
+#[allow(clippy::type_complexity)]
+pub(crate) fn default_observers<S: 'static>() -> Vec<(Arc<dyn Observer<S>>, Naming, FrequencySet)> {
+    if std::env::var_os(QUIET_ENV_VAR).is_some() {
+        return vec![];
+    }
+    registry()
+        .read()
+        .unwrap()
+        .get(&TypeId::of::<S>())
+        .map(|factories| {
+            factories
+                .iter()
+                .filter_map(|factory| factory.downcast_ref::<Factory<S>>())
+                .map(|factory| factory())
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,350 @@
+//! Streaming observations from remote runs back to a central process.
+//!
+//! A [`RemoteForwarder`] attached to a run on a worker serialises every observation as a line of
+//! JSON to any writer, such as a TCP stream or a pipe. On the coordinating process a
+//! [`RemoteDispatcher`] reads the lines back, rebuilds a [`RemoteState`] from each one and
+//! notifies its own observers, so the plotting and reporting observers used for local runs work
+//! unchanged for remote ones.
+//!
+//! A forwarder whose writer keeps failing suspends itself rather than failing on every
+//! observation, as configured by [`RemoteForwarder::with_autosuspend`], and retries the writer
+//! periodically. A forwarder built with [`RemoteForwarder::reconnect_with`] opens a fresh writer
+//! for each retry.
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+
+use hifitime::Duration;
+
+use crate::{
+    watchers::{
+        Attachment, Autosuspend, FallbackClock, FrequencySet, Naming, ObservationError, Observer,
+        ObserverVec, SinkHealth, Stage,
+    },
+    Measure, Reason, RunId, State, Timestamp, KV,
+};
+
+/// A single observation, as sent over the wire
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct RemoteObservation<F, P> {
+    ident: String,
+    stage: Stage,
+    timestamp: Timestamp,
+    state: RemoteState<F, P>,
+}
+
+/// The state of a remote run, rebuilt from a forwarded observation.
+///
+/// Only the values observers read are transferred. The parameters are included when the
+/// forwarder was built with [`RemoteForwarder::with_params`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemoteState<F, P> {
+    iteration: usize,
+    measure: F,
+    best_measure: F,
+    iterations_since_best: usize,
+    termination_reason: Option<Reason>,
+    run_id: Option<RunId>,
+    kv: KV,
+    param: Option<P>,
+}
+
+impl<F, P> RemoteState<F, P>
+where
+    F: Measure + Clone,
+{
+    fn capture<S>(state: &S, param: Option<P>) -> Self
+    where
+        S: State<Float = F>,
+    {
+        Self {
+            iteration: state.current_iteration(),
+            measure: state.measure(),
+            best_measure: state.best_measure(),
+            iterations_since_best: state.iterations_since_best(),
+            termination_reason: state.termination_reason(),
+            run_id: state.run_id().cloned(),
+            kv: KV::of(state),
+            param,
+        }
+    }
+}
+
+impl<F: Default, P> Default for RemoteState<F, P> {
+    fn default() -> Self {
+        Self {
+            iteration: 0,
+            measure: F::default(),
+            best_measure: F::default(),
+            iterations_since_best: 0,
+            termination_reason: None,
+            run_id: None,
+            kv: KV::default(),
+            param: None,
+        }
+    }
+}
+
+impl<F, P> State for RemoteState<F, P>
+where
+    F: Measure + Clone + Default,
+{
+    type Float = F;
+    type Param = P;
+    fn record_time(&mut self, _duration: Duration) {}
+    fn increment_iteration(&mut self) {
+        self.iteration += 1;
+    }
+    fn current_iteration(&self) -> usize {
+        self.iteration
+    }
+    fn update(self) -> Self {
+        self
+    }
+    fn is_initialised(&self) -> bool {
+        true
+    }
+    fn is_terminated(&self) -> bool {
+        self.termination_reason.is_some()
+    }
+    fn terminate_due_to(mut self, reason: Reason) -> Self {
+        self.termination_reason = Some(reason);
+        self
+    }
+    fn termination_reason(&self) -> Option<Reason> {
+        self.termination_reason.clone()
+    }
+    fn get_param(&self) -> Option<&Self::Param> {
+        self.param.as_ref()
+    }
+    fn measure(&self) -> Self::Float {
+        self.measure.clone()
+    }
+    fn best_measure(&self) -> Self::Float {
+        self.best_measure.clone()
+    }
+    fn iterations_since_best(&self) -> usize {
+        self.iterations_since_best
+    }
+    fn kv(&self) -> KV {
+        self.kv.clone()
+    }
+    fn run_id(&self) -> Option<&RunId> {
+        self.run_id.as_ref()
+    }
+}
+
+type ParamCapture<S, P> = Box<dyn Fn(&S) -> Option<P>>;
+type Reconnect<W> = Box<dyn Fn() -> std::io::Result<W>>;
+
+/// Serialises observations of a run as JSON lines, for a [`RemoteDispatcher`] to replay
+pub struct RemoteForwarder<W: Write, S: State, P = ()> {
+    writer: RefCell<W>,
+    param: Option<ParamCapture<S, P>>,
+    clock: FallbackClock,
+    reconnect: Option<Reconnect<W>>,
+    health: SinkHealth<String>,
+}
+
+const SINK: &str = "RemoteForwarder";
+
+impl<W: Write, S: State> RemoteForwarder<W, S, ()> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: RefCell::new(writer),
+            param: None,
+            clock: FallbackClock::default(),
+            reconnect: None,
+            health: SinkHealth::new(SINK, Autosuspend::default()),
+        }
+    }
+}
+
+impl<W: Write, S: State> RemoteForwarder<W, S, S::Param>
+where
+    S::Param: Clone,
+{
+    /// Forward the parameters along with the measures
+    pub fn with_params(writer: W) -> Self {
+        Self {
+            writer: RefCell::new(writer),
+            param: Some(Box::new(|state: &S| state.get_param().cloned())),
+            clock: FallbackClock::default(),
+            reconnect: None,
+            health: SinkHealth::new(SINK, Autosuspend::default()),
+        }
+    }
+}
+
+impl<W, S, P> RemoteForwarder<W, S, P>
+where
+    W: Write,
+    S: State,
+    S::Float: Clone,
+    P: Serialize,
+{
+    /// Set when the forwarder suspends itself after its writer fails, which by default is after
+    /// three consecutive failures
+    #[must_use]
+    pub fn with_autosuspend(mut self, autosuspend: Autosuspend) -> Self {
+        self.health.set_config(autosuspend);
+        self
+    }
+
+    /// Open a new writer with `connect` each time delivery is retried while suspended, rather
+    /// than retrying the writer which failed
+    #[must_use]
+    pub fn reconnect_with(mut self, connect: impl Fn() -> std::io::Result<W> + 'static) -> Self {
+        self.reconnect = Some(Box::new(connect));
+        self
+    }
+
+    /// Whether the forwarder has suspended itself after repeated failures
+    pub fn is_suspended(&self) -> bool {
+        self.health.is_suspended()
+    }
+
+    fn forward(
+        &self,
+        ident: &'static str,
+        state: &S,
+        stage: Stage,
+        timestamp: &Timestamp,
+    ) -> Result<(), ObservationError> {
+        let param = self.param.as_ref().and_then(|capture| capture(state));
+        let observation = RemoteObservation {
+            ident: ident.to_owned(),
+            stage,
+            timestamp: *timestamp,
+            state: RemoteState::capture(state, param),
+        };
+        let mut line = serde_json::to_string(&observation)
+            .map_err(|e| ObservationError::Writer(Box::new(e)))?;
+        line.push('\n');
+        self.health.deliver(
+            state.run_id(),
+            line,
+            |line| self.send(line),
+            || self.reconnect(),
+        );
+        Ok(())
+    }
+
+    fn send(&self, line: &str) -> std::io::Result<()> {
+        let mut writer = self.writer.borrow_mut();
+        writer.write_all(line.as_bytes())?;
+        writer.flush()
+    }
+
+    fn reconnect(&self) -> std::io::Result<()> {
+        if let Some(connect) = &self.reconnect {
+            *self.writer.borrow_mut() = connect()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W, S, P> Observer<S> for RemoteForwarder<W, S, P>
+where
+    W: Write,
+    S: State,
+    S::Float: Clone,
+    P: Serialize,
+{
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        self.observe_at(ident, subject, stage, &self.clock.timestamp(stage));
+    }
+
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
+        // A remote consumer going away must not bring down the run. Delivery failures are
+        // handled by the sink health, leaving only observations which could not be serialised
+        if let Err(e) = self.forward(ident, subject, stage, timestamp) {
+            tracing::warn!(calculation = ident, error = %e, "failed to forward observation");
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DispatchError {
+    #[error("error reading observations: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("malformed observation: {0}")]
+    Malformed(#[from] serde_json::Error),
+}
+
+/// Replays observations forwarded from remote runs to locally attached observers
+pub struct RemoteDispatcher<F, P = ()> {
+    observers: ObserverVec<RemoteState<F, P>>,
+    idents: HashSet<&'static str>,
+}
+
+impl<F, P> Default for RemoteDispatcher<F, P> {
+    fn default() -> Self {
+        Self {
+            observers: ObserverVec::default(),
+            idents: HashSet::new(),
+        }
+    }
+}
+
+impl<F, P> RemoteDispatcher<F, P>
+where
+    F: Measure + Clone + Default + DeserializeOwned,
+    P: DeserializeOwned,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn attach_observer<OBS: Observer<RemoteState<F, P>> + 'static>(
+        mut self,
+        observer: OBS,
+        frequency: impl Into<FrequencySet>,
+    ) -> Self {
+        self.observers.attach_with(
+            Arc::new(observer),
+            Naming::of::<OBS>(),
+            frequency.into(),
+            Attachment::Required,
+        );
+        self
+    }
+
+    /// Read observations until the end of `reader`, returning the number dispatched
+    pub fn dispatch_from<R: BufRead>(&mut self, reader: R) -> Result<usize, DispatchError> {
+        let mut dispatched = 0;
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let observation: RemoteObservation<F, P> = serde_json::from_str(&line)?;
+            let ident = self.intern(observation.ident);
+            // Observers see the time the observation was made on the worker
+            self.observers.notify(
+                ident,
+                &observation.state,
+                observation.stage,
+                &observation.timestamp,
+            );
+            dispatched += 1;
+        }
+        Ok(dispatched)
+    }
+
+    // Observers take the calculation name as a static string. Remote names are leaked once each,
+    // which is bounded by the number of distinct calculations
+    fn intern(&mut self, ident: String) -> &'static str {
+        match self.idents.get(ident.as_str()) {
+            Some(interned) => interned,
+            None => {
+                let interned: &'static str = Box::leak(ident.into_boxed_str());
+                self.idents.insert(interned);
+                interned
+            }
+        }
+    }
+}
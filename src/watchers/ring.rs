@@ -0,0 +1,125 @@
+use std::cell::{Cell, RefCell};
+use std::fs::{File, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    watchers::{FallbackClock, ObservationError, Observer, Stage},
+    Measure, State, Timestamp,
+};
+
+/// The length of every line of a ring file, including the newline
+const RECORD_LEN: usize = 128;
+
+/// Keeps the most recent observations in a file of fixed size, overwriting the oldest.
+///
+/// The file holds `capacity` lines of equal length, so however long the run it never grows, and
+/// a crashed or killed process leaves behind its last moments. Each line starts with a sequence
+/// number followed by the stage, iteration, measure, best measure and wall-clock time in seconds
+/// since the Unix epoch. Unused slots are blank. [`RingFile::read`] returns the lines in the
+/// order they were written.
+pub struct RingFile {
+    path: PathBuf,
+    capacity: usize,
+    file: RefCell<Option<File>>,
+    sequence: Cell<u64>,
+    clock: FallbackClock,
+}
+
+impl RingFile {
+    pub fn new(path: impl Into<PathBuf>, capacity: usize) -> Self {
+        Self {
+            path: path.into(),
+            capacity: capacity.max(1),
+            file: RefCell::new(None),
+            sequence: Cell::new(0),
+            clock: FallbackClock::default(),
+        }
+    }
+
+    /// The observations in a ring file, oldest first
+    pub fn read(path: impl AsRef<Path>) -> std::io::Result<Vec<String>> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut lines = contents
+            .lines()
+            .map(str::trim_end)
+            .filter_map(|line| {
+                let sequence = line.split_whitespace().next()?.parse::<u64>().ok()?;
+                Some((sequence, line.to_owned()))
+            })
+            .collect::<Vec<_>>();
+        lines.sort_by_key(|(sequence, _)| *sequence);
+        Ok(lines.into_iter().map(|(_, line)| line).collect())
+    }
+
+    // Create the file with every slot blank
+    fn create(&self) -> std::io::Result<File> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        let blank = format!("{:width$}\n", "", width = RECORD_LEN - 1);
+        for _ in 0..self.capacity {
+            file.write_all(blank.as_bytes())?;
+        }
+        Ok(file)
+    }
+
+    fn record<S: State>(
+        &self,
+        subject: &S,
+        stage: Stage,
+        timestamp: &Timestamp,
+    ) -> std::io::Result<()> {
+        let mut file = self.file.borrow_mut();
+        if stage == Stage::Initialisation || file.is_none() {
+            *file = Some(self.create()?);
+            self.sequence.set(0);
+        }
+        let file = file.as_mut().unwrap();
+
+        let sequence = self.sequence.get();
+        self.sequence.set(sequence + 1);
+        let mut line = format!(
+            "{sequence} {stage:?} {} {:e} {:e} {:.6}",
+            subject.current_iteration(),
+            subject.measure().real(),
+            subject.best_measure().real(),
+            timestamp.unix_seconds(),
+        );
+        line.truncate(RECORD_LEN - 1);
+        let line = format!("{line:width$}\n", width = RECORD_LEN - 1);
+
+        let slot = sequence % self.capacity as u64;
+        file.seek(SeekFrom::Start(slot * RECORD_LEN as u64))?;
+        file.write_all(line.as_bytes())
+    }
+}
+
+impl<S: State> Observer<S> for RingFile {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        self.observe_at(ident, subject, stage, &self.clock.timestamp(stage));
+    }
+
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
+        if let Err(e) = self.record(subject, stage, timestamp) {
+            tracing::warn!(calculation = ident, error = %e, "failed to write ring file");
+        }
+    }
+
+    fn start(&self) -> Result<(), ObservationError> {
+        self.create()
+            .map(|file| *self.file.borrow_mut() = Some(file))
+            .map_err(|e| {
+                ObservationError::Unavailable(format!(
+                    "cannot create ring file {}: {e}",
+                    self.path.display()
+                ))
+            })
+    }
+
+    fn output_path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+}
@@ -0,0 +1,113 @@
+use slog::{debug, info, o, trace, Level, Logger};
+
+use crate::state::{Measure, State};
+use crate::watchers::{Observer, Stage};
+use crate::{Reason, Timestamp};
+
+/// A logger using the [`slog`](https://crates.io/crates/slog) crate as backend.
+#[derive(Clone)]
+pub struct SlogLogger {
+    /// the logger
+    logger: Logger,
+    level: Level,
+}
+
+impl SlogLogger {
+    /// Attach a child logger to an existing application logger
+    pub fn new(parent: &Logger, level: Level) -> Self {
+        if matches!(level, Level::Critical | Level::Error | Level::Warning) {
+            panic!("we won't emit non-error messages at CRITICAL, ERROR or WARN...");
+        }
+        Self {
+            logger: parent.new(o!("component" => "trellis")),
+            level,
+        }
+    }
+}
+
+impl<S: State> Observer<S> for SlogLogger {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        match stage {
+            Stage::Initialisation => self.observe_stage("initialising", ident),
+            Stage::Finalisation => self.observe_stage("finalising", ident),
+            Stage::Aborted => self.observe_stage("aborted", ident),
+            Stage::Restart => self.observe_stage("restarting", ident),
+            Stage::Termination => {
+                self.observe_termination_reason(ident, subject.termination_reason().as_ref())
+            }
+            Stage::Iteration => self.observe_iteration(ident, subject),
+        }
+    }
+
+    fn observe_termination(
+        &self,
+        ident: &'static str,
+        _subject: &S,
+        reason: Option<&Reason>,
+        _timestamp: &Timestamp,
+    ) {
+        self.observe_termination_reason(ident, reason);
+    }
+}
+
+impl SlogLogger {
+    fn observe_termination_reason(&self, name: &str, reason: Option<&Reason>) {
+        let reason = reason.map_or_else(|| "unknown".to_owned(), ToString::to_string);
+        match self.level {
+            Level::Info => info!(self.logger, "terminated: {}", name; "reason" => %reason),
+            Level::Debug => debug!(self.logger, "terminated: {}", name; "reason" => %reason),
+            Level::Trace => trace!(self.logger, "terminated: {}", name; "reason" => %reason),
+            _ => unreachable!(
+                "constructor does not allow warn or error level events for non-error messages"
+            ),
+        }
+    }
+
+    fn observe_stage(&self, stage: &str, name: &str) {
+        match self.level {
+            Level::Info => info!(self.logger, "{}: {}", stage, name),
+            Level::Debug => debug!(self.logger, "{}: {}", stage, name),
+            Level::Trace => trace!(self.logger, "{}: {}", stage, name),
+            _ => unreachable!(
+                "constructor does not allow warn or error level events for non-error messages"
+            ),
+        }
+    }
+
+    fn observe_iteration<S: State>(&self, name: &'static str, state: &S) {
+        let iteration = state.current_iteration();
+        let best_measure = state.best_measure().real();
+        let measure = state.measure().real();
+        let since_best = state.iterations_since_best();
+        let unit = S::Float::unit();
+        match self.level {
+            Level::Info => info!(self.logger, "iteration";
+                "calculation" => name,
+                "iteration" => iteration,
+                "best_measure" => best_measure,
+                "measure" => measure,
+                "since_best" => since_best,
+                "unit" => unit.as_deref(),
+            ),
+            Level::Debug => debug!(self.logger, "iteration";
+                "calculation" => name,
+                "iteration" => iteration,
+                "best_measure" => best_measure,
+                "measure" => measure,
+                "since_best" => since_best,
+                "unit" => unit.as_deref(),
+            ),
+            Level::Trace => trace!(self.logger, "iteration";
+                "calculation" => name,
+                "iteration" => iteration,
+                "best_measure" => best_measure,
+                "measure" => measure,
+                "since_best" => since_best,
+                "unit" => unit.as_deref(),
+            ),
+            _ => unreachable!(
+                "constructor does not allow warn or error level events for non-error messages"
+            ),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Observing runs through a snapshot of their state.
+//!
+//! Observers are generic over the state of the calculation they watch, so one written for one
+//! calculation cannot be attached to another. A [`SnapshotObserver`] instead receives a
+//! [`Snapshot`] holding the values observers usually read, and is attached to a run of any
+//! calculation wrapped in a [`SnapshotAdapter`].
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::{
+    watchers::{FallbackClock, ObservationError, Observer, Stage},
+    Measure, Reason, RunId, State, Timestamp, KV,
+};
+
+/// The state of a run when it was observed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The run observed, if the state records it
+    pub run_id: Option<RunId>,
+    pub iteration: usize,
+    pub measure: f64,
+    pub best_measure: f64,
+    pub iterations_since_best: usize,
+    /// Why the run terminated, once it has
+    pub termination_reason: Option<Reason>,
+    /// Time since the run started
+    pub elapsed: Duration,
+    /// The parameters serialised as JSON, if the adapter was built with
+    /// [`SnapshotAdapter::with_params`]
+    pub param: Option<String>,
+    /// Additional values reported by the state
+    pub kv: KV,
+}
+
+impl Snapshot {
+    /// Take a snapshot of `state`, observed at `timestamp`, without its parameters
+    pub fn of<S: State>(state: &S, timestamp: &Timestamp) -> Self {
+        Self {
+            run_id: state.run_id().cloned(),
+            iteration: state.current_iteration(),
+            measure: state.measure().real(),
+            best_measure: state.best_measure().real(),
+            iterations_since_best: state.iterations_since_best(),
+            termination_reason: state.termination_reason(),
+            elapsed: timestamp.elapsed,
+            param: None,
+            kv: KV::of(state),
+        }
+    }
+}
+
+/// An observer of any calculation, notified with a [`Snapshot`] of the state
+pub trait SnapshotObserver {
+    fn observe_snapshot(&self, ident: &'static str, snapshot: &Snapshot, stage: Stage);
+
+    /// Check the observer's backend is usable, as [`Observer::start`]
+    fn start(&self) -> Result<(), ObservationError> {
+        Ok(())
+    }
+
+    /// Where the observer writes its output, if it writes to the filesystem
+    fn output_path(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Adapts a [`SnapshotObserver`] to observe runs over state `S`
+pub struct SnapshotAdapter<O, S> {
+    observer: O,
+    param: Option<fn(&S) -> Option<String>>,
+    clock: FallbackClock,
+}
+
+impl<O, S> SnapshotAdapter<O, S> {
+    pub fn new(observer: O) -> Self {
+        Self {
+            observer,
+            param: None,
+            clock: FallbackClock::default(),
+        }
+    }
+}
+
+#[cfg(feature = "writing")]
+impl<O, S> SnapshotAdapter<O, S>
+where
+    S: State,
+    S::Param: Serialize,
+{
+    /// Include the parameters in each snapshot, serialised as JSON
+    pub fn with_params(observer: O) -> Self {
+        Self {
+            observer,
+            param: Some(|state: &S| {
+                state
+                    .get_param()
+                    .and_then(|param| serde_json::to_string(param).ok())
+            }),
+            clock: FallbackClock::default(),
+        }
+    }
+}
+
+impl<O: SnapshotObserver, S: State> Observer<S> for SnapshotAdapter<O, S> {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        self.observe_at(ident, subject, stage, &self.clock.timestamp(stage));
+    }
+
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
+        let mut snapshot = Snapshot::of(subject, timestamp);
+        snapshot.param = self.param.and_then(|capture| capture(subject));
+        self.observer.observe_snapshot(ident, &snapshot, stage);
+    }
+
+    fn observe_termination(
+        &self,
+        ident: &'static str,
+        subject: &S,
+        reason: Option<&Reason>,
+        timestamp: &Timestamp,
+    ) {
+        let mut snapshot = Snapshot::of(subject, timestamp);
+        snapshot.param = self.param.and_then(|capture| capture(subject));
+        if let Some(reason) = reason {
+            snapshot.termination_reason = Some(reason.clone());
+        }
+        self.observer
+            .observe_snapshot(ident, &snapshot, Stage::Termination);
+    }
+
+    fn start(&self) -> Result<(), ObservationError> {
+        self.observer.start()
+    }
+
+    fn output_path(&self) -> Option<PathBuf> {
+        self.observer.output_path()
+    }
+}
@@ -0,0 +1,168 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+
+use plotters::prelude::*;
+
+use crate::{
+    watchers::{ObservationError, Observer, Stage},
+    Measure, State,
+};
+
+/// The image format of a static plot
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StaticPlotFormat {
+    Svg,
+    Png,
+}
+
+impl StaticPlotFormat {
+    fn extension(&self) -> &str {
+        match self {
+            Self::Svg => "svg",
+            Self::Png => "png",
+        }
+    }
+}
+
+/// Renders the convergence curve of a run to an SVG or PNG image when the run is finalised.
+///
+/// Unlike [`PlotGenerator`](crate::PlotGenerator) no browser is needed to view the output, which
+/// suits headless machines. The measure is plotted on a logarithmic axis.
+pub struct StaticPlotGenerator {
+    output_path: PathBuf,
+    format: StaticPlotFormat,
+    title: String,
+    points: RefCell<Vec<(f64, f64)>>,
+}
+
+impl StaticPlotGenerator {
+    /// Plot to `dir/identifier.svg` or `dir/identifier.png`
+    pub fn new(mut dir: PathBuf, identifier: &str, format: StaticPlotFormat) -> Self {
+        dir.push(format!("{identifier}.{}", format.extension()));
+        Self {
+            output_path: dir,
+            format,
+            title: identifier.to_owned(),
+            points: RefCell::new(vec![]),
+        }
+    }
+
+    #[must_use]
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    fn render(&self, y_label: &str) -> Result<(), ObservationError> {
+        let points = self.points.borrow();
+        if points.is_empty() {
+            return Ok(());
+        }
+        let result = match self.format {
+            StaticPlotFormat::Svg => self.draw(
+                SVGBackend::new(&self.output_path, (1000, 800)).into_drawing_area(),
+                &points,
+                y_label,
+            ),
+            StaticPlotFormat::Png => self.draw(
+                BitMapBackend::new(&self.output_path, (1000, 800)).into_drawing_area(),
+                &points,
+                y_label,
+            ),
+        };
+        result.map_err(|e| ObservationError::Writer(e.into()))
+    }
+
+    fn draw<DB: DrawingBackend>(
+        &self,
+        root: DrawingArea<DB, plotters::coord::Shift>,
+        points: &[(f64, f64)],
+        y_label: &str,
+    ) -> Result<(), String> {
+        let to_string = |e: DrawingAreaErrorKind<DB::ErrorType>| e.to_string();
+
+        // Zero and negative measures cannot be shown on a logarithmic axis
+        let positive = points
+            .iter()
+            .copied()
+            .filter(|(_, y)| y.is_finite() && *y > 0.0)
+            .collect::<Vec<_>>();
+        let x_max = points.iter().map(|(x, _)| *x).fold(1.0, f64::max);
+        let (y_min, y_max) = positive
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), (_, y)| {
+                (low.min(*y), high.max(*y))
+            });
+        let (y_min, y_max) = if y_min.is_finite() {
+            (y_min / 2.0, y_max * 2.0)
+        } else {
+            (1e-16, 1.0)
+        };
+
+        root.fill(&WHITE).map_err(to_string)?;
+        let mut chart = ChartBuilder::on(&root)
+            .caption(&self.title, ("sans-serif", 28))
+            .margin(20)
+            .x_label_area_size(50)
+            .y_label_area_size(80)
+            .build_cartesian_2d(0.0..x_max, (y_min..y_max).log_scale())
+            .map_err(to_string)?;
+        chart
+            .configure_mesh()
+            .x_desc("Iteration")
+            .y_desc(y_label)
+            .y_label_formatter(&|y| format!("{y:.0e}"))
+            .draw()
+            .map_err(to_string)?;
+        chart
+            .draw_series(LineSeries::new(positive.iter().copied(), &BLUE))
+            .map_err(to_string)?;
+        root.present().map_err(to_string)
+    }
+}
+
+impl<S: State> Observer<S> for StaticPlotGenerator {
+    fn start(&self) -> Result<(), ObservationError> {
+        let unavailable = |e: std::io::Error| {
+            ObservationError::Unavailable(format!("{}: {e}", self.output_path.display()))
+        };
+        if let Some(dir) = self.output_path.parent() {
+            std::fs::create_dir_all(dir).map_err(unavailable)?;
+        }
+        // The plot is only written once the run is finalised, so check it can be written now
+        let existed = self.output_path.exists();
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.output_path)
+            .map_err(unavailable)?;
+        if !existed {
+            std::fs::remove_file(&self.output_path).map_err(unavailable)?;
+        }
+        Ok(())
+    }
+
+    fn output_path(&self) -> Option<PathBuf> {
+        Some(self.output_path.clone())
+    }
+
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        match stage {
+            Stage::Initialisation => self.points.borrow_mut().clear(),
+            Stage::Restart | Stage::Termination => {}
+            Stage::Iteration => self
+                .points
+                .borrow_mut()
+                .push((subject.current_iteration() as f64, subject.measure().real())),
+            Stage::Finalisation | Stage::Aborted => {
+                let y_label = match S::Float::unit() {
+                    Some(unit) => format!("Measure [{unit}]"),
+                    None => "Measure".to_owned(),
+                };
+                if let Err(e) = self.render(&y_label) {
+                    tracing::warn!(calculation = ident, error = %e, "failed to render plot");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Suspending observers whose remote sink has stopped accepting deliveries.
+//!
+//! A network-backed observer delivers through a [`SinkHealth`], which counts consecutive
+//! failures. Once the sink has failed [`Autosuspend::after_failures`] times in a row the observer
+//! is suspended: observations are buffered or dropped according to the [`SuspendPolicy`], a
+//! single warning is logged, and delivery is retried at most once per
+//! [`Autosuspend::retry_every`]. When a retry succeeds the buffer is delivered and the outage is
+//! closed. Outages are recorded against the run and reported by the
+//! [`RunSummary`](crate::RunSummary) observer.
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::RunId;
+
+/// What a suspended observer does with the observations it cannot deliver
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SuspendPolicy {
+    /// Discard them
+    Drop,
+    /// Keep up to the given number, discarding the oldest, and deliver them once the sink
+    /// recovers
+    Buffer(usize),
+}
+
+/// When an observer suspends itself, and how it recovers
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Autosuspend {
+    failures: usize,
+    retry_every: Duration,
+    policy: SuspendPolicy,
+}
+
+impl Default for Autosuspend {
+    fn default() -> Self {
+        Self {
+            failures: 3,
+            retry_every: Duration::from_secs(5),
+            policy: SuspendPolicy::Buffer(1024),
+        }
+    }
+}
+
+impl Autosuspend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Suspend after `failures` consecutive failed deliveries, three by default
+    #[must_use]
+    pub fn after_failures(mut self, failures: usize) -> Self {
+        self.failures = failures.max(1);
+        self
+    }
+
+    /// Retry delivery at most once per `interval` while suspended, every five seconds by default
+    #[must_use]
+    pub fn retry_every(mut self, interval: Duration) -> Self {
+        self.retry_every = interval;
+        self
+    }
+
+    /// Set what happens to observations while suspended, by default the latest 1024 are buffered
+    #[must_use]
+    pub fn policy(mut self, policy: SuspendPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+/// A period during which an observer could not deliver to its sink
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Outage {
+    /// The observer whose sink failed
+    pub sink: &'static str,
+    /// When the observer was suspended, in seconds since the Unix epoch
+    pub started: f64,
+    /// When delivery resumed, if it did
+    pub ended: Option<f64>,
+    /// The observations discarded during the outage
+    pub dropped: usize,
+    /// The failure which caused the suspension
+    pub error: String,
+}
+
+type Outages = Arc<Mutex<Vec<Outage>>>;
+
+// The outages of each run, read by the run summary. Runs without an identifier share an entry.
+static OUTAGES: Mutex<BTreeMap<Option<String>, Vec<Outages>>> = Mutex::new(BTreeMap::new());
+
+/// The outages of the sinks observing `run_id`
+pub(crate) fn outages(run_id: Option<&RunId>) -> Vec<Outage> {
+    OUTAGES
+        .lock()
+        .unwrap()
+        .get(&run_id.map(ToString::to_string))
+        .into_iter()
+        .flatten()
+        .flat_map(|outages| outages.lock().unwrap().clone())
+        .collect()
+}
+
+/// Drop the outages recorded for `run_id`, once the run has finished
+pub(crate) fn forget_outages(run_id: &RunId) {
+    OUTAGES.lock().unwrap().remove(&Some(run_id.to_string()));
+}
+
+fn unix_seconds() -> f64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0.0, |since| since.as_secs_f64())
+}
+
+/// Tracks the health of a sink, suspending delivery while it is failing
+pub(crate) struct SinkHealth<T> {
+    sink: &'static str,
+    config: Autosuspend,
+    consecutive_failures: Cell<usize>,
+    /// When delivery was last retried, if the sink is suspended
+    suspended: Cell<Option<Instant>>,
+    buffer: RefCell<VecDeque<T>>,
+    outages: Outages,
+    registered: Cell<bool>,
+}
+
+impl<T> SinkHealth<T> {
+    pub(crate) fn new(sink: &'static str, config: Autosuspend) -> Self {
+        Self {
+            sink,
+            config,
+            consecutive_failures: Cell::new(0),
+            suspended: Cell::new(None),
+            buffer: RefCell::new(VecDeque::new()),
+            outages: Outages::default(),
+            registered: Cell::new(false),
+        }
+    }
+
+    pub(crate) fn set_config(&mut self, config: Autosuspend) {
+        self.config = config;
+    }
+
+    pub(crate) fn is_suspended(&self) -> bool {
+        self.suspended.get().is_some()
+    }
+
+    /// Deliver `item` with `send`, or hold it back while the sink is suspended.
+    ///
+    /// While suspended, `reconnect` is called before delivery is retried.
+    pub(crate) fn deliver<E: std::fmt::Display>(
+        &self,
+        run_id: Option<&RunId>,
+        item: T,
+        mut send: impl FnMut(&T) -> Result<(), E>,
+        reconnect: impl FnOnce() -> Result<(), E>,
+    ) {
+        if let Some(last_attempt) = self.suspended.get() {
+            if last_attempt.elapsed() < self.config.retry_every {
+                self.hold(item);
+                return;
+            }
+            self.suspended.set(Some(Instant::now()));
+            self.hold(item);
+            if reconnect().is_ok() && self.flush(&mut send) {
+                self.resume();
+            }
+            return;
+        }
+
+        match send(&item) {
+            Ok(()) => self.consecutive_failures.set(0),
+            Err(e) => {
+                let failures = self.consecutive_failures.get() + 1;
+                self.consecutive_failures.set(failures);
+                if failures < self.config.failures {
+                    tracing::warn!(sink = self.sink, error = %e, "failed to deliver observation");
+                    return;
+                }
+                tracing::warn!(
+                    sink = self.sink,
+                    error = %e,
+                    failures,
+                    "suspending observer after repeated delivery failures"
+                );
+                self.suspend(run_id, e.to_string());
+                self.hold(item);
+            }
+        }
+    }
+
+    fn suspend(&self, run_id: Option<&RunId>, error: String) {
+        self.suspended.set(Some(Instant::now()));
+        self.outages.lock().unwrap().push(Outage {
+            sink: self.sink,
+            started: unix_seconds(),
+            ended: None,
+            dropped: 0,
+            error,
+        });
+        if !self.registered.replace(true) {
+            let mut outages = OUTAGES.lock().unwrap();
+            let entry = outages.entry(run_id.map(ToString::to_string)).or_default();
+            // Runs without an identifier are never forgotten, so drop the outages of sinks which
+            // no longer exist as new ones arrive
+            entry.retain(|outages| Arc::strong_count(outages) > 1);
+            entry.push(self.outages.clone());
+        }
+    }
+
+    fn resume(&self) {
+        self.suspended.set(None);
+        self.consecutive_failures.set(0);
+        if let Some(outage) = self.outages.lock().unwrap().last_mut() {
+            outage.ended = Some(unix_seconds());
+            tracing::info!(
+                sink = self.sink,
+                outage = outage.ended.unwrap_or_default() - outage.started,
+                dropped = outage.dropped,
+                "resumed observer"
+            );
+        }
+    }
+
+    // Buffer an item which cannot be delivered, or drop it
+    fn hold(&self, item: T) {
+        let mut dropped = 0;
+        match self.config.policy {
+            SuspendPolicy::Drop => dropped = 1,
+            SuspendPolicy::Buffer(capacity) => {
+                let mut buffer = self.buffer.borrow_mut();
+                buffer.push_back(item);
+                while buffer.len() > capacity {
+                    buffer.pop_front();
+                    dropped += 1;
+                }
+            }
+        }
+        if dropped > 0 {
+            if let Some(outage) = self.outages.lock().unwrap().last_mut() {
+                outage.dropped += dropped;
+            }
+        }
+    }
+
+    // Deliver the buffer in order, returning whether all of it was delivered
+    fn flush<E>(&self, send: &mut impl FnMut(&T) -> Result<(), E>) -> bool {
+        let mut buffer = self.buffer.borrow_mut();
+        while let Some(item) = buffer.front() {
+            if send(item).is_err() {
+                return false;
+            }
+            buffer.pop_front();
+        }
+        true
+    }
+}
@@ -0,0 +1,279 @@
+use serde::Serialize;
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::{
+    artifacts,
+    watchers::{outages, FallbackClock, ObservationError, Observer, Outage, Stage},
+    writers::{write_atomic, CompressionTally},
+    Grade, Measure, Reason, Report, ReportFormat, RunId, State, Timestamp,
+};
+
+#[derive(Serialize)]
+struct Beat<'a> {
+    calculation: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_id: Option<&'a RunId>,
+    stage: Stage,
+    iteration: usize,
+    measure: f64,
+    best_measure: f64,
+    elapsed: f64,
+    timestamp: f64,
+}
+
+/// Replaces a small JSON file with the progress of the run on every observation.
+///
+/// The file holds the stage, iteration, measures and time of the latest observation, so a
+/// supervisor can tell a slow run from a dead one by the age of the file, and read where it got
+/// to without parsing a log.
+pub struct Heartbeat {
+    path: PathBuf,
+    clock: FallbackClock,
+}
+
+impl Heartbeat {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            clock: FallbackClock::default(),
+        }
+    }
+
+    fn beat<S: State>(
+        &self,
+        ident: &'static str,
+        subject: &S,
+        stage: Stage,
+        timestamp: &Timestamp,
+    ) -> Result<(), ObservationError> {
+        let beat = Beat {
+            calculation: ident,
+            run_id: subject.run_id(),
+            stage,
+            iteration: subject.current_iteration(),
+            measure: subject.measure().real(),
+            best_measure: subject.best_measure().real(),
+            elapsed: timestamp.elapsed.as_secs_f64(),
+            timestamp: timestamp.unix_seconds(),
+        };
+        let json = serde_json::to_vec(&beat).map_err(|e| ObservationError::Writer(Box::new(e)))?;
+        write_atomic(&self.path, false, |f| f.write_all(&json))
+            .map_err(|e| ObservationError::Writer(Box::new(e)))
+    }
+}
+
+impl<S: State> Observer<S> for Heartbeat {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        self.observe_at(ident, subject, stage, &self.clock.timestamp(stage));
+    }
+
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
+        if let Err(e) = self.beat(ident, subject, stage, timestamp) {
+            tracing::warn!(calculation = ident, error = %e, "failed to write heartbeat");
+        }
+    }
+
+    fn output_path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+}
+
+#[derive(Serialize)]
+struct Termination<'a> {
+    calculation: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_id: Option<&'a RunId>,
+    reason: Option<Reason>,
+    grade: Grade,
+    iteration: usize,
+    measure: f64,
+    best_measure: f64,
+    elapsed: f64,
+    timestamp: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compression: Option<CompressionSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    outages: Vec<Outage>,
+}
+
+#[derive(Serialize)]
+struct CompressionSummary {
+    #[serde(flatten)]
+    tally: CompressionTally,
+    ratio: f64,
+}
+
+/// Writes a record of why the run terminated and a Markdown report when the run is finalised.
+///
+/// `termination.json` holds the reason, grade, final iteration and measures, and `report.md`
+/// the [`Report`] of the run including its measure history, both in the given directory. The
+/// grade is assessed from the final state with [`Grade::from_state`], as the observer does not
+/// see the calculation's own assessment. Both files are recorded in the
+/// [artifact index](crate::artifacts) of the directory. If written files or checkpoints were
+/// compressed, the bytes saved, compression ratio and time spent compressing are included, as are
+/// the periods during which a remote observer was [suspended](crate::Autosuspend).
+pub struct RunSummary {
+    dir: PathBuf,
+    /// Files written by other observers, recorded in the artifact index when the run finishes
+    artifacts: Vec<PathBuf>,
+    history: RefCell<Vec<f64>>,
+    /// Why the run terminated, as given by the runner
+    reason: RefCell<Option<Reason>>,
+    clock: FallbackClock,
+}
+
+impl RunSummary {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            artifacts: vec![],
+            history: RefCell::new(vec![]),
+            reason: RefCell::new(None),
+            clock: FallbackClock::default(),
+        }
+    }
+
+    // Record a file written by another observer along with the summary
+    #[must_use]
+    pub(crate) fn recording(mut self, path: PathBuf) -> Self {
+        self.artifacts.push(path);
+        self
+    }
+
+    fn write<S: State>(
+        &self,
+        ident: &'static str,
+        subject: &S,
+        timestamp: &Timestamp,
+    ) -> Result<(), ObservationError> {
+        let tally = CompressionTally::of(subject.run_id());
+        let outages = outages(subject.run_id());
+        let reason = subject
+            .termination_reason()
+            .or_else(|| self.reason.borrow().clone());
+        let termination = Termination {
+            calculation: ident,
+            run_id: subject.run_id(),
+            reason: reason.clone(),
+            grade: Grade::from_state(subject),
+            iteration: subject.current_iteration(),
+            measure: subject.measure().real(),
+            best_measure: subject.best_measure().real(),
+            elapsed: timestamp.elapsed.as_secs_f64(),
+            timestamp: timestamp.unix_seconds(),
+            compression: tally.map(|tally| CompressionSummary {
+                tally,
+                ratio: tally.ratio(),
+            }),
+            outages: outages.clone(),
+        };
+        let json = serde_json::to_vec_pretty(&termination)
+            .map_err(|e| ObservationError::Writer(Box::new(e)))?;
+        write_atomic(&self.dir.join("termination.json"), true, |f| {
+            f.write_all(&json)
+        })
+        .map_err(|e| ObservationError::Writer(Box::new(e)))?;
+
+        let mut report = Report::from_state(
+            ident,
+            subject,
+            Some(timestamp.elapsed.as_secs_f64()),
+            &self.history.borrow(),
+        );
+        report.termination = reason;
+        if let Some(tally) = tally {
+            report.kv.push(
+                "compression",
+                format!(
+                    "{} files, {} to {} bytes, ratio {:.2}, {:.3} s",
+                    tally.files,
+                    tally.raw_bytes,
+                    tally.compressed_bytes,
+                    tally.ratio(),
+                    tally.time.as_secs_f64()
+                ),
+            );
+        }
+        for outage in &outages {
+            let duration = match outage.ended {
+                Some(ended) => format!("{:.1} s", ended - outage.started),
+                None => "unresolved".to_owned(),
+            };
+            report.kv.push(
+                "outage",
+                format!(
+                    "{} suspended, {duration}, {} dropped: {}",
+                    outage.sink, outage.dropped, outage.error
+                ),
+            );
+        }
+        let report = report.render(ReportFormat::Markdown);
+        write_atomic(&self.dir.join("report.md"), true, |f| {
+            f.write_all(report.as_bytes())
+        })
+        .map_err(|e| ObservationError::Writer(Box::new(e)))?;
+
+        let written = [
+            self.dir.join("termination.json"),
+            self.dir.join("report.md"),
+        ];
+        let run_id = subject.run_id().map(|run_id| run_id.as_str());
+        for path in written.iter().chain(&self.artifacts) {
+            artifacts::record(&self.dir, path, run_id)
+                .map_err(|e| ObservationError::Writer(Box::new(e)))?;
+        }
+        if let Some(run_id) = run_id {
+            artifacts::record_measure(&self.dir, run_id, subject.measure().real())
+                .map_err(|e| ObservationError::Writer(Box::new(e)))?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: State> Observer<S> for RunSummary {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        self.observe_at(ident, subject, stage, &self.clock.timestamp(stage));
+    }
+
+    fn observe_termination(
+        &self,
+        _ident: &'static str,
+        _subject: &S,
+        reason: Option<&Reason>,
+        _timestamp: &Timestamp,
+    ) {
+        *self.reason.borrow_mut() = reason.cloned();
+    }
+
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
+        match stage {
+            Stage::Initialisation => self.history.borrow_mut().clear(),
+            Stage::Iteration => self.history.borrow_mut().push(subject.measure().real()),
+            // The state handed over on abort is not the final state, so there is nothing to
+            // summarise
+            Stage::Restart | Stage::Termination | Stage::Aborted => {}
+            Stage::Finalisation => {
+                if let Err(e) = self.write(ident, subject, timestamp) {
+                    tracing::warn!(calculation = ident, error = %e, "failed to write run summary");
+                }
+            }
+        }
+    }
+
+    fn start(&self) -> Result<(), ObservationError> {
+        if self.dir.is_dir() {
+            Ok(())
+        } else {
+            Err(ObservationError::Unavailable(format!(
+                "summary directory {} does not exist",
+                self.dir.display()
+            )))
+        }
+    }
+
+    fn output_path(&self) -> Option<PathBuf> {
+        Some(self.dir.clone())
+    }
+}
@@ -1,13 +1,70 @@
-use tracing::{debug, info, trace, Level, Value};
+use std::cell::Cell;
+use std::time::Duration;
 
-use crate::state::State;
-use crate::watchers::{ObservationError, Observer, Stage};
+use tracing::{debug, info, trace, Level};
 
-/// A logger using the [`slog`](https://crates.io/crates/slog) crate as backend.
+use crate::state::{Measure, State};
+use crate::watchers::{FallbackClock, ObservationError, Observer, Stage};
+use crate::{Reason, Timestamp};
+
+/// Which fields the [`Tracer`] records on each iteration.
+///
+/// Every field is recorded by default. Times are in seconds, measured from the start of the run.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TracerConfig {
+    pub iteration: bool,
+    pub measure: bool,
+    pub best_measure: bool,
+    /// Iterations since the best measure last improved
+    pub since_best: bool,
+    /// The unit of the measure, if it has one
+    pub unit: bool,
+    /// Time since the run started
+    pub elapsed: bool,
+    /// Iterations per second over the run so far
+    pub rate: bool,
+    /// Time since the best measure last improved
+    pub time_since_best: bool,
+}
+
+impl Default for TracerConfig {
+    fn default() -> Self {
+        Self {
+            iteration: true,
+            measure: true,
+            best_measure: true,
+            since_best: true,
+            unit: true,
+            elapsed: true,
+            rate: true,
+            time_since_best: true,
+        }
+    }
+}
+
+impl TracerConfig {
+    /// Record the iteration counts and measures, leaving out the timing fields
+    pub fn measures_only() -> Self {
+        Self {
+            elapsed: false,
+            rate: false,
+            time_since_best: false,
+            ..Self::default()
+        }
+    }
+}
+
+/// A logger using the [`tracing`](https://crates.io/crates/tracing) crate as backend.
 #[derive(Clone)]
 pub struct Tracer {
     /// the logger
     level: Level,
+    config: TracerConfig,
+    /// When the best measure last improved, measured from the start of the run
+    last_improved: Cell<Option<Duration>>,
+    /// The last iteration observed, and when
+    last_observed: Cell<Option<(usize, Duration)>>,
+    clock: FallbackClock,
 }
 
 impl Tracer {
@@ -15,24 +72,88 @@ impl Tracer {
         if matches!(level, Level::ERROR | Level::WARN) {
             panic!("we won't emit non-error messages at ERROR or WARN...");
         }
-        Self { level }
+        Self {
+            level,
+            config: TracerConfig::default(),
+            last_improved: Cell::new(None),
+            last_observed: Cell::new(None),
+            clock: FallbackClock::default(),
+        }
+    }
+
+    /// Set which fields are recorded on each iteration
+    #[must_use]
+    pub fn with_config(mut self, config: TracerConfig) -> Self {
+        self.config = config;
+        self
     }
 }
 
 struct TracingState<I>(I);
 
-impl<F: tracing::Value, S: State<Float = F>> Observer<S> for Tracer {
+impl<S: State> Observer<S> for Tracer {
     fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        self.observe_at(ident, subject, stage, &self.clock.timestamp(stage));
+    }
+
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
         match stage {
-            Stage::Initialisation => self.observe_initialisation(ident),
+            Stage::Initialisation => {
+                self.last_improved.set(Some(timestamp.elapsed));
+                self.last_observed.set(None);
+                self.observe_initialisation(ident)
+            }
             Stage::Finalisation => self.observe_finalisation(ident),
-            Stage::Iteration => self.observe_iteration(subject),
+            Stage::Aborted => self.observe_abort(ident),
+            Stage::Restart => self.observe_restart(ident, subject),
+            Stage::Termination => {
+                self.observe_termination_reason(ident, subject.termination_reason().as_ref())
+            }
+            Stage::Iteration => self.observe_iteration(subject, timestamp),
         }
         .unwrap()
     }
+
+    fn observe_termination(
+        &self,
+        ident: &'static str,
+        _subject: &S,
+        reason: Option<&Reason>,
+        _timestamp: &Timestamp,
+    ) {
+        self.observe_termination_reason(ident, reason).unwrap()
+    }
+}
+
+/// The fields recorded for an iteration, `None` where the config leaves a field out
+struct Fields<'a> {
+    iteration: Option<usize>,
+    measure: Option<f64>,
+    best_measure: Option<f64>,
+    since_best: Option<usize>,
+    unit: Option<&'a str>,
+    elapsed: Option<f64>,
+    rate: Option<f64>,
+    time_since_best: Option<f64>,
 }
 
 impl Tracer {
+    // The tracer need not observe every iteration, so when the best measure improved since the
+    // last observed iteration the time of the improvement is interpolated between the two
+    fn record_improvement<S: State>(&self, state: &S, elapsed: Duration) {
+        let iteration = state.current_iteration();
+        let since_best = state.iterations_since_best();
+        match self.last_observed.replace(Some((iteration, elapsed))) {
+            _ if since_best == 0 => self.last_improved.set(Some(elapsed)),
+            Some((last, at)) if last < iteration && since_best < iteration - last => {
+                let fraction = (iteration - since_best - last) as f64 / (iteration - last) as f64;
+                self.last_improved
+                    .set(Some(at + elapsed.saturating_sub(at).mul_f64(fraction)));
+            }
+            _ => {}
+        }
+    }
+
     /// Log basic information about the optimization after initialization.
     fn observe_initialisation(&self, name: &str) -> Result<(), ObservationError> {
         match self.level {
@@ -48,9 +169,9 @@ impl Tracer {
 
     fn observe_finalisation(&self, name: &str) -> Result<(), ObservationError> {
         match self.level {
-            Level::INFO => info!("initialising: {}", name),
-            Level::DEBUG => debug!("initialising: {}", name),
-            Level::TRACE => trace!("initialising: {}", name),
+            Level::INFO => info!("finalising: {}", name),
+            Level::DEBUG => debug!("finalising: {}", name),
+            Level::TRACE => trace!("finalising: {}", name),
             _ => unreachable!(
                 "constructor does not allow warn or error level events for non-error messages"
             ),
@@ -58,29 +179,104 @@ impl Tracer {
         Ok(())
     }
 
-    fn observe_iteration<F, S>(&self, state: &S) -> Result<(), ObservationError>
-    where
-        S: State<Float = F>,
-        F: Value,
-    {
+    fn observe_termination_reason(
+        &self,
+        name: &str,
+        reason: Option<&Reason>,
+    ) -> Result<(), ObservationError> {
+        let reason = reason.map(ToString::to_string);
+        match self.level {
+            Level::INFO => info!(reason, "terminated: {}", name),
+            Level::DEBUG => debug!(reason, "terminated: {}", name),
+            Level::TRACE => trace!(reason, "terminated: {}", name),
+            _ => unreachable!(
+                "constructor does not allow warn or error level events for non-error messages"
+            ),
+        };
+        Ok(())
+    }
+
+    fn observe_restart<S: State>(&self, name: &str, state: &S) -> Result<(), ObservationError> {
+        let iteration = state.current_iteration();
+        let reason = state.termination_reason().map(|reason| reason.to_string());
+        match self.level {
+            Level::INFO => info!(iteration, reason, "restarting: {}", name),
+            Level::DEBUG => debug!(iteration, reason, "restarting: {}", name),
+            Level::TRACE => trace!(iteration, reason, "restarting: {}", name),
+            _ => unreachable!(
+                "constructor does not allow warn or error level events for non-error messages"
+            ),
+        };
+        Ok(())
+    }
+
+    fn observe_abort(&self, name: &str) -> Result<(), ObservationError> {
+        match self.level {
+            Level::INFO => info!("aborted: {}", name),
+            Level::DEBUG => debug!("aborted: {}", name),
+            Level::TRACE => trace!("aborted: {}", name),
+            _ => unreachable!(
+                "constructor does not allow warn or error level events for non-error messages"
+            ),
+        };
+        Ok(())
+    }
+
+    fn observe_iteration<S: State>(
+        &self,
+        state: &S,
+        timestamp: &Timestamp,
+    ) -> Result<(), ObservationError> {
+        let elapsed = timestamp.elapsed;
+        self.record_improvement(state, elapsed);
+        let config = &self.config;
+        let unit = config.unit.then(S::Float::unit).flatten();
+        let seconds = elapsed.as_secs_f64();
+        let f = Fields {
+            iteration: config.iteration.then(|| state.current_iteration()),
+            measure: config.measure.then(|| state.measure().real()),
+            best_measure: config.best_measure.then(|| state.best_measure().real()),
+            since_best: config.since_best.then(|| state.iterations_since_best()),
+            unit: unit.as_deref(),
+            elapsed: config.elapsed.then_some(seconds),
+            rate: (config.rate && seconds > 0.0)
+                .then(|| state.current_iteration() as f64 / seconds),
+            time_since_best: self
+                .last_improved
+                .get()
+                .filter(|_| config.time_since_best)
+                .map(|improved| elapsed.saturating_sub(improved).as_secs_f64()),
+        };
         match self.level {
             Level::INFO => info!(
-                iteration = state.current_iteration(),
-                best_measure = state.best_measure(),
-                measure = state.measure(),
-                since_best = state.iterations_since_best(),
+                iteration = f.iteration,
+                best_measure = f.best_measure,
+                measure = f.measure,
+                since_best = f.since_best,
+                unit = f.unit,
+                elapsed = f.elapsed,
+                rate = f.rate,
+                time_since_best = f.time_since_best,
             ),
             Level::DEBUG => debug!(
-                iteration = state.current_iteration(),
-                best_measure = state.best_measure(),
-                measure = state.measure(),
-                since_best = state.iterations_since_best(),
+                iteration = f.iteration,
+                best_measure = f.best_measure,
+                measure = f.measure,
+                since_best = f.since_best,
+                unit = f.unit,
+                elapsed = f.elapsed,
+                rate = f.rate,
+                time_since_best = f.time_since_best,
             ),
             Level::TRACE => trace!(
-                iteration = state.current_iteration(),
-                best_measure = state.best_measure(),
-                measure = state.measure(),
-                since_best = state.iterations_since_best(),
+                iteration = f.iteration,
+                best_measure = f.best_measure,
+                measure = f.measure,
+                since_best = f.since_best,
+                unit = f.unit,
+                elapsed = f.elapsed,
+                rate = f.rate,
+                time_since_best = f.time_since_best,
             ),
             _ => unreachable!(
                 "constructor does not allow warn or error level events for non-error messages"
@@ -0,0 +1,201 @@
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
+
+use crate::watchers::{FallbackClock, ObservationError, Observer, Stage};
+use crate::{Measure, Reason, RunId, State, Timestamp};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Outcome {
+    Completed,
+    Failed,
+}
+
+/// The summary posted to the webhook
+#[derive(Serialize)]
+struct Summary {
+    /// A one line description, which Slack and Teams incoming webhooks display as the message
+    text: String,
+    calculation: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_id: Option<RunId>,
+    outcome: Outcome,
+    reason: Option<String>,
+    iterations: usize,
+    best_measure: f64,
+    /// Seconds since the run started
+    duration: f64,
+}
+
+/// What the observer has seen of the run
+#[derive(Default)]
+struct Progress {
+    ident: Option<&'static str>,
+    run_id: Option<RunId>,
+    iteration: usize,
+    best_measure: f64,
+    elapsed: Duration,
+    /// Why the run terminated, as given by the runner
+    reason: Option<Reason>,
+    finished: bool,
+}
+
+/// Posts a JSON summary of the run to a URL when it completes or fails.
+///
+/// The summary holds the calculation, run identifier, outcome, termination reason, iterations,
+/// best measure and duration in seconds, along with a `text` field describing the run in one
+/// line, so Slack and Teams incoming webhooks show a readable message without any glue code.
+///
+/// A run which stops with an error is never finalised, so the summary is posted with the
+/// outcome `failed` when the observer is dropped having seen the run start but not finish. The
+/// iterations and measures are then those of the last iteration observed. Delivery is retried
+/// on failure, and an undeliverable summary is logged rather than failing the run.
+pub struct WebhookObserver {
+    url: String,
+    headers: Vec<(String, String)>,
+    timeout: Duration,
+    retries: usize,
+    progress: RefCell<Progress>,
+    started: Cell<bool>,
+    clock: FallbackClock,
+}
+
+impl WebhookObserver {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            headers: vec![],
+            timeout: Duration::from_secs(10),
+            retries: 2,
+            progress: RefCell::new(Progress::default()),
+            started: Cell::new(false),
+            clock: FallbackClock::default(),
+        }
+    }
+
+    /// Send a header with the request, such as an authorisation token
+    #[must_use]
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Give up on a request after `timeout`, ten seconds by default
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Retry a failed request up to `retries` times, twice by default
+    #[must_use]
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    fn post(&self, summary: &Summary) -> Result<(), ObservationError> {
+        let config = ureq::Agent::config_builder()
+            .timeout_global(Some(self.timeout))
+            .build();
+        let agent = ureq::Agent::new_with_config(config);
+        let mut last_error = None;
+        for _ in 0..=self.retries {
+            let request = self
+                .headers
+                .iter()
+                .fold(agent.post(&self.url), |request, (name, value)| {
+                    request.header(name, value)
+                });
+            match request.send_json(summary) {
+                Ok(_) => return Ok(()),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(ObservationError::Writer(Box::new(last_error.unwrap())))
+    }
+
+    fn send(&self, outcome: Outcome, reason: Option<String>) {
+        let progress = self.progress.borrow();
+        let ident = progress.ident.unwrap_or("calculation");
+        let duration = progress.elapsed.as_secs_f64();
+        let text = format!(
+            "{ident} {} after {} iterations in {duration:.1} s: {}, best measure {:.3e}",
+            match outcome {
+                Outcome::Completed => "completed",
+                Outcome::Failed => "failed",
+            },
+            progress.iteration,
+            reason.as_deref().unwrap_or("no termination reason"),
+            progress.best_measure
+        );
+        let summary = Summary {
+            text,
+            calculation: ident,
+            run_id: progress.run_id.clone(),
+            outcome,
+            reason,
+            iterations: progress.iteration,
+            best_measure: progress.best_measure,
+            duration,
+        };
+        if let Err(e) = self.post(&summary) {
+            tracing::warn!(calculation = ident, url = %self.url, error = %e, "failed to post run summary");
+        }
+    }
+}
+
+impl<S: State> Observer<S> for WebhookObserver {
+    fn observe(&self, ident: &'static str, subject: &S, stage: Stage) {
+        self.observe_at(ident, subject, stage, &self.clock.timestamp(stage));
+    }
+
+    fn observe_termination(
+        &self,
+        _ident: &'static str,
+        _subject: &S,
+        reason: Option<&Reason>,
+        _timestamp: &Timestamp,
+    ) {
+        self.progress.borrow_mut().reason = reason.cloned();
+    }
+
+    fn observe_at(&self, ident: &'static str, subject: &S, stage: Stage, timestamp: &Timestamp) {
+        {
+            let mut progress = self.progress.borrow_mut();
+            progress.ident = Some(ident);
+            progress.run_id = subject.run_id().cloned();
+            progress.elapsed = timestamp.elapsed;
+            // The state handed over on abort is a fresh one, so keep the last values seen
+            if stage != Stage::Aborted {
+                progress.iteration = subject.current_iteration();
+                progress.best_measure = subject.best_measure().real();
+            }
+        }
+        match stage {
+            Stage::Initialisation | Stage::Iteration => self.started.set(true),
+            Stage::Restart | Stage::Termination => {}
+            Stage::Finalisation => {
+                self.progress.borrow_mut().finished = true;
+                let reason = subject
+                    .termination_reason()
+                    .or_else(|| self.progress.borrow().reason.clone())
+                    .map(|reason| reason.to_string());
+                self.send(Outcome::Completed, reason);
+            }
+            Stage::Aborted => {
+                self.progress.borrow_mut().finished = true;
+                self.send(Outcome::Failed, Some("panicked".to_owned()));
+            }
+        }
+    }
+}
+
+impl Drop for WebhookObserver {
+    fn drop(&mut self) {
+        if self.started.get() && !self.progress.get_mut().finished {
+            self.send(Outcome::Failed, Some("error".to_owned()));
+        }
+    }
+}
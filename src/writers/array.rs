@@ -0,0 +1,156 @@
+//! Writing numeric array parameters without a serde wrapper.
+//!
+//! Parameters implementing [`ArrayParam`] are written as NumPy `.npy` files or as CSV with a
+//! shape comment, both of which keep the shape of the array. Implementations are provided for
+//! `ndarray` and `nalgebra` types behind the features of the same names.
+use std::fmt::Display;
+use std::io::Write;
+
+/// The file format of a written array
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ArrayFormat {
+    /// NumPy's binary format, readable with `numpy.load`
+    #[default]
+    Npy,
+    /// Comma separated values, preceded by a `# shape:` comment line
+    Csv,
+}
+
+impl ArrayFormat {
+    pub(crate) fn extension(&self) -> &str {
+        match self {
+            Self::Npy => "npy",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// Elements of an [`ArrayParam`]
+pub trait ArrayElement: Copy + Display {
+    /// The NumPy type descriptor of the element
+    const NPY_DESCR: &'static str;
+    fn write_le(self, out: &mut Vec<u8>);
+}
+
+impl ArrayElement for f64 {
+    const NPY_DESCR: &'static str = "<f8";
+    fn write_le(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl ArrayElement for f32 {
+    const NPY_DESCR: &'static str = "<f4";
+    fn write_le(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+/// A parameter which is a one or two dimensional numeric array
+pub trait ArrayParam {
+    type Element: ArrayElement;
+    /// The length of each axis
+    fn shape(&self) -> Vec<usize>;
+    /// The elements, with the last axis varying fastest
+    fn row_major(&self) -> Vec<Self::Element>;
+}
+
+#[cfg(feature = "ndarray")]
+impl<T: ArrayElement> ArrayParam for ndarray::Array1<T> {
+    type Element = T;
+    fn shape(&self) -> Vec<usize> {
+        vec![self.len()]
+    }
+    fn row_major(&self) -> Vec<T> {
+        self.iter().copied().collect()
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<T: ArrayElement> ArrayParam for ndarray::Array2<T> {
+    type Element = T;
+    fn shape(&self) -> Vec<usize> {
+        let (rows, columns) = self.dim();
+        vec![rows, columns]
+    }
+    // Iteration follows the logical order whatever the memory layout
+    fn row_major(&self) -> Vec<T> {
+        self.iter().copied().collect()
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<T: ArrayElement + nalgebra::Scalar> ArrayParam for nalgebra::DVector<T> {
+    type Element = T;
+    fn shape(&self) -> Vec<usize> {
+        vec![self.len()]
+    }
+    fn row_major(&self) -> Vec<T> {
+        self.iter().copied().collect()
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<T: ArrayElement + nalgebra::Scalar> ArrayParam for nalgebra::DMatrix<T> {
+    type Element = T;
+    fn shape(&self) -> Vec<usize> {
+        vec![self.nrows(), self.ncols()]
+    }
+    // nalgebra stores matrices column major
+    fn row_major(&self) -> Vec<T> {
+        self.row_iter()
+            .flat_map(|row| row.iter().copied().collect::<Vec<_>>())
+            .collect()
+    }
+}
+
+/// Encode an array in NumPy's `.npy` format, version 1.0
+pub(crate) fn to_npy<A: ArrayParam>(array: &A) -> Vec<u8> {
+    let shape = array.shape();
+    let shape = match shape.as_slice() {
+        [length] => format!("{length},"),
+        axes => axes
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': ({shape}), }}",
+        A::Element::NPY_DESCR
+    );
+    // The magic string, version and header length take ten bytes, and the header is padded so
+    // the data starts on a 64 byte boundary
+    let unpadded = 10 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    let mut out = b"\x93NUMPY\x01\x00".to_vec();
+    out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    out.extend_from_slice(header.as_bytes());
+    for element in array.row_major() {
+        element.write_le(&mut out);
+    }
+    out
+}
+
+/// Write an array as CSV, one row of a matrix or one element of a vector per line
+pub(crate) fn write_csv<A: ArrayParam, W: Write>(array: &A, mut out: W) -> std::io::Result<()> {
+    let shape = array.shape();
+    let shape_comment = shape
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("x");
+    writeln!(out, "# shape: {shape_comment}")?;
+    let columns = if shape.len() > 1 { shape[1] } else { 1 };
+    for row in array.row_major().chunks(columns.max(1)) {
+        let row = row
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(out, "{row}")?;
+    }
+    out.flush()
+}
@@ -0,0 +1,173 @@
+//! Compression of written files and checkpoints.
+//!
+//! The time spent compressing and the bytes saved are tallied for each run, and reported by the
+//! [`RunSummary`](crate::RunSummary) observer.
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::RunId;
+
+/// How written data is compressed
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Compression {
+    #[default]
+    None,
+    /// Gzip, readable with `zcat` or Python's `gzip` module
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Zstandard, which compresses faster and smaller than gzip
+    #[cfg(feature = "zstd")]
+    Zstd,
+    /// LZ4 frames, readable with the `lz4` tool, which trades ratio for very fast compression
+    #[cfg(feature = "lz4")]
+    Lz4,
+}
+
+impl Compression {
+    /// The suffix appended to the names of compressed files
+    pub(crate) fn suffix(&self) -> &str {
+        match self {
+            Self::None => "",
+            #[cfg(feature = "gzip")]
+            Self::Gzip => ".gz",
+            #[cfg(feature = "zstd")]
+            Self::Zstd => ".zst",
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => ".lz4",
+        }
+    }
+
+    /// The identifier recorded in checkpoint headers
+    pub(crate) fn tag(&self) -> u8 {
+        match self {
+            Self::None => 0,
+            #[cfg(feature = "gzip")]
+            Self::Gzip => 1,
+            #[cfg(feature = "zstd")]
+            Self::Zstd => 2,
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => 3,
+        }
+    }
+
+    /// The compression recorded by [`Compression::tag`], if this build supports it
+    pub(crate) fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(Self::None),
+            #[cfg(feature = "gzip")]
+            1 => Some(Self::Gzip),
+            #[cfg(feature = "zstd")]
+            2 => Some(Self::Zstd),
+            #[cfg(feature = "lz4")]
+            3 => Some(Self::Lz4),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn compress(&self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(bytes.to_vec()),
+            #[cfg(feature = "gzip")]
+            Self::Gzip => {
+                use std::io::Write;
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "zstd")]
+            Self::Zstd => zstd::encode_all(bytes, 0),
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => {
+                use std::io::Write;
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                encoder.write_all(bytes)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+
+    /// Compress `bytes`, adding the time taken and the bytes saved to the tally for `run_id`
+    pub(crate) fn compress_for(
+        &self,
+        run_id: Option<&str>,
+        bytes: &[u8],
+    ) -> std::io::Result<Vec<u8>> {
+        if *self == Self::None {
+            return Ok(bytes.to_vec());
+        }
+        let start = Instant::now();
+        let compressed = self.compress(bytes)?;
+        let mut tallies = TALLIES.lock().unwrap();
+        let tally = tallies.entry(run_id.map(str::to_owned)).or_default();
+        tally.files += 1;
+        tally.raw_bytes += bytes.len() as u64;
+        tally.compressed_bytes += compressed.len() as u64;
+        tally.time += start.elapsed();
+        Ok(compressed)
+    }
+
+    pub(crate) fn decompress(&self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(bytes.to_vec()),
+            #[cfg(feature = "gzip")]
+            Self::Gzip => {
+                use std::io::Read;
+                let mut out = Vec::new();
+                flate2::read::GzDecoder::new(bytes).read_to_end(&mut out)?;
+                Ok(out)
+            }
+            #[cfg(feature = "zstd")]
+            Self::Zstd => zstd::decode_all(bytes),
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => {
+                use std::io::Read;
+                let mut out = Vec::new();
+                lz4_flex::frame::FrameDecoder::new(bytes).read_to_end(&mut out)?;
+                Ok(out)
+            }
+        }
+    }
+}
+
+static TALLIES: Mutex<BTreeMap<Option<String>, CompressionTally>> = Mutex::new(BTreeMap::new());
+
+/// The data compressed for a run
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize)]
+pub(crate) struct CompressionTally {
+    /// The number of files and checkpoints compressed
+    pub(crate) files: u64,
+    pub(crate) raw_bytes: u64,
+    pub(crate) compressed_bytes: u64,
+    /// The time spent compressing
+    #[serde(serialize_with = "as_secs")]
+    pub(crate) time: Duration,
+}
+
+impl CompressionTally {
+    /// The data compressed for `run_id` so far, if any was
+    pub(crate) fn of(run_id: Option<&RunId>) -> Option<Self> {
+        TALLIES
+            .lock()
+            .unwrap()
+            .get(&run_id.map(ToString::to_string))
+            .copied()
+    }
+
+    /// Drop the tally of `run_id`, once the run has finished
+    pub(crate) fn forget(run_id: &RunId) {
+        TALLIES.lock().unwrap().remove(&Some(run_id.to_string()));
+    }
+
+    /// The uncompressed size over the compressed size
+    pub(crate) fn ratio(&self) -> f64 {
+        self.raw_bytes as f64 / self.compressed_bytes.max(1) as f64
+    }
+}
+
+fn as_secs<S: serde::Serializer>(time: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(time.as_secs_f64())
+}
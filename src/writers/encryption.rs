@@ -0,0 +1,91 @@
+//! Encryption of written payloads at rest with AES-256-GCM.
+//!
+//! An encrypted payload is a magic string, a random 96 bit nonce and the ciphertext with its
+//! authentication tag, so modification of the file is detected when it is decrypted.
+use std::fmt;
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+
+const MAGIC: &[u8; 8] = b"TRLSENC1";
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+    #[error("an encryption key must be 64 hexadecimal digits")]
+    MalformedKey,
+    #[error("environment variable {0} holding the encryption key is not set")]
+    MissingKey(String),
+    #[error("not an encrypted payload")]
+    NotEncrypted,
+    #[error("decryption failed, the key is wrong or the payload was modified")]
+    Decryption,
+}
+
+/// A 256 bit key for encrypting written payloads.
+///
+/// The key is never written alongside the data, and is redacted from `Debug` output.
+#[derive(Clone)]
+pub struct EncryptionKey(Key<Aes256Gcm>);
+
+impl EncryptionKey {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes.into())
+    }
+
+    /// Parse a key written as 64 hexadecimal digits
+    pub fn from_hex(hex: &str) -> Result<Self, EncryptionError> {
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(EncryptionError::MalformedKey);
+        }
+        let mut bytes = [0; 32];
+        for (byte, digits) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            let digits = std::str::from_utf8(digits).map_err(|_| EncryptionError::MalformedKey)?;
+            *byte = u8::from_str_radix(digits, 16).map_err(|_| EncryptionError::MalformedKey)?;
+        }
+        Ok(Self::from_bytes(bytes))
+    }
+
+    /// Read a key written as hexadecimal digits from an environment variable
+    pub fn from_env(var: &str) -> Result<Self, EncryptionError> {
+        let hex = std::env::var(var).map_err(|_| EncryptionError::MissingKey(var.to_owned()))?;
+        Self::from_hex(&hex)
+    }
+
+    /// Generate a random key
+    pub fn generate() -> Self {
+        Self(Aes256Gcm::generate_key(OsRng))
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+pub(crate) fn encrypt(key: &EncryptionKey, plaintext: &[u8]) -> Vec<u8> {
+    let nonce = Aes256Gcm::generate_nonce(OsRng);
+    let ciphertext = Aes256Gcm::new(&key.0)
+        .encrypt(&nonce, plaintext)
+        .expect("encrypting an in-memory buffer cannot fail");
+    let mut out = MAGIC.to_vec();
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    out
+}
+
+/// Decrypt a payload written with `key`, such as a parameter file written by an encrypting
+/// [`FileWriter`](crate::FileWriter)
+pub fn decrypt(key: &EncryptionKey, payload: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    if payload.len() < MAGIC.len() + NONCE_LEN || &payload[..MAGIC.len()] != MAGIC {
+        return Err(EncryptionError::NotEncrypted);
+    }
+    let (nonce, ciphertext) = payload[MAGIC.len()..].split_at(NONCE_LEN);
+    Aes256Gcm::new(&key.0)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| EncryptionError::Decryption)
+}
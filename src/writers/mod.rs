@@ -1,23 +1,78 @@
 //! Inner type for handling of data writing, storage and cleanup
 use fs_err::{File, OpenOptions};
 use serde::Serialize;
-use std::io::BufWriter;
-use std::path::PathBuf;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 use tempfile::{Builder, TempDir};
 
+use crate::{artifacts, RunId};
+
+mod array;
+mod compression;
+#[cfg(feature = "encryption")]
+mod encryption;
+pub use array::{ArrayElement, ArrayFormat, ArrayParam};
+pub use compression::Compression;
+pub(crate) use compression::CompressionTally;
+#[cfg(feature = "encryption")]
+pub(crate) use encryption::encrypt;
+#[cfg(feature = "encryption")]
+pub use encryption::{decrypt, EncryptionError, EncryptionKey};
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum WriteToFileSerializer {
     /// Use [`bincode`](https://crates.io/crates/bincode) for creating binary files
     Bincode,
     /// Use [`serde_json`](https://crates.io/crates/serde_json) for creating JSON files
     JSON,
+    /// Bincode compressed with gzip
+    #[cfg(feature = "gzip")]
+    BincodeGz,
+    /// JSON compressed with gzip
+    #[cfg(feature = "gzip")]
+    JsonGz,
+    /// Bincode compressed with zstd
+    #[cfg(feature = "zstd")]
+    BincodeZstd,
+    /// JSON compressed with zstd
+    #[cfg(feature = "zstd")]
+    JsonZstd,
 }
 
 impl WriteToFileSerializer {
-    fn extension(&self) -> &str {
+    fn format_extension(&self) -> &str {
+        if self.is_json() {
+            "json"
+        } else {
+            "bin"
+        }
+    }
+
+    fn is_json(&self) -> bool {
+        match self {
+            WriteToFileSerializer::Bincode => false,
+            WriteToFileSerializer::JSON => true,
+            #[cfg(feature = "gzip")]
+            WriteToFileSerializer::BincodeGz => false,
+            #[cfg(feature = "gzip")]
+            WriteToFileSerializer::JsonGz => true,
+            #[cfg(feature = "zstd")]
+            WriteToFileSerializer::BincodeZstd => false,
+            #[cfg(feature = "zstd")]
+            WriteToFileSerializer::JsonZstd => true,
+        }
+    }
+
+    /// How files written with this serializer are compressed
+    pub fn compression(&self) -> Compression {
         match self {
-            WriteToFileSerializer::Bincode => "bin",
-            WriteToFileSerializer::JSON => "json",
+            WriteToFileSerializer::Bincode | WriteToFileSerializer::JSON => Compression::None,
+            #[cfg(feature = "gzip")]
+            WriteToFileSerializer::BincodeGz | WriteToFileSerializer::JsonGz => Compression::Gzip,
+            #[cfg(feature = "zstd")]
+            WriteToFileSerializer::BincodeZstd | WriteToFileSerializer::JsonZstd => {
+                Compression::Zstd
+            }
         }
     }
 }
@@ -28,6 +83,60 @@ impl Default for WriteToFileSerializer {
     }
 }
 
+/// When written files are flushed to disk with `fsync`.
+///
+/// Every file is written to a temporary sibling and renamed into place, so an interrupted run
+/// never leaves a partially written file in place of an earlier result. Syncing additionally
+/// protects against losing the written data if the machine itself fails.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum FsyncPolicy {
+    /// Leave flushing to the operating system
+    Never,
+    /// Sync the final outputs when the writer is finalised
+    #[default]
+    Final,
+    /// Sync every file as it is written, which costs at least one disk flush per write
+    Always,
+}
+
+/// Write a file atomically, by writing to a temporary sibling and renaming it over `path`.
+///
+/// If `sync` is set the file is flushed to disk before the rename, and on Unix the directory is
+/// flushed after it, so the rename itself is durable.
+pub(crate) fn write_atomic<E, F>(path: &Path, sync: bool, write: F) -> Result<(), E>
+where
+    E: From<std::io::Error>,
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), E>,
+{
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+
+    let result = (|| {
+        let mut f = BufWriter::new(File::create(&partial)?);
+        write(&mut f)?;
+        let file = f.into_inner().map_err(|e| e.into_error())?;
+        if sync {
+            file.sync_all()?;
+        }
+        crate::ledger::record_bytes_written(file.metadata()?.len());
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs_err::remove_file(&partial);
+        return result;
+    }
+
+    fs_err::rename(&partial, path)?;
+    #[cfg(unix)]
+    if sync {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            File::open(parent)?.sync_all()?;
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum WriterError {
     #[error("Error in serde bincode {0}")]
@@ -38,6 +147,11 @@ pub enum WriterError {
     SerdeJson(#[from] serde_json::Error),
     #[error("Error in csv {0}")]
     Csv(#[from] csv::Error),
+    #[error("Error recording artifact {0}")]
+    Artifact(#[from] artifacts::ArtifactError),
+    #[cfg(feature = "encryption")]
+    #[error("Error in encryption {0}")]
+    Encryption(#[from] EncryptionError),
 }
 
 #[derive(Debug)]
@@ -50,6 +164,19 @@ pub struct Writer {
     tmp_dir: Option<TempDir>,
     /// Whether to preserve_history intermediate results after an iteration completes
     preserve_history: bool,
+    /// When written files are synced to disk
+    fsync_policy: FsyncPolicy,
+    /// When the measure trace is split into chunks, if it is
+    rotation: Option<Rotation>,
+    /// The chunks of the measure trace written so far
+    chunks: Vec<Chunk>,
+    /// How completed measure traces are compressed
+    trace_compression: Compression,
+    /// How parameter files are compressed
+    compression: Compression,
+    /// The key parameter files are encrypted with, if they are
+    #[cfg(feature = "encryption")]
+    encryption: Option<EncryptionKey>,
     /// Path to the latest written file
     last_modified: Option<PathBuf>,
     /// Name override
@@ -62,6 +189,9 @@ pub struct Writer {
     /// This field, if it exists, will override the identifier of any writeable written with the
     /// writer. It only makes sense to use this when the Writer is expected to be called once
     writeable_identifier: Option<String>,
+    /// Identifier of the run being written, appended to the output paths so concurrent runs
+    /// writing to the same directory do not overwrite each other
+    run_id: Option<String>,
 }
 
 pub trait Writeable {
@@ -82,6 +212,60 @@ struct Measure<F: Serialize> {
     measure: F,
 }
 
+/// When an iteration trace is split into a new chunk
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Rotation {
+    records: Option<usize>,
+    bytes: Option<u64>,
+}
+
+impl Rotation {
+    /// Start a new chunk after `records` records
+    pub fn records(records: usize) -> Self {
+        Self {
+            records: Some(records.max(1)),
+            bytes: None,
+        }
+    }
+
+    /// Start a new chunk once a chunk reaches `megabytes` megabytes
+    pub fn megabytes(megabytes: u64) -> Self {
+        Self {
+            records: None,
+            bytes: Some(megabytes.max(1) * 1_000_000),
+        }
+    }
+
+    /// Also start a new chunk after `records` records
+    #[must_use]
+    pub fn or_records(mut self, records: usize) -> Self {
+        self.records = Some(records.max(1));
+        self
+    }
+
+    /// Also start a new chunk once a chunk reaches `megabytes` megabytes
+    #[must_use]
+    pub fn or_megabytes(mut self, megabytes: u64) -> Self {
+        self.bytes = Some(megabytes.max(1) * 1_000_000);
+        self
+    }
+
+    fn is_due(&self, chunk: &Chunk) -> bool {
+        self.records.is_some_and(|records| chunk.records >= records)
+            || self.bytes.is_some_and(|bytes| chunk.bytes >= bytes)
+    }
+}
+
+/// One chunk of a rotated trace, as listed in the index file
+#[derive(Debug, Serialize)]
+struct Chunk {
+    file: String,
+    first_iteration: usize,
+    last_iteration: usize,
+    records: usize,
+    bytes: u64,
+}
+
 impl Writer {
     // Create a new writer, below `directory`
     pub(crate) fn new<P: Into<PathBuf>>(
@@ -102,15 +286,90 @@ impl Writer {
             identifier,
             directory,
             preserve_history: true,
+            fsync_policy: FsyncPolicy::default(),
+            rotation: None,
+            chunks: vec![],
+            trace_compression: Compression::None,
+            compression: Compression::None,
+            #[cfg(feature = "encryption")]
+            encryption: None,
             last_modified: None,
             writeable_identifier: None,
+            run_id: None,
         })
     }
 
+    pub(crate) fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub(crate) fn set_fsync_policy(&mut self, policy: FsyncPolicy) {
+        self.fsync_policy = policy;
+    }
+
+    pub(crate) fn rotate_every(&mut self, rotation: Rotation) {
+        self.rotation = Some(rotation);
+    }
+
+    #[cfg(feature = "encryption")]
+    pub(crate) fn encrypt_with(&mut self, key: EncryptionKey) {
+        self.encryption = Some(key);
+    }
+
+    // Whether parameter files are encrypted
+    fn is_sealed(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        return self.encryption.is_some();
+        #[cfg(not(feature = "encryption"))]
+        false
+    }
+
+    // Encrypt a parameter file if encryption is enabled
+    fn seal(&self, bytes: Vec<u8>) -> Vec<u8> {
+        #[cfg(feature = "encryption")]
+        if let Some(key) = self.encryption.as_ref() {
+            return encrypt(key, &bytes);
+        }
+        bytes
+    }
+
+    // The suffix marking encrypted files
+    fn sealed_suffix(&self) -> &str {
+        if self.is_sealed() {
+            ".enc"
+        } else {
+            ""
+        }
+    }
+
+    // Compress measure traces, and each chunk of a rotated trace, once they are complete
+    pub(crate) fn compress_traces(&mut self, compression: Compression) {
+        self.trace_compression = compression;
+    }
+
+    pub(crate) fn compress_params(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+
     pub(crate) fn with_writeable_identifier(&mut self, identifier: String) {
         self.writeable_identifier = Some(identifier);
     }
 
+    // Write the final output below a name including `run_id`
+    pub(crate) fn scope_to_run(&mut self, run_id: &RunId) {
+        if self.run_id.is_none() {
+            self.run_id = Some(run_id.to_string());
+        }
+    }
+
+    // The name of the final output, scoped to the run if known
+    fn output_stem(&self) -> String {
+        match self.run_id.as_ref() {
+            Some(run_id) => format!("{}-{run_id}", self.identifier),
+            None => self.identifier.clone(),
+        }
+    }
+
     // Write data to `tmp_dir`
     pub(crate) fn write<W>(
         &mut self,
@@ -122,22 +381,71 @@ impl Writer {
     {
         if let Some(tmp_dir) = self.tmp_dir.as_ref() {
             let fname = tmp_dir.path().join(format!(
-                "{}.{}",
+                "{}.{}{}{}",
                 self.writeable_identifier
                     .as_ref()
                     .map_or_else(|| writeable.identifier(), |identifier| identifier),
-                serializer.extension()
+                serializer.format_extension(),
+                self.compression.suffix(),
+                self.sealed_suffix()
             ));
-            let f = BufWriter::new(File::create(fname.clone())?);
-
-            match serializer {
-                WriteToFileSerializer::Bincode => {
-                    bincode::serialize_into(f, writeable.data())?;
+            let sync = self.fsync_policy == FsyncPolicy::Always;
+            write_atomic(&fname, sync, |f| {
+                if self.compression == Compression::None && !self.is_sealed() {
+                    if serializer.is_json() {
+                        serde_json::to_writer_pretty(f, writeable.data())?;
+                    } else {
+                        bincode::serialize_into(f, writeable.data())?;
+                    }
+                } else {
+                    let bytes = if serializer.is_json() {
+                        serde_json::to_vec_pretty(writeable.data())?
+                    } else {
+                        bincode::serialize(writeable.data())?
+                    };
+                    let bytes = self
+                        .compression
+                        .compress_for(self.run_id.as_deref(), &bytes)?;
+                    f.write_all(&self.seal(bytes))?;
                 }
-                WriteToFileSerializer::JSON => {
-                    serde_json::to_writer_pretty(f, writeable.data())?;
+                Ok::<_, WriterError>(())
+            })?;
+
+            // Update the last modified file
+            let _ = self.last_modified.replace(fname);
+
+            return Ok(());
+        }
+        panic!("tmp_dir not found");
+    }
+
+    // Write an array parameter to `tmp_dir`
+    pub(crate) fn write_array<A: ArrayParam>(
+        &mut self,
+        format: ArrayFormat,
+        identifier: &str,
+        array: &A,
+    ) -> Result<(), WriterError> {
+        if let Some(tmp_dir) = self.tmp_dir.as_ref() {
+            let fname = tmp_dir.path().join(format!(
+                "{}.{}{}{}",
+                self.writeable_identifier.as_deref().unwrap_or(identifier),
+                format.extension(),
+                self.compression.suffix(),
+                self.sealed_suffix()
+            ));
+            let sync = self.fsync_policy == FsyncPolicy::Always;
+            write_atomic(&fname, sync, |f| {
+                let mut bytes = vec![];
+                match format {
+                    ArrayFormat::Npy => bytes = array::to_npy(array),
+                    ArrayFormat::Csv => array::write_csv(array, &mut bytes)?,
                 }
-            }
+                let bytes = self
+                    .compression
+                    .compress_for(self.run_id.as_deref(), &bytes)?;
+                f.write_all(&self.seal(bytes))
+            })?;
 
             // Update the last modified file
             let _ = self.last_modified.replace(fname);
@@ -153,9 +461,31 @@ impl Writer {
         iteration: usize,
         measure: F,
     ) -> Result<(), WriterError> {
-        if let Some(tmp_dir) = self.tmp_dir.as_ref() {
-            let fname = tmp_dir.path().join("measure.csv");
+        if let Some(tmp_dir) = self.tmp_dir.as_ref().map(|dir| dir.path().to_owned()) {
+            let fname = match self.rotation {
+                None => tmp_dir.join("measure.csv"),
+                Some(rotation) => {
+                    if self
+                        .chunks
+                        .last()
+                        .is_none_or(|chunk| rotation.is_due(chunk))
+                    {
+                        self.compress_last_chunk(&tmp_dir)?;
+                        self.chunks.push(Chunk {
+                            file: format!("measure-{:05}.csv", self.chunks.len()),
+                            first_iteration: iteration,
+                            last_iteration: iteration,
+                            records: 0,
+                            bytes: 0,
+                        });
+                        self.write_index(&tmp_dir)?;
+                    }
+                    tmp_dir.join(&self.chunks.last().unwrap().file)
+                }
+            };
 
+            // Appending cannot be made atomic by renaming, but an interrupted append only loses
+            // the record being written
             let file = BufWriter::new(
                 OpenOptions::new()
                     .create(true)
@@ -175,6 +505,20 @@ impl Writer {
             };
 
             wtr.serialize(data)?;
+            if self.fsync_policy == FsyncPolicy::Always {
+                wtr.flush()?;
+                if let Ok(file) = wtr.into_inner() {
+                    file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+                }
+            } else {
+                drop(wtr);
+            }
+
+            if let Some(chunk) = self.chunks.last_mut() {
+                chunk.last_iteration = iteration;
+                chunk.records += 1;
+                chunk.bytes = fs_err::metadata(&fname)?.len();
+            }
 
             // Update the last modified file
             let _ = self.last_modified.replace(fname);
@@ -184,6 +528,57 @@ impl Writer {
         panic!("tmp_dir not found");
     }
 
+    // Replace a completed trace with its compressed form, returning the new path
+    fn compress_trace(&self, path: &Path) -> Result<PathBuf, WriterError> {
+        if self.trace_compression == Compression::None {
+            return Ok(path.to_owned());
+        }
+        let mut compressed = path.as_os_str().to_owned();
+        compressed.push(self.trace_compression.suffix());
+        let compressed = PathBuf::from(compressed);
+        let bytes = self
+            .trace_compression
+            .compress_for(self.run_id.as_deref(), &fs_err::read(path)?)?;
+        let sync = self.fsync_policy == FsyncPolicy::Always;
+        write_atomic(&compressed, sync, |f| f.write_all(&bytes))?;
+        fs_err::remove_file(path)?;
+        Ok(compressed)
+    }
+
+    // Compress the chunk of a rotated trace most recently written, which is complete
+    fn compress_last_chunk(&mut self, dir: &Path) -> Result<(), WriterError> {
+        if let Some(chunk) = self.chunks.last() {
+            let compressed = self.compress_trace(&dir.join(&chunk.file))?;
+            let file = compressed
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned();
+            if let Some(chunk) = self.chunks.last_mut() {
+                chunk.bytes = fs_err::metadata(&compressed)?.len();
+                chunk.file = file;
+            }
+            self.last_modified = Some(compressed);
+        }
+        Ok(())
+    }
+
+    // List the chunks of a rotated measure trace in `measure.index.csv`.
+    //
+    // The index is rewritten whenever a chunk is started, and when the writer is cleaned up, so
+    // only the entry for the chunk being written can be out of date.
+    fn write_index(&self, dir: &Path) -> Result<(), WriterError> {
+        let sync = self.fsync_policy == FsyncPolicy::Always;
+        write_atomic(&dir.join("measure.index.csv"), sync, |f| {
+            let mut wtr = csv::Writer::from_writer(f);
+            for chunk in &self.chunks {
+                wtr.serialize(chunk)?;
+            }
+            wtr.flush()?;
+            Ok::<_, WriterError>(())
+        })
+    }
+
     // Write data to `tmp_dir`
     pub(crate) fn write_records<R: Records>(
         &mut self,
@@ -192,16 +587,15 @@ impl Writer {
     ) -> Result<(), WriterError> {
         if let Some(tmp_dir) = self.tmp_dir.as_ref() {
             let fname = tmp_dir.path().join(format!("{}.csv", identifier));
-            let file = BufWriter::new(std::fs::File::create(fname.clone())?);
-
-            //let mut wtr = csv::Writer::from_writer(file);
-            let mut wtr = csv::WriterBuilder::new()
-                .has_headers(false)
-                .from_writer(file);
-
-            for record in records.records() {
-                wtr.serialize(record)?;
-            }
+            let sync = self.fsync_policy == FsyncPolicy::Always;
+            write_atomic(&fname, sync, |f| {
+                let mut wtr = csv::WriterBuilder::new().has_headers(false).from_writer(f);
+                for record in records.records() {
+                    wtr.serialize(record)?;
+                }
+                wtr.flush()?;
+                Ok::<_, WriterError>(())
+            })?;
 
             return Ok(());
         }
@@ -213,18 +607,34 @@ impl Writer {
     // After an iteration we get the converged result and move it to `directory`
     // Where this could be is currently not clear to me. What is a good pattern?
     fn cleanup(&mut self) -> Result<(), WriterError> {
+        let sync = self.fsync_policy != FsyncPolicy::Never;
+
+        if let Some(tmp_dir) = self.tmp_dir.as_ref().map(|dir| dir.path().to_owned()) {
+            if !self.chunks.is_empty() {
+                self.compress_last_chunk(&tmp_dir)?;
+                self.write_index(&tmp_dir)?;
+            } else if let Some(trace) = self
+                .last_modified
+                .clone()
+                .filter(|path| path.ends_with("measure.csv"))
+            {
+                self.last_modified = Some(self.compress_trace(&trace)?);
+            }
+        }
+
         // Move latest file to top level directory
         if let Some(last_modified) = self.last_modified.as_ref() {
             let mut new_location = self.directory.clone();
-            new_location.push(format!("{}.arp", self.identifier));
-            fs_err::copy(last_modified, new_location)?;
+            new_location.push(format!("{}.arp", self.output_stem()));
+            copy_atomic(last_modified, &new_location, sync)?;
+            artifacts::record(&self.directory, &new_location, self.run_id.as_deref())?;
         }
 
         if self.preserve_history {
             if let Some(tmp_dir) = self.tmp_dir.as_ref() {
                 // Delete sub directory
                 let mut perm_dir = self.directory.clone();
-                perm_dir.push(&self.identifier);
+                perm_dir.push(self.output_stem());
                 if !perm_dir.exists() {
                     fs_err::create_dir(&perm_dir)?;
                 }
@@ -235,7 +645,8 @@ impl Writer {
 
                     if let Some(file_name) = location.file_name() {
                         new_location.push(file_name);
-                        fs_err::copy(location, new_location)?;
+                        copy_atomic(&location, &new_location, sync)?;
+                        artifacts::record(&self.directory, &new_location, self.run_id.as_deref())?;
                     }
                 }
             }
@@ -254,6 +665,13 @@ impl Writer {
     }
 }
 
+fn copy_atomic(from: &Path, to: &Path, sync: bool) -> Result<(), WriterError> {
+    write_atomic(to, sync, |f| {
+        std::io::copy(&mut File::open(from)?, f)?;
+        Ok(())
+    })
+}
+
 impl Drop for Writer {
     fn drop(&mut self) {
         let _ = self.cleanup();
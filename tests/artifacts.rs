@@ -0,0 +1,81 @@
+#![cfg(feature = "writing")]
+use std::path::{Path, PathBuf};
+
+use trellis::artifacts::{gc, GcPolicy, RunIndex};
+use trellis::solvers::Bisection;
+use trellis::{Checkpointer, Frequency, GenerateBuilder, RunSummary, State};
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("trellis-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Run bisection for `iterations`, writing a checkpoint and a summary to the shared `dir`.
+///
+/// Returns the identifier of the run.
+fn run_into(dir: &Path, checkpoint: &str, iterations: usize) -> String {
+    let state = Bisection::new(|x: f64| x * x - 2.0)
+        .build_for(())
+        .configure(|state| state.bracket(0.0, 2.0).max_iterations(iterations))
+        .attach_observer(Checkpointer::new(dir.join(checkpoint)), Frequency::OnExit)
+        .attach_observer(RunSummary::new(dir), Frequency::Always)
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    state.run_id().unwrap().to_string()
+}
+
+#[test]
+fn gc_prunes_runs_sharing_a_directory_one_by_one() {
+    let root = scratch_dir("gc-shared");
+    let shared = root.join("shared");
+    std::fs::create_dir(&shared).unwrap();
+    let worse = run_into(&shared, "worse.ckpt", 3);
+    let better = run_into(&shared, "better.ckpt", 30);
+
+    let index = RunIndex::read(&shared).unwrap();
+    assert_eq!(index.measures.len(), 2);
+    assert!(index.measures[&better] < index.measures[&worse]);
+
+    let dry_run = gc(&root, &GcPolicy::new().keep_best(1).dry_run()).unwrap();
+    assert_eq!(dry_run.pruned.len(), 1);
+    assert!(shared.join("worse.ckpt").is_file());
+
+    let report = gc(&root, &GcPolicy::new().keep_best(1)).unwrap();
+    assert_eq!(report.pruned.len(), 1);
+    assert_eq!(report.pruned[0].run_id.as_deref(), Some(worse.as_str()));
+    assert_eq!(report.kept.len(), 1);
+    assert_eq!(report.kept[0].run_id.as_deref(), Some(better.as_str()));
+    assert!(!shared.join("worse.ckpt").exists());
+    assert!(shared.join("better.ckpt").is_file());
+    assert!(shared.join("termination.json").is_file());
+
+    let index = RunIndex::read(&shared).unwrap();
+    assert!(index
+        .artifacts
+        .values()
+        .all(|artifact| artifact.run_id.as_deref() == Some(better.as_str())));
+    assert!(!index.measures.contains_key(&worse));
+    assert_eq!(trellis::artifacts::verify(&shared).unwrap(), vec![]);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn gc_deletes_a_directory_once_every_run_in_it_is_pruned() {
+    let root = scratch_dir("gc-emptied");
+    let shared = root.join("shared");
+    std::fs::create_dir(&shared).unwrap();
+    run_into(&shared, "first.ckpt", 3);
+    run_into(&shared, "second.ckpt", 5);
+
+    let report = gc(&root, &GcPolicy::new().keep_best(0)).unwrap();
+    assert_eq!(report.pruned.len(), 2);
+    assert!(report.kept.is_empty());
+    assert!(!shared.exists());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
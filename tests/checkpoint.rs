@@ -0,0 +1,114 @@
+#![cfg(feature = "writing")]
+use std::path::PathBuf;
+
+use trellis::solvers::{Bisection, BracketState, Brent};
+use trellis::{
+    CheckpointError, Checkpointer, Frequency, GenerateBuilder, MetadataError, Reason, State,
+};
+
+fn square_minus_two(x: f64) -> f64 {
+    x * x - 2.0
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("trellis-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Run bisection for a few iterations, checkpointing every one of them
+fn checkpointed_bisection(path: &std::path::Path) -> BracketState {
+    Bisection::new(square_minus_two)
+        .build_for(())
+        .configure(|state| state.bracket(0.0, 2.0).max_iterations(5))
+        .attach_checkpointer(Checkpointer::new(path), Frequency::Always)
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap()
+}
+
+#[test]
+fn a_checkpoint_resumes_with_the_calculation_which_wrote_it() {
+    let dir = scratch_dir("checkpoint-resume");
+    let path = dir.join("bisection.ckpt");
+    let partial = checkpointed_bisection(&path);
+
+    let state = Bisection::new(square_minus_two)
+        .build_for(())
+        .resume_from_checkpoint(&path)
+        .unwrap()
+        .configure(|state| state.max_iterations(200))
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(state.termination_reason(), Some(Reason::Converged));
+    assert!(state.current_iteration() > partial.current_iteration());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn a_checkpoint_written_by_another_calculation_is_rejected() {
+    let dir = scratch_dir("checkpoint-mismatch");
+    let path = dir.join("bisection.ckpt");
+    checkpointed_bisection(&path);
+
+    let result = Brent::new(square_minus_two)
+        .build_for(())
+        .resume_from_checkpoint(&path);
+    assert!(matches!(
+        result.err(),
+        Some(CheckpointError::Incompatible(
+            MetadataError::CalculationMismatch { .. }
+        ))
+    ));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn a_checkpoint_records_the_calculation_it_was_given() {
+    let dir = scratch_dir("checkpoint-given");
+    let path = dir.join("bisection.ckpt");
+    Bisection::new(square_minus_two)
+        .build_for(())
+        .configure(|state| state.bracket(0.0, 2.0).max_iterations(5))
+        .attach_observer(
+            Checkpointer::new(&path).for_calculation("another calculation", "1.0.0"),
+            Frequency::Always,
+        )
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+
+    let result = Bisection::new(square_minus_two)
+        .build_for(())
+        .resume_from_checkpoint(&path);
+    assert!(matches!(
+        result.err(),
+        Some(CheckpointError::Incompatible(
+            MetadataError::CalculationMismatch { .. }
+        ))
+    ));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn a_state_from_another_calculation_cannot_warm_start() {
+    let partial = Bisection::new(square_minus_two)
+        .build_for(())
+        .configure(|state| state.bracket(0.0, 2.0).max_iterations(5))
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+
+    let result = Brent::new(square_minus_two)
+        .build_for(())
+        .warm_start(partial);
+    assert!(matches!(
+        result.err(),
+        Some(MetadataError::CalculationMismatch { .. })
+    ));
+}
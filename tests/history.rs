@@ -0,0 +1,36 @@
+use trellis::solvers::Bisection;
+use trellis::{GenerateBuilder, State};
+
+fn square_minus_two(x: f64) -> f64 {
+    x * x - 2.0
+}
+
+#[test]
+fn history_is_only_recorded_when_enabled() {
+    let output = Bisection::new(square_minus_two)
+        .build_for(())
+        .configure(|state| state.bracket(0.0, 2.0))
+        .finalise()
+        .unwrap()
+        .run_to_output()
+        .unwrap();
+    assert!(output.history().is_empty());
+    assert!(output.timestamps().is_empty());
+    assert!(output.convergence_report().is_none());
+}
+
+#[test]
+fn history_keeps_the_most_recent_iterations() {
+    let output = Bisection::new(square_minus_two)
+        .build_for(())
+        .configure(|state| state.bracket(0.0, 2.0))
+        .record_history(5)
+        .finalise()
+        .unwrap()
+        .run_to_output()
+        .unwrap();
+    assert!(output.state.current_iteration() > 5);
+    assert_eq!(output.history().len(), 5);
+    assert_eq!(output.timestamps().len(), 5);
+    assert_eq!(output.history()[4], output.state.measure());
+}
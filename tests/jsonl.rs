@@ -0,0 +1,41 @@
+#![cfg(feature = "writing")]
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use trellis::solvers::Bisection;
+use trellis::{Frequency, GenerateBuilder, JsonLinesLogger, Reason, State};
+
+/// A sink which rejects every write
+struct Unwritable;
+
+impl Write for Unwritable {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::other("disk full"))
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Err(std::io::Error::other("disk full"))
+    }
+}
+
+#[test]
+fn a_failing_writer_does_not_stop_the_run() {
+    let state = Bisection::new(|x: f64| x * x - 2.0)
+        .build_for(())
+        .configure(|state| state.bracket(0.0, 2.0))
+        .attach_observer(JsonLinesLogger::new(Unwritable), Frequency::Always)
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(state.termination_reason(), Some(Reason::Converged));
+}
+
+#[test]
+fn a_field_which_is_not_json_is_rejected() {
+    let label = JsonLinesLogger::new(Vec::new()).with_field("label", "baseline");
+    assert!(label.is_ok());
+
+    let keyed_by_pairs = BTreeMap::from([((1, 2), "a")]);
+    let result = JsonLinesLogger::new(Vec::new()).with_field("pairs", keyed_by_pairs);
+    assert!(result.is_err());
+}
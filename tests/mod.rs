@@ -1,7 +1,10 @@
+#![cfg(all(feature = "plotting", feature = "writing"))]
+
 use std::path::PathBuf;
 
 use hifitime::Duration;
 use trellis::prelude::*;
+use trellis::RenderMode;
 
 struct DummyCalculation {}
 
@@ -18,13 +21,11 @@ struct DummyState {
     param: Option<Vec<f64>>,
 }
 
-impl State for DummyState {
-    type Float = f64;
-    type Param = Vec<f64>;
-    fn new() -> Self {
+impl Default for DummyState {
+    fn default() -> Self {
         Self {
-            cost: std::f64::MAX,
-            best_cost: std::f64::MAX,
+            cost: f64::MAX,
+            best_cost: f64::MAX,
             param: None,
             time_elapsed: None,
             iteration: 0,
@@ -33,6 +34,11 @@ impl State for DummyState {
             termination_status: Status::NotTerminated,
         }
     }
+}
+
+impl State for DummyState {
+    type Float = f64;
+    type Param = Vec<f64>;
 
     fn record_time(&mut self, duration: Duration) {
         self.time_elapsed = Some(duration);
@@ -46,11 +52,12 @@ impl State for DummyState {
         self.iteration
     }
 
-    fn update(&mut self) {
+    fn update(mut self) -> Self {
         if self.best_cost > self.cost {
             self.best_cost = self.cost;
             self.best_cost_iteration = self.iteration;
         }
+        self
     }
 
     fn measure(&self) -> Self::Float {
@@ -84,6 +91,7 @@ impl State for DummyState {
 }
 
 #[derive(Debug)]
+#[allow(dead_code)]
 enum DummyError {
     TypeA,
 }
@@ -148,6 +156,7 @@ fn problems_run_successfully() {
         x_label: "Iteration".into(),
         y_label: "Measure".into(),
         title: "Optimisation Progress".into(),
+        render: RenderMode::Buffered { every: None },
     };
 
     let runner = calculation
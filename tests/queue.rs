@@ -0,0 +1,193 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use trellis::{QueueError, RunQueue};
+
+#[test]
+fn a_panicking_run_fails_without_disturbing_the_others() {
+    let mut queue = RunQueue::<usize, String>::new(2);
+    queue.push(|_| Ok(1));
+    queue.push(|_| panic!("diverged"));
+    queue.push(|_| Err("no bracket".to_owned()));
+    queue.push(|_| Ok(4));
+    let progress = queue.progress_handle();
+
+    let results = queue.run();
+    assert!(matches!(results[0], Some(Ok(1))));
+    assert!(matches!(
+        &results[1],
+        Some(Err(QueueError::Panicked(message))) if message == "diverged"
+    ));
+    assert!(matches!(
+        &results[2],
+        Some(Err(QueueError::Run(message))) if message == "no bracket"
+    ));
+    assert!(matches!(results[3], Some(Ok(4))));
+
+    let progress = *progress.lock().unwrap();
+    assert_eq!(progress.succeeded, 2);
+    assert_eq!(progress.failed, 2);
+}
+
+#[test]
+fn a_preemptible_run_makes_way_for_a_higher_priority_run() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut queue = RunQueue::<&'static str, String>::new(1);
+    let submitter = queue.submitter();
+    let progress = queue.progress_handle();
+
+    let low_log = log.clone();
+    queue.push_preemptible(0, move |_, preemption| {
+        low_log
+            .lock()
+            .unwrap()
+            .push(format!("low {}", preemption.resumes()));
+        if preemption.resumes() > 0 {
+            return Ok("low");
+        }
+        let high_log = low_log.clone();
+        assert!(submitter.push_with_priority(10, move |_| {
+            high_log.lock().unwrap().push("high".to_owned());
+            Ok("high")
+        }));
+        let started = Instant::now();
+        while !preemption.is_preempted() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "never preempted"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        preemption.mark_stopped();
+        Ok("preempted")
+    });
+
+    let results = queue.run();
+    assert!(matches!(results[0], Some(Ok("low"))));
+    assert!(matches!(results[1], Some(Ok("high"))));
+    assert_eq!(*log.lock().unwrap(), ["low 0", "high", "low 1"]);
+
+    let progress = *progress.lock().unwrap();
+    assert_eq!(progress.started, 2);
+    assert_eq!(progress.succeeded, 2);
+    assert_eq!(progress.preempted, 1);
+}
+
+#[test]
+fn a_preempted_run_which_finishes_anyway_keeps_its_result() {
+    let starts = Arc::new(Mutex::new(0));
+    let mut queue = RunQueue::<&'static str, String>::new(1);
+    let submitter = queue.submitter();
+    let progress = queue.progress_handle();
+
+    let low_starts = starts.clone();
+    queue.push_preemptible(0, move |_, preemption| {
+        *low_starts.lock().unwrap() += 1;
+        submitter.push_with_priority(10, |_| Ok("high"));
+        let started = Instant::now();
+        while !preemption.is_preempted() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "never preempted"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        Ok("low")
+    });
+
+    let results = queue.run();
+    assert!(matches!(results[0], Some(Ok("low"))));
+    assert!(matches!(results[1], Some(Ok("high"))));
+    assert_eq!(*starts.lock().unwrap(), 1);
+
+    let progress = *progress.lock().unwrap();
+    assert_eq!(progress.succeeded, 2);
+    assert_eq!(progress.preempted, 0);
+}
+
+#[test]
+fn a_run_which_cannot_be_preempted_is_left_to_finish() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let mut queue = RunQueue::<&'static str, String>::new(1);
+    let submitter = queue.submitter();
+
+    let low_log = log.clone();
+    queue.push(move |_| {
+        let high_log = low_log.clone();
+        submitter.push_with_priority(10, move |_| {
+            high_log.lock().unwrap().push("high");
+            Ok("high")
+        });
+        std::thread::sleep(Duration::from_millis(20));
+        low_log.lock().unwrap().push("low");
+        Ok("low")
+    });
+
+    let results = queue.run();
+    assert!(matches!(results[0], Some(Ok("low"))));
+    assert!(matches!(results[1], Some(Ok("high"))));
+    assert_eq!(*log.lock().unwrap(), ["low", "high"]);
+}
+
+#[cfg(feature = "writing")]
+#[test]
+fn a_preempted_run_resumes_from_its_checkpoint() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use trellis::solvers::Bisection;
+    use trellis::{Checkpointer, Frequency, GenerateBuilder, State};
+
+    let dir = std::env::temp_dir().join(format!("trellis-preempt-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("low.ckpt");
+
+    let mut queue = RunQueue::<(f64, usize), String>::new(1);
+    let submitter = queue.submitter();
+    let resumed_evaluations = Arc::new(AtomicUsize::new(0));
+
+    let evaluations = resumed_evaluations.clone();
+    queue.push_preemptible(0, move |cancellation, preemption| {
+        let resumes = preemption.resumes();
+        let submitter = submitter.clone();
+        let evaluations = evaluations.clone();
+        let count = AtomicUsize::new(0);
+        let function = move |x: f64| {
+            let n = count.fetch_add(1, Ordering::SeqCst);
+            if resumes == 0 && n == 5 {
+                submitter.push_with_priority(10, |_| Ok((0.0, 0)));
+            }
+            if resumes > 0 {
+                evaluations.fetch_add(1, Ordering::SeqCst);
+            }
+            std::thread::sleep(Duration::from_millis(2));
+            x * x - 2.0
+        };
+        let builder = Bisection::new(function).build_for(());
+        let builder = if resumes == 0 {
+            builder.configure(|state| state.bracket(0.0, 2.0))
+        } else {
+            builder
+                .resume_from_checkpoint(&path)
+                .map_err(|e| e.to_string())?
+        };
+        let state = builder
+            .attach_observer(Checkpointer::new(&path), Frequency::Always)
+            .with_plugin(preemption)
+            .with_controller(cancellation)
+            .finalise()
+            .map_err(|e| e.to_string())?
+            .run()
+            .map_err(|e| e.to_string())?;
+        Ok((state.root().unwrap(), state.current_iteration()))
+    });
+    let progress = queue.progress_handle();
+
+    let results = queue.run();
+    let Some(Ok((root, iterations))) = results[0] else {
+        panic!("the preempted run did not finish");
+    };
+    assert!((root - std::f64::consts::SQRT_2).abs() < 1e-6);
+    assert_eq!(progress.lock().unwrap().preempted, 1);
+    // The resumed run carried on from its checkpoint rather than starting again
+    assert!(resumed_evaluations.load(Ordering::SeqCst) < iterations);
+    std::fs::remove_dir_all(dir).unwrap();
+}
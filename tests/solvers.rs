@@ -0,0 +1,257 @@
+#[cfg(feature = "ndarray")]
+use ndarray::{array, Array1};
+#[cfg(feature = "ndarray")]
+use trellis::solvers::{AndersonAccelerated, FixedPointState, Jacobi, LinearSystem, Sor};
+use trellis::solvers::{Bisection, BracketState, Brent, FixedPoint, RootError};
+use trellis::{ErrorKind, GenerateBuilder, Reason, State};
+
+fn square_minus_two(x: f64) -> f64 {
+    x * x - 2.0
+}
+
+fn assert_found_root_of_two(state: &BracketState) {
+    assert_eq!(state.termination_reason(), Some(Reason::Converged));
+    let root = state.root().expect("a converged run has a root");
+    assert!(
+        (root - std::f64::consts::SQRT_2).abs() < 1e-6,
+        "expected a root at sqrt(2), found {root}"
+    );
+}
+
+#[test]
+fn bisection_finds_a_known_root() {
+    let state = Bisection::new(square_minus_two)
+        .build_for(())
+        .configure(|state| state.bracket(0.0, 2.0))
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_found_root_of_two(&state);
+}
+
+#[test]
+fn brent_finds_a_known_root() {
+    let state = Brent::new(square_minus_two)
+        .build_for(())
+        .configure(|state| state.bracket(0.0, 2.0))
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_found_root_of_two(&state);
+    assert!(
+        state.current_iteration() < 20,
+        "Brent's method should converge quickly, took {} iterations",
+        state.current_iteration()
+    );
+}
+
+#[test]
+fn a_bracket_without_a_sign_change_is_rejected() {
+    let bisection = Bisection::new(square_minus_two)
+        .build_for(())
+        .configure(|state| state.bracket(2.0, 3.0))
+        .finalise()
+        .unwrap()
+        .run();
+    assert!(matches!(
+        bisection.unwrap_err().kind(),
+        ErrorKind::Calculation(RootError::NotBracketed { .. })
+    ));
+
+    let brent = Brent::new(square_minus_two)
+        .build_for(())
+        .configure(|state| state.bracket(2.0, 3.0))
+        .finalise()
+        .unwrap()
+        .run();
+    assert!(matches!(
+        brent.unwrap_err().kind(),
+        ErrorKind::Calculation(RootError::NotBracketed { .. })
+    ));
+}
+
+#[test]
+fn a_missing_bracket_is_rejected() {
+    let result = Brent::new(square_minus_two)
+        .build_for(())
+        .finalise()
+        .unwrap()
+        .run();
+    assert!(matches!(
+        result.unwrap_err().kind(),
+        ErrorKind::Calculation(RootError::MissingBracket)
+    ));
+}
+
+#[test]
+fn brent_continues_from_a_warm_start() {
+    let partial = Brent::new(square_minus_two)
+        .build_for(())
+        .configure(|state| state.bracket(0.0, 2.0).max_iterations(3))
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(
+        partial.termination_reason(),
+        Some(Reason::ExceededMaxIterations)
+    );
+
+    let state = Brent::new(square_minus_two)
+        .build_for(())
+        .warm_start(partial)
+        .unwrap()
+        .configure(|state| state.max_iterations(200))
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_found_root_of_two(&state);
+}
+
+#[test]
+fn bisection_continues_from_a_warm_start() {
+    let partial = Bisection::new(square_minus_two)
+        .build_for(())
+        .configure(|state| state.bracket(0.0, 2.0).max_iterations(5))
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+
+    let state = Bisection::new(square_minus_two)
+        .build_for(())
+        .warm_start(partial)
+        .unwrap()
+        .configure(|state| state.max_iterations(200))
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_found_root_of_two(&state);
+}
+
+#[test]
+fn fixed_point_converges_on_a_contraction() {
+    // cos maps [0, 1] into itself with a derivative below one, so has a unique fixed point there
+    let state = FixedPoint::new(|x: &f64| x.cos())
+        .build_for(())
+        .configure(|state| state.with_param(1.0).tolerance(1e-12))
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(state.termination_reason(), Some(Reason::Converged));
+    let x = *state.param().unwrap();
+    assert!(
+        (x - x.cos()).abs() < 1e-10,
+        "{x} is not a fixed point of cos"
+    );
+}
+
+#[test]
+fn damped_fixed_point_converges_on_a_contraction() {
+    let state = FixedPoint::new(|x: &Vec<f64>| x.iter().map(|x| 0.5 * x + 1.0).collect())
+        .damping(0.5)
+        .build_for(())
+        .configure(|state| state.with_param(vec![0.0, 10.0]).tolerance(1e-12))
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(state.termination_reason(), Some(Reason::Converged));
+    for x in state.param().unwrap() {
+        assert!(
+            (x - 2.0).abs() < 1e-10,
+            "expected a fixed point at 2, found {x}"
+        );
+    }
+}
+
+// A slowly contracting affine map, with its fixed point at (1, 2, 3)
+#[cfg(feature = "ndarray")]
+fn slow_contraction(x: &Array1<f64>) -> Array1<f64> {
+    let rates = array![0.95, 0.9, 0.8];
+    let fixed = array![1.0, 2.0, 3.0];
+    &fixed + &(&rates * &(x - &fixed))
+}
+
+#[cfg(feature = "ndarray")]
+fn assert_converged_to_one_two_three(state: &FixedPointState<Array1<f64>>) {
+    assert_eq!(state.termination_reason(), Some(Reason::Converged));
+    let x = state.param().unwrap();
+    for (found, expected) in x.iter().zip([1.0, 2.0, 3.0]) {
+        assert!(
+            (found - expected).abs() < 1e-8,
+            "expected (1, 2, 3), found {x}"
+        );
+    }
+}
+
+#[cfg(feature = "ndarray")]
+#[test]
+fn anderson_converges_faster_than_plain_fixed_point() {
+    let plain = FixedPoint::new(slow_contraction)
+        .build_for(())
+        .configure(|state| state.with_param(Array1::zeros(3)).tolerance(1e-10))
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_converged_to_one_two_three(&plain);
+
+    let accelerated = AndersonAccelerated::new(FixedPoint::new(slow_contraction))
+        .build_for(())
+        .configure(|state| state.with_param(Array1::zeros(3)).tolerance(1e-10))
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_converged_to_one_two_three(&accelerated);
+
+    assert!(
+        accelerated.current_iteration() * 4 < plain.current_iteration(),
+        "Anderson acceleration took {} iterations, plain iteration {}",
+        accelerated.current_iteration(),
+        plain.current_iteration()
+    );
+}
+
+// A strictly diagonally dominant system, solved by (1, 2, 3)
+#[cfg(feature = "ndarray")]
+fn diagonally_dominant_system() -> LinearSystem {
+    LinearSystem::new(
+        array![[4.0, 1.0, 1.0], [1.0, 5.0, 2.0], [1.0, 2.0, 6.0]],
+        array![9.0, 17.0, 23.0],
+    )
+}
+
+#[cfg(feature = "ndarray")]
+#[test]
+fn jacobi_solves_a_diagonally_dominant_system() {
+    let state = Jacobi
+        .build_for(diagonally_dominant_system())
+        .configure(|state| state.tolerance(1e-10))
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_converged_to_one_two_three(&state);
+}
+
+#[cfg(feature = "ndarray")]
+#[test]
+fn sor_solves_a_diagonally_dominant_system() {
+    for relaxation in [1.0, 1.2] {
+        let state = Sor::new(relaxation)
+            .build_for(diagonally_dominant_system())
+            .configure(|state| state.tolerance(1e-10))
+            .finalise()
+            .unwrap()
+            .run()
+            .unwrap();
+        assert_converged_to_one_two_three(&state);
+    }
+}
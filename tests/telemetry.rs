@@ -0,0 +1,22 @@
+#![cfg(feature = "writing")]
+use trellis::solvers::Bisection;
+use trellis::{GenerateBuilder, Reason, State, Telemetry};
+
+#[test]
+fn telemetry_which_cannot_be_written_does_not_stop_the_run() {
+    let dir = std::env::temp_dir().join(format!("trellis-telemetry-{}", std::process::id()));
+    let telemetry = Telemetry::in_dir(&dir).unwrap();
+    // Every write below the directory now fails
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let state = Bisection::new(|x: f64| x * x - 2.0)
+        .build_for(())
+        .configure(|state| state.bracket(0.0, 2.0))
+        .with_plugin(telemetry)
+        .finalise()
+        .unwrap()
+        .run()
+        .unwrap();
+    assert_eq!(state.termination_reason(), Some(Reason::Converged));
+    assert!(!dir.exists());
+}